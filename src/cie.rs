@@ -0,0 +1,71 @@
+// CIE XYZからsRGBへの変換
+// 白色点の違いはBradford変換で吸収する
+
+pub const D50: [f64; 3] = [0.96422, 1.0, 0.82521];
+pub const D65: [f64; 3] = [0.95047, 1.0, 1.08883];
+
+const BRADFORD: [[f64; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+const BRADFORD_INVERSE: [[f64; 3]; 3] = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+// D65を白色点とするXYZから線形sRGBへの変換
+const XYZ_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+fn mul(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    let mut result = [0.0; 3];
+    for (row, r) in m.iter().zip(result.iter_mut()) {
+        *r = row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+    }
+    result
+}
+
+// white_pointを白色点とするXYZをD65へ順応させる
+fn adapt_to_d65(xyz: [f64; 3], white_point: [f64; 3]) -> [f64; 3] {
+    let source = mul(&BRADFORD, white_point);
+    let destination = mul(&BRADFORD, D65);
+
+    let cone = mul(&BRADFORD, xyz);
+    let mut scaled = [0.0; 3];
+    for i in 0..3 {
+        scaled[i] = if source[i] == 0.0 {
+            cone[i]
+        } else {
+            cone[i] * destination[i] / source[i]
+        };
+    }
+
+    mul(&BRADFORD_INVERSE, scaled)
+}
+
+// white_pointを白色点とするXYZをsRGBの0.0~1.0に変換する
+pub fn xyz_to_srgb(xyz: [f64; 3], white_point: [f64; 3]) -> [f64; 3] {
+    let linear = mul(&XYZ_TO_LINEAR_SRGB, adapt_to_d65(xyz, white_point));
+
+    [
+        srgb_gamma(linear[0]),
+        srgb_gamma(linear[1]),
+        srgb_gamma(linear[2]),
+    ]
+}
+
+pub fn srgb_gamma(linear: f64) -> f64 {
+    let linear = linear.clamp(0.0, 1.0);
+
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::object::PdfObject;
+use crate::parser::{self, Object};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    Filter(filter::Error),
+    Parser(parser::error::Error),
+    InvalidInlineImage,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::Filter(e) => write!(f, "filter: {}", e),
+            Error::Parser(e) => write!(f, "parser: {}", e),
+            Error::InvalidInlineImage => write!(f, "inline image is invalid"),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+impl From<filter::Error> for Error {
+    fn from(e: filter::Error) -> Self {
+        Self::Filter(e)
+    }
+}
+impl From<parser::error::Error> for Error {
+    fn from(e: parser::error::Error) -> Self {
+        Self::Parser(e)
+    }
+}
+
+// コンテンツストリーム中の1つの操作
+// オペランドは演算子の前に置かれる
+// インライン画像 BI ... ID ... EI は演算子をBIとし，画像の辞書とデータの文字列の2つをオペランドとする
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operator: String,
+    pub operands: Vec<Object>,
+}
+
+// /Contentsは1つのストリームかストリームの配列
+// 配列のときストリームの境界はトークンの区切りになるので，間に改行を挟んで連結する
+pub fn decode_contents(
+    contents_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Vec<u8>, Error> {
+    let contents_obj = object::resolve(contents_obj, file, xref)?;
+
+    let streams = match &contents_obj {
+        Object::Array(array) => {
+            let mut streams = vec![];
+            for obj in array {
+                streams.push(object::resolve(obj, file, xref)?);
+            }
+            streams
+        }
+        obj => vec![obj.clone()],
+    };
+
+    let mut content = vec![];
+    for stream in &streams {
+        let stream = object::PdfStreamObj::ensure_stream(stream)?;
+
+        content.append(&mut filter::decode_stream(stream, file, xref)?);
+        content.push(b'\n');
+    }
+
+    Ok(content)
+}
+
+pub fn parse_operations(content: &[u8]) -> Result<Vec<Operation>, Error> {
+    if content.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut parser = parser::Parser::new_content_stream(content, 0)?;
+
+    let mut operations = vec![];
+    let mut operands = vec![];
+    while parser.has_next() {
+        match parser.next_operator() {
+            // BIからIDまでのキーと値はオペランドとして集めておく
+            Some(operator) if operator == "BI" => operands.clear(),
+            Some(operator) if operator == "ID" => {
+                let image_dict = to_inline_image_dict(std::mem::take(&mut operands))?;
+
+                let data = match parser.next_inline_image_data() {
+                    Some(data) => data,
+                    None => return Err(Error::InvalidInlineImage),
+                };
+                match parser.next_operator() {
+                    Some(operator) if operator == "EI" => {}
+                    _ => return Err(Error::InvalidInlineImage),
+                }
+
+                let byte_offset = image_dict.byte_offset();
+                operations.push(Operation {
+                    operator: String::from("BI"),
+                    operands: vec![
+                        Object::Dict(image_dict),
+                        Object::String(object::PdfString::new(data, byte_offset)),
+                    ],
+                });
+            }
+            Some(operator) => operations.push(Operation {
+                operator,
+                operands: std::mem::take(&mut operands),
+            }),
+            None => operands.push(parser.parse()?),
+        }
+    }
+
+    // 末尾に演算子の続かないオペランドが残っていても無視する
+    Ok(operations)
+}
+
+// BIとIDの間に並んだキーと値の組を辞書にする
+fn to_inline_image_dict(operands: Vec<Object>) -> Result<object::PdfDict, Error> {
+    if !operands.len().is_multiple_of(2) {
+        return Err(Error::InvalidInlineImage);
+    }
+
+    let byte_offset = operands.first().map_or(0, |obj| obj.byte_offset());
+
+    let mut dict = HashMap::new();
+    let mut operands = operands.into_iter();
+    while let (Some(key), Some(value)) = (operands.next(), operands.next()) {
+        let key = match key {
+            Object::Name(name) => String::from(name.as_str()),
+            _ => return Err(Error::InvalidInlineImage),
+        };
+
+        dict.insert(key, value);
+    }
+
+    Ok(object::PdfDict::new(dict, byte_offset))
+}
@@ -0,0 +1,82 @@
+use super::*;
+use crate::object::*;
+
+fn operators(operations: &[Operation]) -> Vec<&str> {
+    operations
+        .iter()
+        .map(|operation| operation.operator.as_str())
+        .collect()
+}
+
+#[test]
+fn parse_graphics_operations() {
+    let content = b"q 1 0 0 1 10 20 cm /Im1 Do Q\n0.5 0 0 RG 10 10 100 50 re f*";
+
+    let operations = parse_operations(content).unwrap();
+
+    assert_eq!(
+        operators(&operations),
+        vec!["q", "cm", "Do", "Q", "RG", "re", "f*"]
+    );
+    assert_eq!(operations[1].operands.len(), 6);
+    assert_eq!(
+        operations[2].operands,
+        vec![Object::Name(PdfName::new(String::from("Im1"), 19))]
+    );
+    assert_eq!(
+        operations[4].operands[0],
+        Object::Real(PdfReal::new(0.5, 29))
+    );
+}
+
+#[test]
+fn parse_text_operations() {
+    let content = b"BT /F1 12 Tf 72 700 Td (Hi) Tj [(A) -120 (B)] TJ T* (x) ' 1 2 (y) \" ET";
+
+    let operations = parse_operations(content).unwrap();
+
+    assert_eq!(
+        operators(&operations),
+        vec!["BT", "Tf", "Td", "Tj", "TJ", "T*", "'", "\"", "ET"]
+    );
+    assert_eq!(
+        operations[3].operands,
+        vec![Object::String(PdfString::new(b"Hi".to_vec(), 23))]
+    );
+    assert!(matches!(&operations[4].operands[0], Object::Array(array) if array.len() == 3));
+    assert_eq!(operations[7].operands.len(), 3);
+}
+
+#[test]
+fn parse_operations_with_dict_operand() {
+    let content = b"/Span << /ActualText (abc) >> BDC EMC";
+
+    let operations = parse_operations(content).unwrap();
+
+    assert_eq!(operators(&operations), vec!["BDC", "EMC"]);
+    assert!(matches!(&operations[0].operands[1], Object::Dict(_)));
+}
+
+#[test]
+fn parse_empty_content() {
+    assert!(parse_operations(b"").unwrap().is_empty());
+}
+
+#[test]
+fn parse_inline_image() {
+    let content = b"q BI /W 2 /H 1 /CS /G /BPC 8 ID \x00EI\xff\nEI Q";
+
+    let operations = parse_operations(content).unwrap();
+
+    assert_eq!(operators(&operations), vec!["q", "BI", "Q"]);
+
+    let image_dict = PdfDict::ensure_with_key(&operations[1].operands[0], vec![]).unwrap();
+    assert_eq!(
+        image_dict.get("CS"),
+        Some(&Object::Name(PdfName::new(String::from("G"), 19)))
+    );
+
+    // 空白が続かないEIはデータの一部
+    let data = PdfString::ensure(&operations[1].operands[1]).unwrap();
+    assert_eq!(data.as_bytes(), b"\x00EI\xff");
+}
@@ -1,6 +1,7 @@
 use crate::cross_reference;
 use crate::header;
 use crate::object;
+use crate::page;
 use crate::page_tree;
 use crate::trailer::error as trailer_error;
 
@@ -11,6 +12,7 @@ pub enum Error {
     Trailer(trailer_error::Error),
     Xref(cross_reference::Error),
     PageTree(page_tree::Error),
+    Page(page::Error),
     Object(object::Error),
 }
 
@@ -50,6 +52,12 @@ impl From<page_tree::Error> for Error {
     }
 }
 
+impl From<page::Error> for Error {
+    fn from(e: page::Error) -> Self {
+        Self::Page(e)
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -58,6 +66,7 @@ impl std::fmt::Display for Error {
             Error::Trailer(e) => write!(f, "trailer error: {}", e),
             Error::Xref(e) => write!(f, "cross reference table error: {}", e),
             Error::PageTree(e) => write!(f, "page tree error {}", e),
+            Error::Page(e) => write!(f, "page error: {}", e),
             Error::Object(e) => write!(f, "object: {}", e),
         }
     }
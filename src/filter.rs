@@ -0,0 +1,491 @@
+use flate2::read::ZlibDecoder;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+
+use crate::cross_reference;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    UnsupporttedFilter(String),
+    Decode(std::io::Error),
+    InvalidData(&'static str),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::UnsupporttedFilter(name) => write!(f, "filter `{}` is not supportted", name),
+            Error::Decode(e) => write!(f, "cannot decode stream: {}", e),
+            Error::InvalidData(name) => write!(f, "stream data is invalid for `{}`", name),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+
+pub fn flate_decode(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut deflater = ZlibDecoder::new(bytes);
+
+    let mut decoded = vec![];
+    match deflater.read_to_end(&mut decoded) {
+        Ok(_) => Ok(decoded),
+        Err(e) => Err(Error::Decode(e)),
+    }
+}
+
+fn is_white_space(byte: u8) -> bool {
+    matches!(byte, 0 | 9 | 10 | 12 | 13 | 32)
+}
+
+// 16進数2桁で1バイトを表し，>で終わる
+// 奇数桁で終わったときは最後に0を補う
+pub fn ascii_hex_decode(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = vec![];
+    let mut high: Option<u8> = None;
+
+    for byte in bytes {
+        if *byte == b'>' {
+            break;
+        }
+        if is_white_space(*byte) {
+            continue;
+        }
+
+        let digit = match (*byte as char).to_digit(16) {
+            Some(digit) => digit as u8,
+            None => return Err(Error::InvalidData("ASCIIHexDecode")),
+        };
+
+        high = match high {
+            Some(h) => {
+                decoded.push(h << 4 | digit);
+                None
+            }
+            None => Some(digit),
+        };
+    }
+
+    if let Some(h) = high {
+        decoded.push(h << 4);
+    }
+
+    Ok(decoded)
+}
+
+// 5文字の!~uで4バイトを表し，~>で終わる
+// zは4バイトの0を表す
+pub fn ascii85_decode(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = vec![];
+    let mut group: Vec<u32> = Vec::with_capacity(5);
+
+    for byte in bytes {
+        match byte {
+            b'~' => break,
+            b'z' if group.is_empty() => decoded.extend([0; 4]),
+            b'!'..=b'u' => {
+                group.push((byte - b'!') as u32);
+
+                if group.len() == 5 {
+                    let value = group.iter().fold(0u64, |acc, v| acc * 85 + *v as u64);
+                    if value > u32::MAX as u64 {
+                        return Err(Error::InvalidData("ASCII85Decode"));
+                    }
+
+                    decoded.extend((value as u32).to_be_bytes());
+                    group.clear();
+                }
+            }
+            byte if is_white_space(*byte) => {}
+            _ => return Err(Error::InvalidData("ASCII85Decode")),
+        }
+    }
+
+    // 最後の半端な組はuで埋めてから余分なバイトを捨てる
+    if !group.is_empty() {
+        if group.len() == 1 {
+            return Err(Error::InvalidData("ASCII85Decode"));
+        }
+
+        let n = group.len();
+        group.resize(5, 84);
+
+        let value = group.iter().fold(0u64, |acc, v| acc * 85 + *v as u64);
+        if value > u32::MAX as u64 {
+            return Err(Error::InvalidData("ASCII85Decode"));
+        }
+
+        decoded.extend(&(value as u32).to_be_bytes()[..n - 1]);
+    }
+
+    Ok(decoded)
+}
+
+// 長さのバイトが0~127なら続く長さ+1バイトをそのまま，129~255なら次の1バイトを257-長さ回繰り返す
+// 128で終わる
+pub fn run_length_decode(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let length = bytes[i] as usize;
+        i += 1;
+
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = i + length + 1;
+                if bytes.len() < end {
+                    return Err(Error::InvalidData("RunLengthDecode"));
+                }
+
+                decoded.extend(&bytes[i..end]);
+                i = end;
+            }
+            _ => {
+                let byte = match bytes.get(i) {
+                    Some(byte) => *byte,
+                    None => return Err(Error::InvalidData("RunLengthDecode")),
+                };
+
+                decoded.extend(std::iter::repeat_n(byte, 257 - length));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(decoded)
+}
+
+// 9~12ビットの可変長符号
+// 256は辞書の初期化，257は終わりを表す
+// early_changeなら（/EarlyChangeが既定値の1なら）符号長を1つ早く伸ばす
+pub fn lzw_decode(bytes: &[u8], early_change: bool) -> Result<Vec<u8>, Error> {
+    const CLEAR_TABLE: usize = 256;
+    const END_OF_DATA: usize = 257;
+
+    let mut decoded = vec![];
+    let mut table: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+    table.push(vec![]);
+    table.push(vec![]);
+
+    let mut code_length = 9;
+    let mut previous: Option<usize> = None;
+
+    let mut buffer: u32 = 0;
+    let mut buffered_bits = 0;
+
+    for byte in bytes {
+        buffer = buffer << 8 | *byte as u32;
+        buffered_bits += 8;
+
+        while buffered_bits >= code_length {
+            let code =
+                ((buffer >> (buffered_bits - code_length)) & ((1 << code_length) - 1)) as usize;
+            buffered_bits -= code_length;
+
+            if code == CLEAR_TABLE {
+                table.truncate(258);
+                code_length = 9;
+                previous = None;
+                continue;
+            }
+            if code == END_OF_DATA {
+                return Ok(decoded);
+            }
+
+            let entry = match previous {
+                None => match table.get(code) {
+                    Some(entry) => entry.clone(),
+                    None => return Err(Error::InvalidData("LZWDecode")),
+                },
+                Some(previous) => {
+                    let entry = if code < table.len() {
+                        table[code].clone()
+                    } else if code == table.len() {
+                        // 登録しようとしている符号が現れたときは前の列に自身の先頭を足したもの
+                        let mut entry = table[previous].clone();
+                        entry.push(table[previous][0]);
+                        entry
+                    } else {
+                        return Err(Error::InvalidData("LZWDecode"));
+                    };
+
+                    let mut new_entry = table[previous].clone();
+                    new_entry.push(entry[0]);
+                    table.push(new_entry);
+
+                    entry
+                }
+            };
+
+            decoded.extend(&entry);
+            previous = Some(code);
+
+            if table.len() + early_change as usize >= (1 << code_length) && code_length < 12 {
+                code_length += 1;
+            }
+        }
+    }
+
+    Ok(decoded)
+}
+
+// /DecodeParmsの整数の値．省略されていればdefault
+fn get_parm(
+    decode_parms: Option<&object::PdfDict>,
+    key: &str,
+    default: isize,
+) -> Result<isize, Error> {
+    match decode_parms.and_then(|dict| dict.get(key)) {
+        Some(obj) => Ok(object::PdfInteger::ensure(obj)?.unpack()),
+        None => Ok(default),
+    }
+}
+
+// /Predictorで差分として符号化された行を元に戻す
+// 2はTIFFの予測，10以上はPNGの予測で，PNGでは行ごとに先頭1バイトで予測の種類を指定する
+fn apply_predictor(
+    bytes: Vec<u8>,
+    decode_parms: Option<&object::PdfDict>,
+) -> Result<Vec<u8>, Error> {
+    let predictor = get_parm(decode_parms, "Predictor", 1)?;
+    if predictor == 1 {
+        return Ok(bytes);
+    }
+
+    let colors = get_parm(decode_parms, "Colors", 1)?;
+    let bits_per_component = get_parm(decode_parms, "BitsPerComponent", 8)?;
+    let columns = get_parm(decode_parms, "Columns", 1)?;
+    if colors < 1 || columns < 1 || !matches!(bits_per_component, 1 | 2 | 4 | 8 | 16) {
+        return Err(Error::InvalidData("Predictor"));
+    }
+    let (colors, columns, bits_per_component) = (
+        colors as usize,
+        columns as usize,
+        bits_per_component as usize,
+    );
+
+    // 1行のバイト数と，左隣の画素までのバイト数
+    let pixel_bits = colors
+        .checked_mul(bits_per_component)
+        .ok_or(Error::InvalidData("Predictor"))?;
+    let row_length = pixel_bits
+        .checked_mul(columns)
+        .map(|bits| bits.div_ceil(8))
+        .ok_or(Error::InvalidData("Predictor"))?;
+    let pixel_length = pixel_bits.div_ceil(8);
+
+    match predictor {
+        2 => Ok(tiff_predictor(
+            bytes,
+            row_length,
+            colors,
+            colors * columns,
+            bits_per_component,
+        )),
+        10..=15 => png_predictor(&bytes, row_length, pixel_length),
+        _ => Err(Error::InvalidData("Predictor")),
+    }
+}
+
+// 各成分から同じ行の左隣の画素の同じ成分を引いた値が並んでいる
+fn tiff_predictor(
+    mut bytes: Vec<u8>,
+    row_length: usize,
+    colors: usize,
+    n_samples: usize,
+    bits_per_component: usize,
+) -> Vec<u8> {
+    for row in bytes.chunks_mut(row_length) {
+        match bits_per_component {
+            8 => {
+                for i in colors..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - colors]);
+                }
+            }
+            16 => {
+                for i in (2 * colors..row.len() - row.len() % 2).step_by(2) {
+                    let left = u16::from_be_bytes([row[i - 2 * colors], row[i - 2 * colors + 1]]);
+                    let value = u16::from_be_bytes([row[i], row[i + 1]]).wrapping_add(left);
+                    row[i..i + 2].copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            // 1バイトに複数の成分が詰まっている．行末の詰め物のビットはそのままにする
+            bits => {
+                let mask = (1u8 << bits) - 1;
+                let get = |row: &[u8], j: usize| {
+                    let bit = j * bits;
+                    (row[bit / 8] >> (8 - bits - bit % 8)) & mask
+                };
+
+                for j in colors..n_samples.min(row.len() * 8 / bits) {
+                    let value = get(row, j).wrapping_add(get(row, j - colors)) & mask;
+                    let bit = j * bits;
+                    let shift = 8 - bits - bit % 8;
+                    row[bit / 8] = row[bit / 8] & !(mask << shift) | value << shift;
+                }
+            }
+        }
+    }
+
+    bytes
+}
+
+// 各行の先頭の1バイトが予測の種類で，0なら無し，1は左，2は上，3は左と上の平均，4はPaeth
+// 予測に使うのは画素単位ではなくバイト単位の値
+fn png_predictor(bytes: &[u8], row_length: usize, pixel_length: usize) -> Result<Vec<u8>, Error> {
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    // 壊れた/Columnsで巨大な行を確保しないよう，データより長い分は取らない
+    let mut previous = vec![0u8; row_length.min(bytes.len())];
+
+    for chunk in bytes.chunks(row_length + 1) {
+        let (predictor, row) = match chunk.split_first() {
+            Some(split) => split,
+            None => break,
+        };
+
+        let mut current = row.to_vec();
+        for i in 0..current.len() {
+            let left = if i >= pixel_length {
+                current[i - pixel_length]
+            } else {
+                0
+            };
+            let up = previous[i];
+            let up_left = if i >= pixel_length {
+                previous[i - pixel_length]
+            } else {
+                0
+            };
+
+            let prediction = match predictor {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(Error::InvalidData("Predictor")),
+            };
+            current[i] = current[i].wrapping_add(prediction);
+        }
+
+        decoded.extend(&current);
+        previous[..current.len()].copy_from_slice(&current);
+    }
+
+    Ok(decoded)
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let p = left as i16 + up as i16 - up_left as i16;
+    let (pa, pb, pc) = (
+        (p - left as i16).abs(),
+        (p - up as i16).abs(),
+        (p - up_left as i16).abs(),
+    );
+
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+// ストリームの/Filterに従ってストリームのバイト列をデコードする
+pub fn decode_stream(
+    stream_obj: &object::PdfStreamObj,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Vec<u8>, Error> {
+    let bytes = stream_obj.get_stream(file, xref)?;
+    let filters = get_filter_names(&stream_obj.dict)?;
+    let decode_parms = get_decode_parms(&stream_obj.dict, filters.len(), file, xref)?;
+
+    apply_filters(bytes, &filters, &decode_parms)
+}
+
+// フィルタの名前の列を，それぞれの/DecodeParmsとともに順に適用する
+pub fn apply_filters(
+    mut bytes: Vec<u8>,
+    filters: &[String],
+    decode_parms: &[Option<object::PdfDict>],
+) -> Result<Vec<u8>, Error> {
+    for (i, filter) in filters.iter().enumerate() {
+        let decode_parms = decode_parms.get(i).and_then(|dict| dict.as_ref());
+
+        bytes = match filter.as_str() {
+            "FlateDecode" => apply_predictor(flate_decode(&bytes)?, decode_parms)?,
+            "ASCIIHexDecode" => ascii_hex_decode(&bytes)?,
+            "ASCII85Decode" => ascii85_decode(&bytes)?,
+            "LZWDecode" => {
+                let early_change = get_parm(decode_parms, "EarlyChange", 1)? != 0;
+                apply_predictor(lzw_decode(&bytes, early_change)?, decode_parms)?
+            }
+            "RunLengthDecode" => run_length_decode(&bytes)?,
+            _ => return Err(Error::UnsupporttedFilter(filter.clone())),
+        };
+    }
+
+    Ok(bytes)
+}
+
+// /DecodeParmsは/Filterが配列ならそれと同じ長さの配列になる
+// フィルタごとの辞書を返し，省略されたものやnullはNoneにする
+pub fn get_decode_parms(
+    stream_dict: &object::PdfDict,
+    n_filters: usize,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Vec<Option<object::PdfDict>>, Error> {
+    let mut decode_parms = vec![];
+
+    if let Some(obj) = stream_dict.get("DecodeParms") {
+        let items = match object::resolve(obj, file, xref)? {
+            Object::Array(array) => array.into_iter().cloned().collect(),
+            obj => vec![obj],
+        };
+
+        for item in items.iter().take(n_filters) {
+            decode_parms.push(match object::resolve(item, file, xref)? {
+                Object::Dict(dict) => Some(dict),
+                _ => None,
+            });
+        }
+    }
+
+    decode_parms.resize(n_filters, None);
+    Ok(decode_parms)
+}
+
+// /Filterは名前単体か名前の配列で指定される
+pub fn get_filter_names(stream_dict: &object::PdfDict) -> Result<Vec<String>, Error> {
+    let filter_obj = match stream_dict.get("Filter") {
+        Some(obj) => obj,
+        None => return Ok(vec![]),
+    };
+
+    if let Ok(array_obj) = object::PdfArray::ensure(filter_obj) {
+        let mut names = vec![];
+        for name in array_obj {
+            names.push(String::from(object::PdfName::ensure(name)?.as_str()));
+        }
+
+        Ok(names)
+    } else {
+        Ok(vec![String::from(
+            object::PdfName::ensure(filter_obj)?.as_str(),
+        )])
+    }
+}
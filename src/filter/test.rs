@@ -0,0 +1,165 @@
+use super::*;
+use crate::test_util;
+
+#[test]
+fn ascii_hex_decode_odd_digits() {
+    assert_eq!(
+        ascii_hex_decode(b"61 62\n6>").unwrap(),
+        vec![0x61, 0x62, 0x60]
+    );
+}
+
+#[test]
+fn ascii_hex_decode_invalid() {
+    assert!(matches!(
+        ascii_hex_decode(b"6x>"),
+        Err(Error::InvalidData(_))
+    ));
+}
+
+#[test]
+fn ascii85_decode_with_partial_group() {
+    assert_eq!(
+        ascii85_decode(b"9jqo^F*2M7/c~>").unwrap(),
+        b"Man sure.".to_vec()
+    );
+    assert_eq!(ascii85_decode(b"/c~>").unwrap(), b".".to_vec());
+    assert_eq!(ascii85_decode(b"z~>").unwrap(), vec![0; 4]);
+}
+
+#[test]
+fn run_length_decode_literal_and_repeat() {
+    let encoded = [2, b'a', b'b', b'c', 254, b'z', 128];
+
+    assert_eq!(run_length_decode(&encoded).unwrap(), b"abczzz".to_vec());
+}
+
+#[test]
+fn lzw_decode_example() {
+    // PDFの仕様書にある例
+    let encoded = [0x80, 0x0b, 0x60, 0x50, 0x22, 0x0c, 0x0c, 0x85, 0x01];
+
+    assert_eq!(
+        lzw_decode(&encoded, true).unwrap(),
+        vec![0x2d, 0x2d, 0x2d, 0x2d, 0x2d, 0x41, 0x2d, 0x2d, 0x2d, 0x42]
+    );
+}
+
+#[test]
+fn lzw_decode_late_change() {
+    // 符号長が伸びる所を越えるよう，辞書に300以上の列が登録されるデータ
+    let data: Vec<u8> = (0..2000u32).map(|i| (i * i / 7) as u8).collect();
+
+    assert_eq!(lzw_decode(&lzw_encode(&data, true), true).unwrap(), data);
+    assert_eq!(lzw_decode(&lzw_encode(&data, false), false).unwrap(), data);
+    assert_ne!(lzw_decode(&lzw_encode(&data, false), true).ok(), Some(data));
+}
+
+// 試験用のLZWの符号化
+fn lzw_encode(data: &[u8], early_change: bool) -> Vec<u8> {
+    let mut table: std::collections::HashMap<Vec<u8>, u32> =
+        (0..=255u8).map(|b| (vec![b], b as u32)).collect();
+    let mut next_code = 258;
+    let mut code_length = 9;
+
+    let mut codes = vec![(256, code_length)];
+    let mut current: Vec<u8> = vec![];
+    for byte in data {
+        let mut extended = current.clone();
+        extended.push(*byte);
+
+        if table.contains_key(&extended) {
+            current = extended;
+            continue;
+        }
+
+        codes.push((table[&current], code_length));
+        table.insert(extended, next_code);
+        next_code += 1;
+        if next_code + early_change as u32 > (1 << code_length) && code_length < 12 {
+            code_length += 1;
+        }
+        current = vec![*byte];
+    }
+    codes.push((table[&current], code_length));
+    codes.push((257, code_length));
+
+    let mut encoded = vec![];
+    let mut buffer: u64 = 0;
+    let mut buffered_bits = 0;
+    for (code, length) in codes {
+        buffer = buffer << length | code as u64;
+        buffered_bits += length;
+        while buffered_bits >= 8 {
+            encoded.push((buffer >> (buffered_bits - 8)) as u8);
+            buffered_bits -= 8;
+        }
+    }
+    if buffered_bits > 0 {
+        encoded.push((buffer << (8 - buffered_bits)) as u8);
+    }
+
+    encoded
+}
+
+fn decode_with_parms(data: &[u8], decode_parms: &str) -> Vec<u8> {
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, data).unwrap();
+    let (mut file, xref) = test_util::write_pdf(&[test_util::stream(
+        &format!("/Filter [/FlateDecode] /DecodeParms [{}]", decode_parms),
+        &encoder.finish().unwrap(),
+    )]);
+
+    let stream_obj = object::resolve(&test_util::parse("1 0 R"), &mut file, &xref).unwrap();
+    decode_stream(
+        object::PdfStreamObj::ensure_stream(&stream_obj).unwrap(),
+        &mut file,
+        &xref,
+    )
+    .unwrap()
+}
+
+#[test]
+fn png_predictor() {
+    // 行ごとに Sub, Up, None, Average, Paeth の予測を使う
+    let data = [
+        1, 10, 10, 10, //
+        2, 5, 5, 10, //
+        0, 1, 2, 3, //
+        3, 4, 5, 5, //
+        4, 2, 1, 253,
+    ];
+
+    assert_eq!(
+        decode_with_parms(&data, "<< /Predictor 12 /Columns 3 >>"),
+        vec![10, 20, 30, 15, 25, 40, 1, 2, 3, 4, 8, 10, 6, 9, 7]
+    );
+}
+
+#[test]
+fn tiff_predictor() {
+    // 8ビット2色
+    assert_eq!(
+        decode_with_parms(
+            &[10, 20, 5, 254, 0, 255, 1, 1],
+            "<< /Predictor 2 /Colors 2 /Columns 2 >>"
+        ),
+        vec![10, 20, 15, 18, 0, 255, 1, 0]
+    );
+    // 16ビット
+    assert_eq!(
+        decode_with_parms(
+            &[0x01, 0x02, 0x01, 0xfe],
+            "<< /Predictor 2 /BitsPerComponent 16 /Columns 2 >>"
+        ),
+        vec![0x01, 0x02, 0x03, 0x00]
+    );
+    // 4ビットで，行末の詰め物は変えない
+    assert_eq!(
+        decode_with_parms(
+            &[0x32, 0xf0],
+            "<< /Predictor 2 /BitsPerComponent 4 /Columns 3 >>"
+        ),
+        vec![0x35, 0x40]
+    );
+}
@@ -0,0 +1,499 @@
+use std::fmt;
+use std::fs::File;
+
+use crate::content;
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::parser::Object;
+
+mod afm;
+mod cid;
+mod cmap;
+mod composite;
+mod embedded;
+mod encoding;
+mod glyph_list;
+mod info;
+
+#[cfg(test)]
+mod test;
+
+pub use cmap::CMap;
+use composite::CompositeFont;
+pub use composite::VerticalMetric;
+pub use embedded::{extract_embedded_fonts, EmbeddedFont, FontFileType};
+pub use encoding::{BaseEncoding, SimpleEncoding};
+pub use info::FontInfo;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    Filter(filter::Error),
+    Content(content::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::Filter(e) => write!(f, "filter: {}", e),
+            Error::Content(e) => write!(f, "content: {}", e),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+impl From<filter::Error> for Error {
+    fn from(e: filter::Error) -> Self {
+        Self::Filter(e)
+    }
+}
+impl From<content::Error> for Error {
+    fn from(e: content::Error) -> Self {
+        Self::Content(e)
+    }
+}
+
+// /Widthsも/MissingWidthも無いときの字幅（1000分の1単位）
+const DEFAULT_WIDTH: f64 = 500.0;
+// /FontDescriptorからも/FontBBoxからも分からないときのベースラインから上端と下端まで
+const DEFAULT_ASCENT: f64 = 800.0;
+const DEFAULT_DESCENT: f64 = -200.0;
+
+// 文字列を描くときの1文字分
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    pub code: u32,
+    // 対応するUnicodeの文字列．分からないときは空
+    pub text: String,
+    // テキスト空間での字幅（フォントの大きさが1のとき）
+    pub width: f64,
+    // 1バイトの文字コード32にはTwの語間が加わる
+    pub is_space: bool,
+    // 縦書きのフォントでの字送り（テキスト空間の単位）
+    pub vertical: Option<VerticalMetric>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Font {
+    // /BaseFont．サブセットの接頭辞も含む
+    base_font: String,
+    kind: FontKind,
+    // /ToUnicodeがあれば符号化より優先する
+    to_unicode: Option<CMap>,
+}
+
+#[derive(Debug, Clone)]
+enum FontKind {
+    Simple(SimpleFont),
+    Composite(CompositeFont),
+}
+
+// 単純フォント（Type1，TrueType，Type3）
+#[derive(Debug, Clone)]
+struct SimpleFont {
+    first_char: u32,
+    widths: Vec<f64>,
+    missing_width: f64,
+    // グリフ空間からテキスト空間への横方向の倍率
+    // Type3以外は1000分の1
+    width_scale: f64,
+    encoding: SimpleEncoding,
+    // ベースラインから上端と下端まで（グリフ空間の単位）
+    extent: Option<(f64, f64)>,
+}
+
+// フォントが見つからないときに使う
+impl Default for Font {
+    fn default() -> Self {
+        Font {
+            base_font: String::new(),
+            kind: FontKind::Simple(SimpleFont {
+                first_char: 0,
+                widths: vec![],
+                missing_width: DEFAULT_WIDTH,
+                width_scale: 0.001,
+                encoding: SimpleEncoding::new(BaseEncoding::Standard),
+                extent: None,
+            }),
+            to_unicode: None,
+        }
+    }
+}
+
+impl Font {
+    pub fn new(
+        font_dict: &object::PdfDict,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<Font, Error> {
+        let kind = match font_dict.get("Subtype") {
+            Some(Object::Name(subtype)) if subtype.as_str() == "Type0" => {
+                FontKind::Composite(CompositeFont::new(font_dict, file, xref)?)
+            }
+            _ => FontKind::Simple(SimpleFont::new(font_dict, file, xref)?),
+        };
+
+        let base_font = match font_dict.get("BaseFont") {
+            Some(obj) => String::from(object::PdfName::ensure(obj)?.as_str()),
+            None => String::new(),
+        };
+
+        Ok(Font {
+            base_font,
+            kind,
+            to_unicode: parse_to_unicode(font_dict, file, xref),
+        })
+    }
+
+    pub fn base_font(&self) -> &str {
+        &self.base_font
+    }
+
+    // ベースラインから字の上端と下端まで（テキスト空間の単位）
+    pub fn extent(&self) -> (f64, f64) {
+        let (extent, scale) = match &self.kind {
+            FontKind::Simple(font) => (font.extent, font.width_scale),
+            FontKind::Composite(font) => (font.extent, 0.001),
+        };
+        let (ascent, descent) = extent.unwrap_or((DEFAULT_ASCENT, DEFAULT_DESCENT));
+
+        (ascent * scale, descent * scale)
+    }
+
+    // 縦書きのCMapを使っているType0フォントならtrue
+    pub fn is_vertical(&self) -> bool {
+        matches!(&self.kind, FontKind::Composite(font) if font.is_vertical)
+    }
+
+    // 文字列のバイト列を1文字ずつに分ける
+    pub fn decode(&self, bytes: &[u8]) -> Vec<Glyph> {
+        match &self.kind {
+            FontKind::Simple(font) => bytes
+                .iter()
+                .map(|byte| {
+                    let code = *byte as u32;
+                    let text = match self.lookup_to_unicode(&[*byte]) {
+                        Some(text) => text,
+                        None => font.encoding.to_unicode(*byte).unwrap_or_default(),
+                    };
+
+                    Glyph {
+                        code,
+                        text,
+                        width: font.glyph_width(code) * font.width_scale,
+                        is_space: code == 32,
+                        vertical: None,
+                    }
+                })
+                .collect(),
+            FontKind::Composite(font) => {
+                let mut glyphs = vec![];
+
+                let mut i = 0;
+                while i < bytes.len() {
+                    let cid_code = font.encoding.next_code(&bytes[i..]);
+                    let code_bytes = &bytes[i..i + cid_code.length];
+
+                    // /ToUnicode，Unicodeの文字コード，文字集合のCIDの順に探す
+                    let text = match self.lookup_to_unicode(code_bytes) {
+                        Some(text) => text,
+                        None => cid_code
+                            .unicode
+                            .or_else(|| cid_code.cid.and_then(|cid| font.cid_to_unicode(cid)))
+                            .map(String::from)
+                            .unwrap_or_default(),
+                    };
+
+                    let width = font.width(cid_code.cid);
+                    let vertical = if font.is_vertical {
+                        let metric = font.vertical_metric(cid_code.cid);
+                        Some(VerticalMetric {
+                            advance: metric.advance * 0.001,
+                            origin_x: Some(metric.origin_x.unwrap_or(width / 2.0) * 0.001),
+                            origin_y: metric.origin_y * 0.001,
+                        })
+                    } else {
+                        None
+                    };
+
+                    glyphs.push(Glyph {
+                        code: cmap::bytes_to_u32(code_bytes),
+                        text,
+                        width: width * 0.001,
+                        is_space: code_bytes == [32],
+                        vertical,
+                    });
+
+                    i += cid_code.length;
+                }
+
+                glyphs
+            }
+        }
+    }
+
+    fn lookup_to_unicode(&self, code: &[u8]) -> Option<String> {
+        self.to_unicode
+            .as_ref()
+            .and_then(|cmap| cmap.to_unicode(code))
+            .map(String::from)
+    }
+}
+
+impl SimpleFont {
+    fn new(
+        font_dict: &object::PdfDict,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<SimpleFont, Error> {
+        let is_type3 = match font_dict.get("Subtype") {
+            Some(obj) => object::PdfName::ensure(obj)?.as_str() == "Type3",
+            None => false,
+        };
+
+        let base_font = match font_dict.get("BaseFont") {
+            Some(obj) => String::from(object::PdfName::ensure(obj)?.as_str()),
+            None => String::new(),
+        };
+        let encoding = parse_encoding(
+            font_dict.get("Encoding"),
+            BaseEncoding::builtin(strip_subset_prefix(&base_font)),
+            file,
+            xref,
+        )?;
+
+        let first_char = match font_dict.get("FirstChar") {
+            Some(obj) => object::PdfInteger::ensure(&object::resolve(obj, file, xref)?)?.unpack(),
+            None => 0,
+        };
+
+        let widths = match font_dict.get("Widths") {
+            Some(obj) => {
+                let widths = object::resolve(obj, file, xref)?;
+                let mut width_vec = vec![];
+                for width in object::PdfArray::ensure(&widths)? {
+                    width_vec.push(object::PdfReal::ensure_number(&object::resolve(
+                        width, file, xref,
+                    )?)?);
+                }
+                width_vec
+            }
+            None => vec![],
+        };
+
+        let missing_width = match font_dict.get("FontDescriptor") {
+            Some(obj) => {
+                let descriptor = object::resolve(obj, file, xref)?;
+                let descriptor = object::PdfDict::ensure_with_key(&descriptor, vec![])?;
+                match descriptor.get("MissingWidth") {
+                    Some(obj) => object::PdfReal::ensure_number(obj)?,
+                    None if widths.is_empty() => DEFAULT_WIDTH,
+                    None => 0.0,
+                }
+            }
+            None => DEFAULT_WIDTH,
+        };
+
+        // 標準14フォントは/Widthsが無ければAFMの字幅を使う
+        let metrics = if is_type3 {
+            None
+        } else {
+            afm::standard_font_metrics(strip_subset_prefix(&base_font))
+        };
+        let (first_char, widths) = match metrics {
+            Some(metrics) if widths.is_empty() => (
+                0,
+                (0..=255)
+                    .map(|code| {
+                        encoding
+                            .glyph_name(code)
+                            .and_then(|name| metrics.width(name))
+                            .unwrap_or(missing_width)
+                    })
+                    .collect(),
+            ),
+            _ => (first_char, widths),
+        };
+
+        // Type3は/FontMatrixでグリフ空間を決める
+        let width_scale = match font_dict.get("FontMatrix") {
+            Some(obj) if is_type3 => {
+                let matrix = object::resolve(obj, file, xref)?;
+                match object::PdfArray::ensure(&matrix)?.get(0) {
+                    Some(scale) => object::PdfReal::ensure_number(scale)?,
+                    None => 0.001,
+                }
+            }
+            _ => 0.001,
+        };
+
+        Ok(SimpleFont {
+            first_char: first_char.max(0) as u32,
+            widths,
+            missing_width,
+            width_scale,
+            encoding,
+            extent: parse_extent(font_dict, file, xref)?
+                .or(metrics.map(|metrics| metrics.extent())),
+        })
+    }
+
+    // 字幅（グリフ空間の単位）
+    fn glyph_width(&self, code: u32) -> f64 {
+        match code.checked_sub(self.first_char) {
+            Some(i) => match self.widths.get(i as usize) {
+                Some(width) => *width,
+                None => self.missing_width,
+            },
+            None => self.missing_width,
+        }
+    }
+}
+
+// /Encodingは符号化の名前か，/BaseEncodingと/Differencesを持つ辞書
+// 省略されたときや/BaseEncodingが無いときはフォントの組み込みの符号化を使う
+fn parse_encoding(
+    encoding_obj: Option<&Object>,
+    builtin: BaseEncoding,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<SimpleEncoding, Error> {
+    let encoding_obj = match encoding_obj {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(SimpleEncoding::new(builtin)),
+    };
+
+    match &encoding_obj {
+        Object::Name(name) => Ok(SimpleEncoding::new(
+            BaseEncoding::from_name(name.as_str()).unwrap_or(builtin),
+        )),
+        Object::Dict(dict) => {
+            let base = match dict.get("BaseEncoding") {
+                Some(obj) => BaseEncoding::from_name(object::PdfName::ensure(obj)?.as_str())
+                    .unwrap_or(builtin),
+                None => builtin,
+            };
+
+            let mut encoding = SimpleEncoding::new(base);
+            if let Some(obj) = dict.get("Differences") {
+                let differences = object::resolve(obj, file, xref)?;
+                encoding.apply_differences(object::PdfArray::ensure(&differences)?)?;
+            }
+
+            Ok(encoding)
+        }
+        _ => Ok(SimpleEncoding::new(builtin)),
+    }
+}
+
+// 字の高さは/FontDescriptorの/Ascentと/Descent，それが無ければ/FontBBoxから求める
+// Type3では/FontBBoxがフォント辞書にある
+fn parse_extent(
+    font_dict: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<(f64, f64)>, Error> {
+    let descriptor = match font_dict.get("FontDescriptor") {
+        Some(obj) => Some(object::resolve(obj, file, xref)?),
+        None => None,
+    };
+    let descriptor = match &descriptor {
+        Some(obj) => Some(object::PdfDict::ensure_with_key(obj, vec![])?),
+        None => None,
+    };
+
+    if let Some(descriptor) = descriptor {
+        let ascent = match descriptor.get("Ascent") {
+            Some(obj) => object::PdfReal::ensure_number(&object::resolve(obj, file, xref)?)?,
+            None => 0.0,
+        };
+        let descent = match descriptor.get("Descent") {
+            Some(obj) => object::PdfReal::ensure_number(&object::resolve(obj, file, xref)?)?,
+            None => 0.0,
+        };
+        // 0になっているフォントも多いので，そのときは/FontBBoxを見る
+        if ascent != 0.0 || descent != 0.0 {
+            return Ok(Some((ascent, descent)));
+        }
+    }
+
+    let bbox = match descriptor.and_then(|d| d.get("FontBBox")) {
+        Some(obj) => Some(obj),
+        None => font_dict.get("FontBBox"),
+    };
+    let bbox = match bbox {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(None),
+    };
+    let bbox = object::PdfArray::ensure(&bbox)?;
+    match (bbox.get(1), bbox.get(3)) {
+        (Some(lower), Some(upper)) => {
+            let lower = object::PdfReal::ensure_number(lower)?;
+            let upper = object::PdfReal::ensure_number(upper)?;
+            if lower == upper {
+                Ok(None)
+            } else {
+                Ok(Some((upper.max(lower), upper.min(lower))))
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+// /FontDescriptorの/CapHeight．/Ascentと同じく0なら分からないものとする
+fn parse_cap_height(
+    font_dict: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<f64>, Error> {
+    let descriptor = match font_dict.get("FontDescriptor") {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(None),
+    };
+    let descriptor = object::PdfDict::ensure_with_key(&descriptor, vec![])?;
+
+    let cap_height = match descriptor.get("CapHeight") {
+        Some(obj) => object::PdfReal::ensure_number(&object::resolve(obj, file, xref)?)?,
+        None => return Ok(None),
+    };
+    if cap_height == 0.0 {
+        Ok(None)
+    } else {
+        Ok(Some(cap_height))
+    }
+}
+
+// /ToUnicodeはCMapのストリーム
+// /Identity-Hなどの名前が指定されていることもあるが，それは無視する
+// 壊れた/ToUnicodeは無いものとして扱い，符号化から文字を求める
+fn parse_to_unicode(
+    font_dict: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Option<CMap> {
+    let to_unicode = object::resolve(font_dict.get("ToUnicode")?, file, xref).ok()?;
+
+    match &to_unicode {
+        Object::StreamObj(stream) => {
+            let content = filter::decode_stream(stream, file, xref).ok()?;
+            CMap::parse(&content).ok()
+        }
+        _ => None,
+    }
+}
+
+// サブセットフォントの名前 "ABCDEF+Helvetica" から "ABCDEF+" を取り除く
+pub fn strip_subset_prefix(base_font: &str) -> &str {
+    match base_font.split_once('+') {
+        Some((prefix, name))
+            if prefix.len() == 6 && prefix.bytes().all(|b| b.is_ascii_uppercase()) =>
+        {
+            name
+        }
+        _ => base_font,
+    }
+}
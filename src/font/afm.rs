@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// 標準14フォントのAFMファイル
+// 字幅は符号化されていないグリフも含めて全て持つ
+const AFM_FILES: &[&str] = &[
+    include_str!("afm/Courier.afm"),
+    include_str!("afm/Courier-Bold.afm"),
+    include_str!("afm/Courier-Oblique.afm"),
+    include_str!("afm/Courier-BoldOblique.afm"),
+    include_str!("afm/Helvetica.afm"),
+    include_str!("afm/Helvetica-Bold.afm"),
+    include_str!("afm/Helvetica-Oblique.afm"),
+    include_str!("afm/Helvetica-BoldOblique.afm"),
+    include_str!("afm/Times-Roman.afm"),
+    include_str!("afm/Times-Bold.afm"),
+    include_str!("afm/Times-Italic.afm"),
+    include_str!("afm/Times-BoldItalic.afm"),
+    include_str!("afm/Symbol.afm"),
+    include_str!("afm/ZapfDingbats.afm"),
+];
+
+// AFMから読んだフォントの寸法（1000分の1単位）
+// SymbolとZapfDingbatsにはAscender，DescenderとCapHeightが無い
+#[derive(Debug)]
+pub struct FontMetrics {
+    pub ascent: Option<f64>,
+    pub descent: Option<f64>,
+    pub cap_height: Option<f64>,
+    // FontBBox [x_min y_min x_max y_max]
+    pub bbox: [f64; 4],
+    widths: HashMap<&'static str, f64>,
+}
+
+impl FontMetrics {
+    pub fn width(&self, glyph_name: &str) -> Option<f64> {
+        self.widths.get(glyph_name).copied()
+    }
+
+    // ベースラインから字の上端と下端まで
+    // Ascender，Descenderが無いフォントはFontBBoxの上端と下端
+    pub fn extent(&self) -> (f64, f64) {
+        (
+            self.ascent.unwrap_or(self.bbox[3]),
+            self.descent.unwrap_or(self.bbox[1]),
+        )
+    }
+}
+
+fn parse_afm(afm: &'static str) -> (&'static str, FontMetrics) {
+    let mut font_name = "";
+    let mut bbox = [0.0; 4];
+    let mut ascent = None;
+    let mut descent = None;
+    let mut cap_height = None;
+    let mut widths = HashMap::new();
+
+    for line in afm.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "FontName" => font_name = value,
+            "FontBBox" => {
+                for (i, v) in value.split_whitespace().take(4).enumerate() {
+                    bbox[i] = v.parse().unwrap_or(0.0);
+                }
+            }
+            "Ascender" => ascent = value.parse().ok(),
+            "Descender" => descent = value.parse().ok(),
+            "CapHeight" => cap_height = value.parse().ok(),
+            // C 32 ; WX 278 ; N space ;
+            "C" => {
+                let mut width = None;
+                let mut name = None;
+                for item in value.split(';') {
+                    match item.trim().split_once(' ') {
+                        Some(("WX", v)) => width = v.parse().ok(),
+                        Some(("N", v)) => name = Some(v),
+                        _ => {}
+                    }
+                }
+                if let (Some(width), Some(name)) = (width, name) {
+                    widths.insert(name, width);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let metrics = FontMetrics {
+        ascent,
+        descent,
+        cap_height,
+        bbox,
+        widths,
+    };
+
+    (font_name, metrics)
+}
+
+// Arial，TimesNewRomanなどの別名も標準14フォントの名前にする
+// "Arial,Bold"，"TimesNewRomanPS-BoldItalicMT" のようにスタイルが付くことがある
+fn standard_name(base_font: &str) -> Option<&'static str> {
+    let name: String = base_font.chars().filter(|c| *c != ' ').collect();
+    let (family, style) = match name.find([',', '-']) {
+        Some(i) => (&name[..i], &name[i + 1..]),
+        None => (name.as_str(), ""),
+    };
+    let is_bold = style.contains("Bold");
+    let is_italic = style.contains("Italic") || style.contains("Oblique");
+
+    let name = match family {
+        "Helvetica" | "Arial" | "ArialMT" => match (is_bold, is_italic) {
+            (false, false) => "Helvetica",
+            (true, false) => "Helvetica-Bold",
+            (false, true) => "Helvetica-Oblique",
+            (true, true) => "Helvetica-BoldOblique",
+        },
+        "Times" | "TimesNewRoman" | "TimesNewRomanPS" | "TimesNewRomanPSMT" => {
+            match (is_bold, is_italic) {
+                (false, false) => "Times-Roman",
+                (true, false) => "Times-Bold",
+                (false, true) => "Times-Italic",
+                (true, true) => "Times-BoldItalic",
+            }
+        }
+        "Courier" | "CourierNew" | "CourierNewPS" | "CourierNewPSMT" => {
+            match (is_bold, is_italic) {
+                (false, false) => "Courier",
+                (true, false) => "Courier-Bold",
+                (false, true) => "Courier-Oblique",
+                (true, true) => "Courier-BoldOblique",
+            }
+        }
+        "Symbol" | "SymbolMT" => "Symbol",
+        "ZapfDingbats" | "Dingbats" => "ZapfDingbats",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+// /Widthsなどが無い標準14フォントのための寸法
+pub fn standard_font_metrics(base_font: &str) -> Option<&'static FontMetrics> {
+    static METRICS: OnceLock<HashMap<&'static str, FontMetrics>> = OnceLock::new();
+
+    METRICS
+        .get_or_init(|| AFM_FILES.iter().map(|afm| parse_afm(afm)).collect())
+        .get(standard_name(base_font)?)
+}
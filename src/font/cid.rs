@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::cmap::CMap;
+
+// Adobeの文字集合(/CIDSystemInfoの/Ordering)ごとのCIDからUnicodeへの対応
+// 今はJapan1の欧文，半角カナとJIS X 0208の範囲(CID 1〜7477)だけを持つ
+// GB1，CNS1とKorea1の表は無いので，/ToUnicodeが無ければ文字は分からない
+// 完全な表にはAdobeのcid2code.txtが必要で，それはまだ同梱していない
+const JAPAN1: &str = include_str!("cid/Adobe-Japan1.txt");
+
+// JIS X 0208の文字コードからAdobe-Japan1のCIDへの対応
+const JIS_CMAP: &[u8] = include_bytes!("cid/H.txt");
+
+struct CidTable {
+    to_unicode: HashMap<u32, char>,
+    to_cid: HashMap<char, u32>,
+}
+
+// "CID;Unicode" か "最初のCID-最後のCID;最初のUnicode" の行を読む
+fn parse_cid_table(table: &'static str) -> CidTable {
+    let mut to_unicode = HashMap::new();
+
+    for line in table.lines().filter(|line| !line.starts_with('#')) {
+        let (cids, unicode) = match line.split_once(';') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (first, last) = cids.split_once('-').unwrap_or((cids, cids));
+
+        let (first, last, unicode) = match (
+            first.parse::<u32>(),
+            last.parse::<u32>(),
+            u32::from_str_radix(unicode, 16),
+        ) {
+            (Ok(first), Ok(last), Ok(unicode)) => (first, last, unicode),
+            _ => continue,
+        };
+
+        for cid in first..=last {
+            if let Some(c) = char::from_u32(unicode + cid - first) {
+                to_unicode.insert(cid, c);
+            }
+        }
+    }
+
+    // 同じ文字に複数のCIDがあるときは小さいCIDを使う
+    let mut to_cid = HashMap::new();
+    for (cid, c) in to_unicode.iter() {
+        let entry = to_cid.entry(*c).or_insert(*cid);
+        if *cid < *entry {
+            *entry = *cid;
+        }
+    }
+
+    CidTable { to_unicode, to_cid }
+}
+
+fn get_table(ordering: &str) -> Option<&'static CidTable> {
+    static JAPAN1_TABLE: OnceLock<CidTable> = OnceLock::new();
+
+    match ordering {
+        "Japan1" => Some(JAPAN1_TABLE.get_or_init(|| parse_cid_table(JAPAN1))),
+        _ => None,
+    }
+}
+
+pub fn cid_to_unicode(ordering: &str, cid: u32) -> Option<char> {
+    get_table(ordering)?.to_unicode.get(&cid).copied()
+}
+
+pub fn unicode_to_cid(ordering: &str, c: char) -> Option<u32> {
+    get_table(ordering)?.to_cid.get(&c).copied()
+}
+
+// JIS X 0208の区点を2バイトの文字コード(0x2121〜0x7E7E)で与える
+pub fn jis_to_cid(code: u16) -> Option<u32> {
+    static JIS: OnceLock<CMap> = OnceLock::new();
+
+    JIS.get_or_init(|| CMap::parse(JIS_CMAP).unwrap_or_default())
+        .to_cid(&code.to_be_bytes())
+}
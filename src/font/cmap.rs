@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use super::glyph_list;
+use super::Error;
+use crate::content;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// bfrangeを展開するときの1つの範囲の上限
+// 壊れたCMapで巨大な表を作らないようにする
+const MAX_RANGE_SIZE: u32 = 0x10000;
+
+// 文字コードの範囲．コードのバイト数はlowとhighの長さで決まる
+#[derive(Debug, Clone)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+// 各バイトがlowとhighの対応するバイトの間にあるか
+fn is_in_range(code: &[u8], low: &[u8], high: &[u8]) -> bool {
+    code.len() == low.len()
+        && code.len() == high.len()
+        && code
+            .iter()
+            .zip(low.iter().zip(high.iter()))
+            .all(|(b, (low, high))| low <= b && b <= high)
+}
+
+// <low> <high> cid の範囲
+#[derive(Debug, Clone)]
+struct CidRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+    cid: u32,
+}
+
+// /ToUnicodeのCMapと，Type0フォントの/Encodingに埋め込まれたCMap
+// 文字コードのバイト列からUnicodeの文字列やCIDへの対応を持つ
+#[derive(Debug, Clone, Default)]
+pub struct CMap {
+    codespace_ranges: Vec<CodespaceRange>,
+    unicode_map: HashMap<Vec<u8>, String>,
+    cid_map: HashMap<Vec<u8>, u32>,
+    cid_ranges: Vec<CidRange>,
+    // usecmapで取り込む定義済みCMapの名前
+    use_cmap: Option<String>,
+    // /WMode 1 なら縦書き
+    wmode: isize,
+}
+
+impl CMap {
+    // begincodespacerange，beginbfchar，beginbfrange，begincidchar，begincidrangeの節と
+    // usecmap，/WModeを読む．それ以外の定義(/CIDSystemInfoなど)は無視する
+    pub fn parse(content: &[u8]) -> Result<CMap, Error> {
+        let mut cmap = CMap::default();
+
+        for operation in content::parse_operations(content)? {
+            let operands = &operation.operands;
+
+            match operation.operator.as_str() {
+                "def" => {
+                    if let (Some(Object::Name(key)), Some(Object::Integer(value))) =
+                        (operands.first(), operands.get(1))
+                    {
+                        if key.as_str() == "WMode" {
+                            cmap.wmode = value.unpack();
+                        }
+                    }
+                }
+                "usecmap" => {
+                    if let Some(Object::Name(name)) = operands.first() {
+                        cmap.use_cmap = Some(String::from(name.as_str()));
+                    }
+                }
+                "endcidchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        let code = object::PdfString::ensure(&pair[0])?.as_bytes();
+                        let cid = object::PdfInteger::ensure(&pair[1])?.unpack();
+                        cmap.cid_map.insert(code.to_vec(), cid.max(0) as u32);
+                    }
+                }
+                "endcidrange" => {
+                    for range in operands.chunks_exact(3) {
+                        cmap.cid_ranges.push(CidRange {
+                            low: object::PdfString::ensure(&range[0])?.as_bytes().to_vec(),
+                            high: object::PdfString::ensure(&range[1])?.as_bytes().to_vec(),
+                            cid: object::PdfInteger::ensure(&range[2])?.unpack().max(0) as u32,
+                        });
+                    }
+                }
+                "endcodespacerange" => {
+                    for range in operands.chunks_exact(2) {
+                        cmap.codespace_ranges.push(CodespaceRange {
+                            low: object::PdfString::ensure(&range[0])?.as_bytes().to_vec(),
+                            high: object::PdfString::ensure(&range[1])?.as_bytes().to_vec(),
+                        });
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        let code = object::PdfString::ensure(&pair[0])?.as_bytes();
+                        if let Some(text) = destination_to_string(&pair[1]) {
+                            cmap.unicode_map.insert(code.to_vec(), text);
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for range in operands.chunks_exact(3) {
+                        cmap.insert_bf_range(
+                            object::PdfString::ensure(&range[0])?.as_bytes(),
+                            object::PdfString::ensure(&range[1])?.as_bytes(),
+                            &range[2],
+                        )?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(cmap)
+    }
+
+    // <low> <high> <dst> は low から順に dst の最後の文字を1ずつ増やして割り当てる
+    // <low> <high> [<dst1> <dst2> ...] は配列の要素を順に割り当てる
+    fn insert_bf_range(&mut self, low: &[u8], high: &[u8], dst: &Object) -> Result<(), Error> {
+        if low.len() != high.len() || low.is_empty() || 4 < low.len() {
+            return Ok(());
+        }
+
+        let low_value = bytes_to_u32(low);
+        let high_value = bytes_to_u32(high);
+        if high_value < low_value || MAX_RANGE_SIZE <= high_value - low_value {
+            return Ok(());
+        }
+
+        for (i, value) in (low_value..=high_value).enumerate() {
+            let code = value.to_be_bytes()[4 - low.len()..].to_vec();
+
+            let text = match dst {
+                Object::Array(array) => match array.get(i) {
+                    Some(obj) => destination_to_string(obj),
+                    None => break,
+                },
+                obj => {
+                    let mut bytes = object::PdfString::ensure(obj)?.as_bytes().to_vec();
+                    increment_last_unit(&mut bytes, i as u32);
+                    utf16be_to_string(&bytes)
+                }
+            };
+
+            if let Some(text) = text {
+                self.unicode_map.insert(code, text);
+            }
+        }
+
+        Ok(())
+    }
+
+    // バイト列の先頭の文字コードが何バイトかを返す
+    // どのcodespacerangeにも当てはまらないときは，最も短い範囲のバイト数とする
+    pub fn code_length(&self, bytes: &[u8]) -> usize {
+        for length in 1..=4.min(bytes.len()) {
+            if self.has_codespace(&bytes[..length]) {
+                return length;
+            }
+        }
+
+        self.codespace_ranges
+            .iter()
+            .map(|range| range.low.len())
+            .min()
+            .unwrap_or(1)
+            .clamp(1, bytes.len().max(1))
+    }
+
+    // 文字コードがcodespacerangeのどれかに当てはまるか
+    pub fn has_codespace(&self, code: &[u8]) -> bool {
+        self.codespace_ranges
+            .iter()
+            .any(|range| is_in_range(code, &range.low, &range.high))
+    }
+
+    pub fn to_unicode(&self, code: &[u8]) -> Option<&str> {
+        self.unicode_map.get(code).map(|text| text.as_str())
+    }
+
+    // cidcharを優先し，無ければcidrangeから求める
+    // CIDが溢れる範囲は無いものとして次の範囲を探す
+    pub fn to_cid(&self, code: &[u8]) -> Option<u32> {
+        if let Some(cid) = self.cid_map.get(code) {
+            return Some(*cid);
+        }
+
+        self.cid_ranges
+            .iter()
+            .filter(|range| is_in_range(code, &range.low, &range.high))
+            .find_map(|range| {
+                let offset = bytes_to_u32(code).checked_sub(bytes_to_u32(&range.low))?;
+                range.cid.checked_add(offset)
+            })
+    }
+
+    pub fn use_cmap(&self) -> Option<&str> {
+        self.use_cmap.as_deref()
+    }
+
+    pub fn wmode(&self) -> isize {
+        self.wmode
+    }
+}
+
+pub fn bytes_to_u32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u32)
+}
+
+// 最後のUTF-16の符号単位（奇数バイトなら最後のバイト）に足す
+fn increment_last_unit(bytes: &mut [u8], n: u32) {
+    let len = bytes.len();
+
+    if len >= 2 && len.is_multiple_of(2) {
+        let unit = u16::from_be_bytes([bytes[len - 2], bytes[len - 1]]).wrapping_add(n as u16);
+        bytes[len - 2..].copy_from_slice(&unit.to_be_bytes());
+    } else if let Some(last) = bytes.last_mut() {
+        *last = last.wrapping_add(n as u8);
+    }
+}
+
+// 割り当て先はUTF-16BEの文字列．古いCMapではグリフ名のこともある
+fn destination_to_string(obj: &Object) -> Option<String> {
+    match obj {
+        Object::String(string) => utf16be_to_string(string.as_bytes()),
+        Object::Name(name) => glyph_list::glyph_name_to_unicode(name.as_str(), false),
+        _ => None,
+    }
+}
+
+// サロゲートペアも1つの文字にする
+fn utf16be_to_string(bytes: &[u8]) -> Option<String> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    if !bytes.len().is_multiple_of(2) {
+        return Some(bytes.iter().map(|b| char::from(*b)).collect());
+    }
+
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+
+    Some(String::from_utf16_lossy(&units))
+}
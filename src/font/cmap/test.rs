@@ -0,0 +1,66 @@
+use super::*;
+
+const CMAP: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+2 begincodespacerange
+<00> <80>
+<8140> <FFFF>
+endcodespacerange
+3 beginbfchar
+<01> <0041>
+<02> <D83DDE00>
+<8141> <00660069>
+endbfchar
+2 beginbfrange
+<10> <12> <0061>
+<8150> <8152> [<3042> <3044> <3046>]
+endbfrange
+endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end
+";
+
+#[test]
+fn bf_char_and_range() {
+    let cmap = CMap::parse(CMAP).unwrap();
+
+    assert_eq!(cmap.to_unicode(&[0x01]), Some("A"));
+    // サロゲートペア
+    assert_eq!(cmap.to_unicode(&[0x02]), Some("\u{1f600}"));
+    assert_eq!(cmap.to_unicode(&[0x81, 0x41]), Some("fi"));
+    assert_eq!(cmap.to_unicode(&[0x10]), Some("a"));
+    assert_eq!(cmap.to_unicode(&[0x12]), Some("c"));
+    assert_eq!(cmap.to_unicode(&[0x81, 0x51]), Some("い"));
+    assert_eq!(cmap.to_unicode(&[0x03]), None);
+}
+
+#[test]
+fn variable_length_codes() {
+    let cmap = CMap::parse(CMAP).unwrap();
+
+    assert_eq!(cmap.code_length(&[0x41, 0x81, 0x41]), 1);
+    assert_eq!(cmap.code_length(&[0x81, 0x41]), 2);
+    assert_eq!(cmap.code_length(&[0x81]), 1);
+}
+
+#[test]
+fn cid_range_overflow() {
+    let cmap = CMap::parse(
+        b"1 begincodespacerange <00> <ff> endcodespacerange
+2 begincidrange
+<00> <ff> 4294967294
+<00> <7f> 100
+endcidrange",
+    )
+    .unwrap();
+
+    assert_eq!(cmap.to_cid(&[0x01]), Some(u32::MAX));
+    // 溢れる範囲は飛ばして次の範囲を使う
+    assert_eq!(cmap.to_cid(&[0x02]), Some(102));
+    assert_eq!(cmap.to_cid(&[0x80]), None);
+}
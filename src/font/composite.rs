@@ -0,0 +1,544 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use super::cid;
+use super::cmap::{self, CMap};
+use super::{parse_extent, Error};
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// CIDフォントの/DWの既定値
+const DEFAULT_WIDTH: f64 = 1000.0;
+// /DW2の既定値 [原点のy 縦の送り幅]
+const DEFAULT_VERTICAL: [f64; 2] = [880.0, -1000.0];
+
+// 縦書きの字送り（グリフ空間の単位）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerticalMetric {
+    // 縦の送り幅．下へ進むので通常は負
+    pub advance: f64,
+    // 縦書きの原点の位置（横書きの原点から見た位置）
+    // /W2に無いCIDではxは決まっておらず，字幅の半分を使う
+    pub origin_x: Option<f64>,
+    pub origin_y: f64,
+}
+
+// Type0フォントの/Encoding
+#[derive(Debug, Clone)]
+pub enum CidEncoding {
+    // Identity-H，Identity-V
+    // 2バイトの文字コードがそのままCID
+    Identity,
+    // 90ms-RKSJ-Hなど，Shift_JISの文字コード
+    // 1バイトの文字はproportionalなら欧文，そうでなければ半角のCIDにする
+    ShiftJis {
+        proportional: bool,
+    },
+    // EUC-H，EUC-V
+    EucJp,
+    // H，V
+    // JIS X 0208の2バイトの文字コード
+    Jis,
+    // UniJIS-UCS2-Hなど，文字コードがUnicode
+    // UTF16ならサロゲートペアの4バイトの文字コードもある
+    Unicode {
+        is_utf16: bool,
+    },
+    // /Encodingに埋め込まれたCMap
+    // usecmapで定義済みCMapを取り込んでいるときは，見つからない文字コードをそちらで引く
+    Embedded {
+        cmap: Box<CMap>,
+        base: Option<Box<CidEncoding>>,
+    },
+    // 対応していない定義済みCMapか，/Encodingが無いか壊れている
+    // 文字コードの区切りもCIDも分からないので2バイトずつに区切り，/ToUnicodeだけで文字を求める
+    Unknown,
+}
+
+// 文字列の先頭から読んだ1つの文字コード
+#[derive(Debug, Clone, PartialEq)]
+pub struct CidCode {
+    pub length: usize,
+    pub cid: Option<u32>,
+    // 文字コード自体がUnicodeのときの文字
+    pub unicode: Option<char>,
+}
+
+impl CidEncoding {
+    // 定義済みCMapの名前から
+    // 対応していないCMapはNone
+    // Unicodeの文字コードのCMapはCIDの表があるJapan1のUniJISだけを扱う
+    pub fn from_name(name: &str) -> Option<CidEncoding> {
+        let base_name = name
+            .strip_suffix("-H")
+            .or_else(|| name.strip_suffix("-V"))
+            .unwrap_or(name);
+
+        let encoding = match base_name {
+            "Identity" => CidEncoding::Identity,
+            "90ms-RKSJ" | "RKSJ" | "83pv-RKSJ" | "90pv-RKSJ" | "Add-RKSJ" | "Ext-RKSJ" => {
+                CidEncoding::ShiftJis {
+                    proportional: false,
+                }
+            }
+            "90msp-RKSJ" => CidEncoding::ShiftJis { proportional: true },
+            "EUC" => CidEncoding::EucJp,
+            "H" | "V" => CidEncoding::Jis,
+            _ => {
+                let rest = base_name.strip_prefix("UniJIS")?;
+
+                // UniJIS2004-UTF16やUniJIS-UCS2-HWなどの変種も同じように扱う
+                let is_utf16 = if rest.contains("UTF16") {
+                    true
+                } else if rest.contains("UCS2") {
+                    false
+                } else {
+                    return None;
+                };
+
+                CidEncoding::Unicode { is_utf16 }
+            }
+        };
+
+        Some(encoding)
+    }
+
+    // 定義済みCMapの名前の -V は縦書き
+    pub fn is_vertical_name(name: &str) -> bool {
+        name.ends_with("-V") || name == "V"
+    }
+
+    // 文字コードがUnicodeのCIDやJIS系のCMapが決める文字集合
+    // Identityと埋め込みCMapでは分からない
+    pub fn ordering(&self) -> Option<&'static str> {
+        match self {
+            CidEncoding::Identity | CidEncoding::Unknown => None,
+            CidEncoding::ShiftJis { .. }
+            | CidEncoding::EucJp
+            | CidEncoding::Jis
+            | CidEncoding::Unicode { .. } => Some("Japan1"),
+            CidEncoding::Embedded { base, .. } => base.as_ref().and_then(|base| base.ordering()),
+        }
+    }
+
+    pub fn next_code(&self, bytes: &[u8]) -> CidCode {
+        let lead = bytes[0];
+        let two_bytes = bytes.get(1).map(|second| (lead, *second));
+
+        match self {
+            CidEncoding::Identity => {
+                let length = 2.min(bytes.len());
+                CidCode {
+                    length,
+                    cid: Some(cmap::bytes_to_u32(&bytes[..length])),
+                    unicode: None,
+                }
+            }
+            CidEncoding::ShiftJis { proportional } => match two_bytes {
+                Some((lead, trail)) if matches!(lead, 0x81..=0x9f | 0xe0..=0xfc) => CidCode {
+                    length: 2,
+                    cid: shift_jis_to_jis(lead, trail).and_then(cid::jis_to_cid),
+                    unicode: None,
+                },
+                _ => CidCode {
+                    length: 1,
+                    cid: single_byte_cid(lead, *proportional),
+                    unicode: None,
+                },
+            },
+            CidEncoding::EucJp => match two_bytes {
+                Some((0x8e, kana)) => CidCode {
+                    length: 2,
+                    cid: single_byte_cid(kana, false),
+                    unicode: None,
+                },
+                Some((lead, trail)) if lead >= 0xa1 => CidCode {
+                    length: 2,
+                    cid: cid::jis_to_cid(u16::from_be_bytes([lead & 0x7f, trail & 0x7f])),
+                    unicode: None,
+                },
+                _ => CidCode {
+                    length: 1,
+                    cid: single_byte_cid(lead, false),
+                    unicode: None,
+                },
+            },
+            CidEncoding::Jis => {
+                let length = 2.min(bytes.len());
+                CidCode {
+                    length,
+                    cid: cid::jis_to_cid(cmap::bytes_to_u32(&bytes[..length]) as u16),
+                    unicode: None,
+                }
+            }
+            CidEncoding::Unicode { is_utf16 } => {
+                let unit = |i: usize| match (bytes.get(i), bytes.get(i + 1)) {
+                    (Some(high), Some(low)) => Some(u16::from_be_bytes([*high, *low])),
+                    _ => None,
+                };
+
+                let (length, unicode) = match (unit(0), unit(2)) {
+                    (Some(high), Some(low))
+                        if *is_utf16
+                            && (0xd800..0xdc00).contains(&high)
+                            && (0xdc00..0xe000).contains(&low) =>
+                    {
+                        (
+                            4,
+                            char::decode_utf16([high, low]).next().and_then(|c| c.ok()),
+                        )
+                    }
+                    (Some(unit), _) => (2, char::from_u32(unit as u32)),
+                    (None, _) => (bytes.len(), None),
+                };
+
+                CidCode {
+                    length,
+                    cid: unicode.and_then(|c| cid::unicode_to_cid("Japan1", c)),
+                    unicode,
+                }
+            }
+            CidEncoding::Unknown => CidCode {
+                length: 2.min(bytes.len()),
+                cid: None,
+                unicode: None,
+            },
+            CidEncoding::Embedded { cmap, base } => {
+                let has_codespace =
+                    (1..=4.min(bytes.len())).any(|length| cmap.has_codespace(&bytes[..length]));
+
+                match base {
+                    Some(base) if !has_codespace => base.next_code(bytes),
+                    _ => {
+                        let length = cmap.code_length(bytes);
+                        let code = &bytes[..length];
+                        let cid = match (cmap.to_cid(code), base) {
+                            (Some(cid), _) => Some(cid),
+                            (None, Some(base)) => base.next_code(code).cid,
+                            (None, None) => None,
+                        };
+
+                        CidCode {
+                            length,
+                            cid,
+                            unicode: None,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Shift_JISの2バイトをJIS X 0208の2バイトの文字コードにする
+fn shift_jis_to_jis(lead: u8, trail: u8) -> Option<u16> {
+    if !matches!(trail, 0x40..=0x7e | 0x80..=0xfc) {
+        return None;
+    }
+
+    let offset = if lead <= 0x9f { 0x70 } else { 0xb0 };
+    let (row, cell) = if trail >= 0x9f {
+        ((lead - offset) * 2, trail - 0x7e)
+    } else {
+        let cell = if trail >= 0x80 {
+            trail - 0x20
+        } else {
+            trail - 0x1f
+        };
+        ((lead - offset) * 2 - 1, cell)
+    };
+
+    Some(u16::from_be_bytes([row, cell]))
+}
+
+// 1バイトの欧文と半角カナのAdobe-Japan1のCID
+fn single_byte_cid(byte: u8, proportional: bool) -> Option<u32> {
+    match byte {
+        0x20..=0x7e if proportional => Some(1 + byte as u32 - 0x20),
+        0x20..=0x7e => Some(231 + byte as u32 - 0x20),
+        0xa1..=0xdf => Some(327 + byte as u32 - 0xa1),
+        _ => None,
+    }
+}
+
+// Type0フォントと，その/DescendantFontsのCIDフォント
+#[derive(Debug, Clone)]
+pub struct CompositeFont {
+    pub encoding: CidEncoding,
+    pub is_vertical: bool,
+    // /ToUnicodeが無いときにCIDからUnicodeを求めるための文字集合
+    ordering: Option<String>,
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+    vertical_metrics: HashMap<u32, VerticalMetric>,
+    default_vertical: [f64; 2],
+    // CIDフォントの/FontDescriptorから求めた字の上端と下端
+    pub extent: Option<(f64, f64)>,
+}
+
+impl CompositeFont {
+    pub fn new(
+        font_dict: &object::PdfDict,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<CompositeFont, Error> {
+        let (encoding, is_vertical) = match font_dict.get("Encoding") {
+            Some(obj) => parse_cid_encoding(&object::resolve(obj, file, xref)?, file, xref)?,
+            None => (CidEncoding::Unknown, false),
+        };
+
+        let descendant = match font_dict.get("DescendantFonts") {
+            Some(obj) => {
+                let descendants = object::resolve(obj, file, xref)?;
+                match object::PdfArray::ensure(&descendants)?.get(0) {
+                    Some(obj) => Some(object::resolve(obj, file, xref)?),
+                    None => None,
+                }
+            }
+            None => None,
+        };
+        let descendant = match &descendant {
+            Some(obj) => Some(object::PdfDict::ensure_with_key(obj, vec![])?),
+            None => None,
+        };
+
+        let mut font = CompositeFont {
+            encoding,
+            is_vertical,
+            ordering: None,
+            widths: HashMap::new(),
+            default_width: DEFAULT_WIDTH,
+            vertical_metrics: HashMap::new(),
+            default_vertical: DEFAULT_VERTICAL,
+            extent: None,
+        };
+
+        let descendant = match descendant {
+            Some(descendant) => descendant,
+            None => return Ok(font),
+        };
+
+        font.extent = parse_extent(descendant, file, xref)?;
+
+        let ordering = get_ordering(descendant, file, xref)?;
+        // IdentityのCIDFontType2ではCIDはグリフ番号なので，文字集合のCIDとはみなせない
+        let is_identity_type2 = matches!(font.encoding, CidEncoding::Identity)
+            && matches!(descendant.get("Subtype"), Some(Object::Name(subtype)) if subtype.as_str() == "CIDFontType2");
+        if !is_identity_type2 {
+            font.ordering = font.encoding.ordering().map(String::from).or(ordering);
+        }
+
+        if let Some(obj) = descendant.get("DW") {
+            font.default_width =
+                object::PdfReal::ensure_number(&object::resolve(obj, file, xref)?)?;
+        }
+        if let Some(obj) = descendant.get("W") {
+            let w = object::resolve(obj, file, xref)?;
+            let mut items = vec![];
+            for obj in object::PdfArray::ensure(&w)? {
+                items.push(object::resolve(obj, file, xref)?);
+            }
+            font.widths = parse_widths(&items)?;
+        }
+
+        if let Some(obj) = descendant.get("DW2") {
+            let dw2 = object::resolve(obj, file, xref)?;
+            let dw2 = object::PdfArray::ensure(&dw2)?;
+            if let (Some(origin_y), Some(advance)) = (dw2.get(0), dw2.get(1)) {
+                font.default_vertical = [
+                    object::PdfReal::ensure_number(origin_y)?,
+                    object::PdfReal::ensure_number(advance)?,
+                ];
+            }
+        }
+        if let Some(obj) = descendant.get("W2") {
+            let w2 = object::resolve(obj, file, xref)?;
+            let mut items = vec![];
+            for obj in object::PdfArray::ensure(&w2)? {
+                items.push(object::resolve(obj, file, xref)?);
+            }
+            font.vertical_metrics = parse_vertical_metrics(&items)?;
+        }
+
+        Ok(font)
+    }
+
+    // 字幅（グリフ空間の単位）
+    pub fn width(&self, cid: Option<u32>) -> f64 {
+        match cid.and_then(|cid| self.widths.get(&cid)) {
+            Some(width) => *width,
+            None => self.default_width,
+        }
+    }
+
+    // 縦書きの字送り．/W2に無いCIDは/DW2を使う
+    pub fn vertical_metric(&self, cid: Option<u32>) -> VerticalMetric {
+        match cid.and_then(|cid| self.vertical_metrics.get(&cid)) {
+            Some(metric) => *metric,
+            None => VerticalMetric {
+                advance: self.default_vertical[1],
+                origin_x: None,
+                origin_y: self.default_vertical[0],
+            },
+        }
+    }
+
+    pub fn cid_to_unicode(&self, cid: u32) -> Option<char> {
+        cid::cid_to_unicode(self.ordering.as_deref()?, cid)
+    }
+}
+
+// /Encodingは定義済みCMapの名前か，CMapのストリーム
+fn parse_cid_encoding(
+    encoding_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<(CidEncoding, bool), Error> {
+    match encoding_obj {
+        // 対応していない定義済みCMapをIdentityとみなすと誤ったCIDになるので，分からないものとする
+        Object::Name(name) => Ok((
+            CidEncoding::from_name(name.as_str()).unwrap_or(CidEncoding::Unknown),
+            CidEncoding::is_vertical_name(name.as_str()),
+        )),
+        Object::StreamObj(stream) => {
+            let cmap = CMap::parse(&filter::decode_stream(stream, file, xref)?)?;
+
+            // ストリームの/UseCMapでも取り込むCMapを指定できる
+            let use_cmap = match (cmap.use_cmap(), stream.dict.get("UseCMap")) {
+                (Some(name), _) => Some(String::from(name)),
+                (None, Some(Object::Name(name))) => Some(String::from(name.as_str())),
+                _ => None,
+            };
+            let base = use_cmap
+                .as_deref()
+                .and_then(CidEncoding::from_name)
+                .map(Box::new);
+
+            let is_vertical = match stream.dict.get("WMode") {
+                Some(obj) => object::PdfInteger::ensure(obj)?.unpack() == 1,
+                None => cmap.wmode() == 1,
+            };
+
+            Ok((
+                CidEncoding::Embedded {
+                    cmap: Box::new(cmap),
+                    base,
+                },
+                is_vertical,
+            ))
+        }
+        _ => Ok((CidEncoding::Unknown, false)),
+    }
+}
+
+fn get_ordering(
+    descendant: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<String>, Error> {
+    let system_info = match descendant.get("CIDSystemInfo") {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(None),
+    };
+    let system_info = object::PdfDict::ensure_with_key(&system_info, vec![])?;
+
+    match system_info.get("Ordering") {
+        Some(obj) => {
+            let ordering = object::resolve(obj, file, xref)?;
+            Ok(Some(
+                String::from_utf8_lossy(object::PdfString::ensure(&ordering)?.as_bytes())
+                    .into_owned(),
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+// /W [c [w1 w2 ...] c_first c_last w ...]
+pub fn parse_widths(items: &[Object]) -> Result<HashMap<u32, f64>, Error> {
+    let mut widths = HashMap::new();
+    let mut i = 0;
+    while i < items.len() {
+        let first = object::PdfReal::ensure_number(&items[i])?.max(0.0) as u32;
+
+        match items.get(i + 1) {
+            Some(Object::Array(array)) => {
+                for (j, width) in array.into_iter().enumerate() {
+                    // CIDが溢れる分は捨てる
+                    let cid = match first.checked_add(j as u32) {
+                        Some(cid) => cid,
+                        None => break,
+                    };
+                    widths.insert(cid, object::PdfReal::ensure_number(width)?);
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let last = object::PdfReal::ensure_number(last)?.max(0.0) as u32;
+                let width = match items.get(i + 2) {
+                    Some(width) => object::PdfReal::ensure_number(width)?,
+                    None => break,
+                };
+                // 壊れた範囲で巨大な表を作らないようにする
+                for cid in first..=last.min(first.saturating_add(0xffff)) {
+                    widths.insert(cid, width);
+                }
+                i += 3;
+            }
+            None => break,
+        }
+    }
+
+    Ok(widths)
+}
+
+// /W2 [c [w1y vx vy w1y vx vy ...] c_first c_last w1y vx vy ...]
+pub fn parse_vertical_metrics(items: &[Object]) -> Result<HashMap<u32, VerticalMetric>, Error> {
+    let number = |obj: &Object| object::PdfReal::ensure_number(obj);
+    let to_metric = |values: &[Object]| -> Result<VerticalMetric, Error> {
+        Ok(VerticalMetric {
+            advance: number(&values[0])?,
+            origin_x: Some(number(&values[1])?),
+            origin_y: number(&values[2])?,
+        })
+    };
+
+    let mut metrics = HashMap::new();
+    let mut i = 0;
+    while i < items.len() {
+        let first = number(&items[i])?.max(0.0) as u32;
+
+        match items.get(i + 1) {
+            Some(Object::Array(array)) => {
+                let values: Vec<Object> = array.into_iter().cloned().collect();
+                for (j, values) in values.chunks_exact(3).enumerate() {
+                    let cid = match first.checked_add(j as u32) {
+                        Some(cid) => cid,
+                        None => break,
+                    };
+                    metrics.insert(cid, to_metric(values)?);
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let last = number(last)?.max(0.0) as u32;
+                let metric = match items.get(i + 2..i + 5) {
+                    Some(values) => to_metric(values)?,
+                    None => break,
+                };
+                for cid in first..=last.min(first.saturating_add(0xffff)) {
+                    metrics.insert(cid, metric);
+                }
+                i += 5;
+            }
+            None => break,
+        }
+    }
+
+    Ok(metrics)
+}
@@ -0,0 +1,184 @@
+use super::*;
+use crate::test_util;
+
+fn unicode_of(encoding: &CidEncoding, bytes: &[u8]) -> String {
+    let mut text = String::new();
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = encoding.next_code(&bytes[i..]);
+        let c = code
+            .unicode
+            .or_else(|| cid::cid_to_unicode("Japan1", code.cid.unwrap()))
+            .unwrap();
+        text.push(c);
+        i += code.length;
+    }
+
+    text
+}
+
+#[test]
+fn predefined_cmap_names() {
+    assert!(matches!(
+        CidEncoding::from_name("Identity-V"),
+        Some(CidEncoding::Identity)
+    ));
+    assert!(matches!(
+        CidEncoding::from_name("90msp-RKSJ-H"),
+        Some(CidEncoding::ShiftJis { proportional: true })
+    ));
+    assert!(matches!(
+        CidEncoding::from_name("UniJIS-UTF16-V"),
+        Some(CidEncoding::Unicode { is_utf16: true })
+    ));
+    assert!(CidEncoding::from_name("GBK-EUC-H").is_none());
+    // CIDの表が無い文字集合のUnicodeのCMapは扱わない
+    assert!(CidEncoding::from_name("UniGB-UCS2-H").is_none());
+    assert!(CidEncoding::from_name("UniKS-UTF16-V").is_none());
+}
+
+#[test]
+fn unknown_cmap() {
+    let (mut file, xref) = test_util::write_pdf(&[]);
+
+    let name = test_util::parse("/GBK-EUC-H");
+    let (encoding, is_vertical) = parse_cid_encoding(&name, &mut file, &xref).unwrap();
+    assert!(matches!(encoding, CidEncoding::Unknown));
+    assert!(!is_vertical);
+    assert_eq!(encoding.ordering(), None);
+
+    let code = encoding.next_code(&[0xb0, 0xa1, 0x41]);
+    assert_eq!(code.length, 2);
+    assert_eq!(code.cid, None);
+    assert_eq!(code.unicode, None);
+    assert_eq!(encoding.next_code(&[0x41]).length, 1);
+
+    let (encoding, is_vertical) =
+        parse_cid_encoding(&test_util::parse("/UniKS-UCS2-V"), &mut file, &xref).unwrap();
+    assert!(matches!(encoding, CidEncoding::Unknown));
+    assert!(is_vertical);
+}
+
+#[test]
+fn shift_jis_codes() {
+    assert_eq!(shift_jis_to_jis(0x81, 0x40), Some(0x2121));
+    assert_eq!(shift_jis_to_jis(0x88, 0x9f), Some(0x3021));
+    assert_eq!(shift_jis_to_jis(0xea, 0xa4), Some(0x7426));
+
+    let encoding = CidEncoding::from_name("90ms-RKSJ-H").unwrap();
+    // "日本語 ｱ" のShift_JIS
+    let bytes = [0x93, 0xfa, 0x96, 0x7b, 0x8c, 0xea, 0x20, 0xb1];
+    assert_eq!(unicode_of(&encoding, &bytes), "日本語 ｱ");
+
+    let code = encoding.next_code(&bytes[6..]);
+    assert_eq!(code.length, 1);
+    assert_eq!(code.cid, Some(231));
+}
+
+#[test]
+fn euc_and_jis_codes() {
+    let euc = CidEncoding::from_name("EUC-H").unwrap();
+    assert_eq!(
+        unicode_of(&euc, &[0xc6, 0xfc, 0xcb, 0xdc, 0x41, 0x8e, 0xb1]),
+        "日本Aｱ"
+    );
+
+    let jis = CidEncoding::from_name("H").unwrap();
+    let code = jis.next_code(&[0x21, 0x21]);
+    assert_eq!(code.cid, Some(633));
+    assert_eq!(unicode_of(&jis, &[0x46, 0x7c]), "日");
+}
+
+#[test]
+fn unicode_codes() {
+    let ucs2 = CidEncoding::from_name("UniJIS-UCS2-H").unwrap();
+    let code = ucs2.next_code(&[0x65, 0xe5]);
+    assert_eq!(code.length, 2);
+    assert_eq!(code.unicode, Some('日'));
+    assert_eq!(code.cid, CidEncoding::Jis.next_code(&[0x46, 0x7c]).cid);
+
+    let utf16 = CidEncoding::from_name("UniJIS-UTF16-H").unwrap();
+    let code = utf16.next_code(&[0xd8, 0x40, 0xdc, 0x0b, 0x00, 0x41]);
+    assert_eq!(code.length, 4);
+    assert_eq!(code.unicode, Some('\u{2000b}'));
+}
+
+#[test]
+fn cid_widths() {
+    let integer = |i: isize| Object::Integer(object::PdfInteger::new(i, 0));
+    let items = vec![
+        integer(1),
+        Object::Array(object::PdfArray::new(vec![integer(500), integer(600)], 0)),
+        integer(633),
+        integer(635),
+        integer(1000),
+    ];
+
+    let widths = parse_widths(&items).unwrap();
+
+    assert_eq!(widths.get(&1), Some(&500.0));
+    assert_eq!(widths.get(&2), Some(&600.0));
+    assert_eq!(widths.get(&634), Some(&1000.0));
+    assert_eq!(widths.get(&636), None);
+}
+
+#[test]
+fn vertical_metrics() {
+    let number = |n: f64| Object::Real(object::PdfReal::new(n, 0));
+    let items = vec![
+        number(120.0),
+        Object::Array(object::PdfArray::new(
+            vec![number(-500.0), number(250.0), number(880.0)],
+            0,
+        )),
+        number(633.0),
+        number(634.0),
+        number(-1000.0),
+        number(500.0),
+        number(900.0),
+    ];
+
+    let metrics = parse_vertical_metrics(&items).unwrap();
+
+    assert_eq!(
+        metrics.get(&120),
+        Some(&VerticalMetric {
+            advance: -500.0,
+            origin_x: Some(250.0),
+            origin_y: 880.0,
+        })
+    );
+    assert_eq!(metrics.get(&634).unwrap().origin_y, 900.0);
+    assert!(!metrics.contains_key(&635));
+
+    assert!(CidEncoding::is_vertical_name("90ms-RKSJ-V"));
+    assert!(CidEncoding::is_vertical_name("V"));
+    assert!(!CidEncoding::is_vertical_name("UniJIS-UCS2-H"));
+}
+
+#[test]
+fn cid_widths_overflow() {
+    let integer = |i: isize| Object::Integer(object::PdfInteger::new(i, 0));
+    let items = vec![
+        integer(u32::MAX as isize),
+        Object::Array(object::PdfArray::new(vec![integer(500), integer(600)], 0)),
+    ];
+
+    let widths = parse_widths(&items).unwrap();
+
+    assert_eq!(widths.len(), 1);
+    assert_eq!(widths.get(&u32::MAX), Some(&500.0));
+
+    let items = vec![
+        integer(u32::MAX as isize),
+        Object::Array(object::PdfArray::new(
+            [-1000, 500, 880, -1000, 500, 880].map(integer).to_vec(),
+            0,
+        )),
+    ];
+
+    let metrics = parse_vertical_metrics(&items).unwrap();
+
+    assert_eq!(metrics.len(), 1);
+}
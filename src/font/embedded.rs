@@ -0,0 +1,149 @@
+use std::fs::File;
+
+use super::{strip_subset_prefix, Error};
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// /FontDescriptorに埋め込まれたフォントプログラムの形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontFileType {
+    // /FontFile
+    Type1,
+    // /FontFile2
+    TrueType,
+    // /FontFile3の/Subtype
+    Type1C,
+    CIDFontType0C,
+    OpenType,
+}
+
+impl FontFileType {
+    // 保存するときの拡張子
+    // /FontFileは平文の部分と暗号化された部分をそのままつないだもので，PFAでもPFBでもない
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FontFileType::Type1 => "t1",
+            FontFileType::TrueType => "ttf",
+            FontFileType::Type1C | FontFileType::CIDFontType0C => "cff",
+            FontFileType::OpenType => "otf",
+        }
+    }
+}
+
+// 埋め込まれたフォントプログラム
+#[derive(Debug, Clone)]
+pub struct EmbeddedFont {
+    // フォントプログラムのストリームの (オブジェクト番号, 世代番号)
+    pub object_ref: (usize, usize),
+    // サブセットの接頭辞を除いた/BaseFont
+    pub base_font: String,
+    // "ABCDEF+" の "ABCDEF"
+    pub subset_prefix: Option<String>,
+    pub font_type: FontFileType,
+    // フィルターをデコードしたフォントプログラム
+    pub data: Vec<u8>,
+}
+
+// フォント辞書の/FontDescriptorから埋め込まれたフォントプログラムを取り出す
+// Type0は子孫のCIDフォントの/FontDescriptorを見る．Type3や埋め込まれていないフォントは空
+pub fn extract_embedded_fonts(
+    font_dict: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Vec<EmbeddedFont>, Error> {
+    let base_font = match font_dict.get("BaseFont") {
+        Some(obj) => {
+            String::from(object::PdfName::ensure(&object::resolve(obj, file, xref)?)?.as_str())
+        }
+        None => String::new(),
+    };
+
+    let mut fonts = vec![];
+    match font_dict.get("Subtype") {
+        Some(Object::Name(subtype)) if subtype.as_str() == "Type0" => {
+            let descendants = match font_dict.get("DescendantFonts") {
+                Some(obj) => object::resolve(obj, file, xref)?,
+                None => return Ok(fonts),
+            };
+            for descendant in object::PdfArray::ensure(&descendants)? {
+                let descendant = object::resolve(descendant, file, xref)?;
+                let descendant = object::PdfDict::ensure_with_key(&descendant, vec![])?;
+                if let Some(font) = extract_font_file(descendant, &base_font, file, xref)? {
+                    fonts.push(font);
+                }
+            }
+        }
+        _ => {
+            if let Some(font) = extract_font_file(font_dict, &base_font, file, xref)? {
+                fonts.push(font);
+            }
+        }
+    }
+
+    Ok(fonts)
+}
+
+fn extract_font_file(
+    font_dict: &object::PdfDict,
+    base_font: &str,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<EmbeddedFont>, Error> {
+    let descriptor = match font_dict.get("FontDescriptor") {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(None),
+    };
+    // CIDフォントは自身の/BaseFontを持つ
+    let base_font = match font_dict.get("BaseFont") {
+        Some(obj) => {
+            String::from(object::PdfName::ensure(&object::resolve(obj, file, xref)?)?.as_str())
+        }
+        None => String::from(base_font),
+    };
+    let descriptor = object::PdfDict::ensure_with_key(&descriptor, vec![])?;
+
+    for key in ["FontFile", "FontFile2", "FontFile3"] {
+        let font_file_ref = match descriptor.get(key) {
+            Some(obj) => object::PdfIndirectRef::ensure(obj)?,
+            None => continue,
+        };
+        let font_file = object::resolve(&Object::IndirectRef(font_file_ref.clone()), file, xref)?;
+        let font_file = object::PdfStreamObj::ensure_stream(&font_file)?;
+
+        let font_type = match key {
+            "FontFile" => FontFileType::Type1,
+            "FontFile2" => FontFileType::TrueType,
+            _ => match font_file.dict.get("Subtype") {
+                Some(Object::Name(subtype)) => match subtype.as_str() {
+                    "Type1C" => FontFileType::Type1C,
+                    "CIDFontType0C" => FontFileType::CIDFontType0C,
+                    "OpenType" => FontFileType::OpenType,
+                    _ => continue,
+                },
+                _ => continue,
+            },
+        };
+
+        let name = strip_subset_prefix(&base_font);
+        let subset_prefix = if name.len() < base_font.len() {
+            Some(String::from(&base_font[..6]))
+        } else {
+            None
+        };
+
+        return Ok(Some(EmbeddedFont {
+            object_ref: font_file_ref.unpack(),
+            base_font: String::from(name),
+            subset_prefix,
+            font_type,
+            data: filter::decode_stream(font_file, file, xref)?,
+        }));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,124 @@
+use super::*;
+use crate::test_util;
+
+fn extract(font: &str, file: &mut File, xref: &cross_reference::XRef) -> Vec<EmbeddedFont> {
+    let font_obj = test_util::parse(font);
+    let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![]).unwrap();
+
+    extract_embedded_fonts(font_dict, file, xref).unwrap()
+}
+
+#[test]
+fn font_file_types() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        test_util::stream("", b"type1"),
+        test_util::stream("", b"truetype"),
+        test_util::stream("/Subtype /Type1C", b"cff"),
+        test_util::stream("/Subtype /OpenType", b"otf"),
+        test_util::stream("/Subtype /Unknown", b"unknown"),
+    ]);
+
+    let cases = [
+        (
+            "FontFile 1 0 R",
+            (1, 0),
+            FontFileType::Type1,
+            "t1",
+            &b"type1"[..],
+        ),
+        (
+            "FontFile2 2 0 R",
+            (2, 0),
+            FontFileType::TrueType,
+            "ttf",
+            b"truetype",
+        ),
+        (
+            "FontFile3 3 0 R",
+            (3, 0),
+            FontFileType::Type1C,
+            "cff",
+            b"cff",
+        ),
+        (
+            "FontFile3 4 0 R",
+            (4, 0),
+            FontFileType::OpenType,
+            "otf",
+            b"otf",
+        ),
+    ];
+    for (font_file, object_ref, font_type, extension, data) in cases {
+        let fonts = extract(
+            &format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /FontDescriptor << /{} >> >>",
+                font_file
+            ),
+            &mut file,
+            &xref,
+        );
+
+        assert_eq!(fonts.len(), 1);
+        assert_eq!(fonts[0].object_ref, object_ref);
+        assert_eq!(fonts[0].font_type, font_type);
+        assert_eq!(fonts[0].font_type.extension(), extension);
+        assert_eq!(fonts[0].data, data);
+    }
+
+    // 知らない/Subtypeの/FontFile3や，埋め込まれていないフォントは取り出さない
+    let unknown = extract(
+        "<< /Subtype /Type1 /BaseFont /Helvetica /FontDescriptor << /FontFile3 5 0 R >> >>",
+        &mut file,
+        &xref,
+    );
+    assert!(unknown.is_empty());
+    let not_embedded = extract(
+        "<< /Subtype /Type1 /BaseFont /Helvetica /FontDescriptor << >> >>",
+        &mut file,
+        &xref,
+    );
+    assert!(not_embedded.is_empty());
+}
+
+#[test]
+fn subset_prefix_and_type0_descendant() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        test_util::stream("/Subtype /CIDFontType0C", b"cid"),
+        b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /XYZABC+KozMinPro-Regular \
+          /FontDescriptor << /FontFile3 1 0 R >> >>"
+            .to_vec(),
+        test_util::stream("", b"truetype"),
+    ]);
+
+    // Type0は子孫のCIDフォントの/FontDescriptorと/BaseFontを使う
+    let fonts = extract(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /XYZABC+KozMinPro-Regular-Identity-H \
+         /Encoding /Identity-H /DescendantFonts [2 0 R] >>",
+        &mut file,
+        &xref,
+    );
+    assert_eq!(fonts.len(), 1);
+    assert_eq!(fonts[0].object_ref, (1, 0));
+    assert_eq!(fonts[0].font_type, FontFileType::CIDFontType0C);
+    assert_eq!(fonts[0].base_font, "KozMinPro-Regular");
+    assert_eq!(fonts[0].subset_prefix.as_deref(), Some("XYZABC"));
+
+    let fonts = extract(
+        "<< /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+Arial \
+         /FontDescriptor << /FontFile2 3 0 R >> >>",
+        &mut file,
+        &xref,
+    );
+    assert_eq!(fonts[0].base_font, "Arial");
+    assert_eq!(fonts[0].subset_prefix.as_deref(), Some("ABCDEF"));
+
+    // 大文字6文字でない接頭辞はサブセットとみなさない
+    let fonts = extract(
+        "<< /Type /Font /Subtype /TrueType /BaseFont /Abc+Arial \
+         /FontDescriptor << /FontFile2 3 0 R >> >>",
+        &mut file,
+        &xref,
+    );
+    assert_eq!(fonts[0].base_font, "Abc+Arial");
+    assert_eq!(fonts[0].subset_prefix, None);
+}
@@ -0,0 +1,1109 @@
+use super::glyph_list;
+use super::Error;
+use crate::object;
+use crate::parser::Object;
+
+// 単純フォントの符号化．1バイトの文字コードをグリフ名に対応付ける
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Standard,
+    WinAnsi,
+    MacRoman,
+    // Symbolフォントの組み込みの符号化
+    Symbol,
+    // ZapfDingbatsフォントの組み込みの符号化
+    ZapfDingbats,
+}
+
+impl BaseEncoding {
+    // /Encodingや/BaseEncodingに指定できる名前から
+    // MacExpertEncodingは対応していない
+    pub fn from_name(name: &str) -> Option<BaseEncoding> {
+        match name {
+            "StandardEncoding" => Some(BaseEncoding::Standard),
+            "WinAnsiEncoding" => Some(BaseEncoding::WinAnsi),
+            "MacRomanEncoding" => Some(BaseEncoding::MacRoman),
+            _ => None,
+        }
+    }
+
+    // 標準14フォントなどの，フォント自身が持っている符号化
+    // サブセットの接頭辞は取り除いておくこと
+    pub fn builtin(base_font: &str) -> BaseEncoding {
+        if base_font.starts_with("Symbol") {
+            BaseEncoding::Symbol
+        } else if base_font.contains("Dingbats") {
+            BaseEncoding::ZapfDingbats
+        } else {
+            BaseEncoding::Standard
+        }
+    }
+
+    // 文字コードを添字とする256個のグリフ名
+    pub fn glyph_names(&self) -> Vec<Option<String>> {
+        let table = match self {
+            BaseEncoding::Standard => STANDARD,
+            BaseEncoding::WinAnsi => WIN_ANSI,
+            BaseEncoding::MacRoman => MAC_ROMAN,
+            BaseEncoding::Symbol => SYMBOL,
+            BaseEncoding::ZapfDingbats => ZAPF_DINGBATS,
+        };
+
+        let mut names = vec![None; 256];
+        for (code, name) in table {
+            names[*code as usize] = Some(String::from(*name));
+        }
+
+        names
+    }
+}
+
+// 単純フォントの文字コードからグリフ名，グリフ名からUnicodeへの対応
+#[derive(Debug, Clone)]
+pub struct SimpleEncoding {
+    // 文字コードを添字とする256個のグリフ名
+    glyph_names: Vec<Option<String>>,
+    is_zapf_dingbats: bool,
+}
+
+impl SimpleEncoding {
+    pub fn new(base: BaseEncoding) -> SimpleEncoding {
+        SimpleEncoding {
+            glyph_names: base.glyph_names(),
+            is_zapf_dingbats: base == BaseEncoding::ZapfDingbats,
+        }
+    }
+
+    // /Differences [code /name1 /name2 ... code /name ...] で一部の文字コードのグリフ名を置き換える
+    // 数値の後の名前はその文字コードから順に割り当てる
+    pub fn apply_differences(&mut self, differences: &object::PdfArray) -> Result<(), Error> {
+        let mut code: usize = 0;
+
+        for obj in differences {
+            match obj {
+                Object::Integer(_) | Object::Real(_) => {
+                    code = object::PdfReal::ensure_number(obj)? as usize;
+                }
+                obj => {
+                    let name = object::PdfName::ensure(obj)?;
+                    if let Some(glyph_name) = self.glyph_names.get_mut(code) {
+                        *glyph_name = Some(String::from(name.as_str()));
+                    }
+                    code += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn glyph_name(&self, code: u8) -> Option<&str> {
+        self.glyph_names[code as usize].as_deref()
+    }
+
+    // Unicodeが分からないグリフ名や，割り当てのない文字コードはNone
+    pub fn to_unicode(&self, code: u8) -> Option<String> {
+        glyph_list::glyph_name_to_unicode(self.glyph_name(code)?, self.is_zapf_dingbats)
+    }
+}
+
+// StandardEncoding
+const STANDARD: &[(u8, &str)] = &[
+    (0x20, "space"),
+    (0x21, "exclam"),
+    (0x22, "quotedbl"),
+    (0x23, "numbersign"),
+    (0x24, "dollar"),
+    (0x25, "percent"),
+    (0x26, "ampersand"),
+    (0x27, "quoteright"),
+    (0x28, "parenleft"),
+    (0x29, "parenright"),
+    (0x2a, "asterisk"),
+    (0x2b, "plus"),
+    (0x2c, "comma"),
+    (0x2d, "hyphen"),
+    (0x2e, "period"),
+    (0x2f, "slash"),
+    (0x30, "zero"),
+    (0x31, "one"),
+    (0x32, "two"),
+    (0x33, "three"),
+    (0x34, "four"),
+    (0x35, "five"),
+    (0x36, "six"),
+    (0x37, "seven"),
+    (0x38, "eight"),
+    (0x39, "nine"),
+    (0x3a, "colon"),
+    (0x3b, "semicolon"),
+    (0x3c, "less"),
+    (0x3d, "equal"),
+    (0x3e, "greater"),
+    (0x3f, "question"),
+    (0x40, "at"),
+    (0x41, "A"),
+    (0x42, "B"),
+    (0x43, "C"),
+    (0x44, "D"),
+    (0x45, "E"),
+    (0x46, "F"),
+    (0x47, "G"),
+    (0x48, "H"),
+    (0x49, "I"),
+    (0x4a, "J"),
+    (0x4b, "K"),
+    (0x4c, "L"),
+    (0x4d, "M"),
+    (0x4e, "N"),
+    (0x4f, "O"),
+    (0x50, "P"),
+    (0x51, "Q"),
+    (0x52, "R"),
+    (0x53, "S"),
+    (0x54, "T"),
+    (0x55, "U"),
+    (0x56, "V"),
+    (0x57, "W"),
+    (0x58, "X"),
+    (0x59, "Y"),
+    (0x5a, "Z"),
+    (0x5b, "bracketleft"),
+    (0x5c, "backslash"),
+    (0x5d, "bracketright"),
+    (0x5e, "asciicircum"),
+    (0x5f, "underscore"),
+    (0x60, "quoteleft"),
+    (0x61, "a"),
+    (0x62, "b"),
+    (0x63, "c"),
+    (0x64, "d"),
+    (0x65, "e"),
+    (0x66, "f"),
+    (0x67, "g"),
+    (0x68, "h"),
+    (0x69, "i"),
+    (0x6a, "j"),
+    (0x6b, "k"),
+    (0x6c, "l"),
+    (0x6d, "m"),
+    (0x6e, "n"),
+    (0x6f, "o"),
+    (0x70, "p"),
+    (0x71, "q"),
+    (0x72, "r"),
+    (0x73, "s"),
+    (0x74, "t"),
+    (0x75, "u"),
+    (0x76, "v"),
+    (0x77, "w"),
+    (0x78, "x"),
+    (0x79, "y"),
+    (0x7a, "z"),
+    (0x7b, "braceleft"),
+    (0x7c, "bar"),
+    (0x7d, "braceright"),
+    (0x7e, "asciitilde"),
+    (0xa1, "exclamdown"),
+    (0xa2, "cent"),
+    (0xa3, "sterling"),
+    (0xa4, "fraction"),
+    (0xa5, "yen"),
+    (0xa6, "florin"),
+    (0xa7, "section"),
+    (0xa8, "currency"),
+    (0xa9, "quotesingle"),
+    (0xaa, "quotedblleft"),
+    (0xab, "guillemotleft"),
+    (0xac, "guilsinglleft"),
+    (0xad, "guilsinglright"),
+    (0xae, "fi"),
+    (0xaf, "fl"),
+    (0xb1, "endash"),
+    (0xb2, "dagger"),
+    (0xb3, "daggerdbl"),
+    (0xb4, "periodcentered"),
+    (0xb6, "paragraph"),
+    (0xb7, "bullet"),
+    (0xb8, "quotesinglbase"),
+    (0xb9, "quotedblbase"),
+    (0xba, "quotedblright"),
+    (0xbb, "guillemotright"),
+    (0xbc, "ellipsis"),
+    (0xbd, "perthousand"),
+    (0xbf, "questiondown"),
+    (0xc1, "grave"),
+    (0xc2, "acute"),
+    (0xc3, "circumflex"),
+    (0xc4, "tilde"),
+    (0xc5, "macron"),
+    (0xc6, "breve"),
+    (0xc7, "dotaccent"),
+    (0xc8, "dieresis"),
+    (0xca, "ring"),
+    (0xcb, "cedilla"),
+    (0xcd, "hungarumlaut"),
+    (0xce, "ogonek"),
+    (0xcf, "caron"),
+    (0xd0, "emdash"),
+    (0xe1, "AE"),
+    (0xe3, "ordfeminine"),
+    (0xe8, "Lslash"),
+    (0xe9, "Oslash"),
+    (0xea, "OE"),
+    (0xeb, "ordmasculine"),
+    (0xf1, "ae"),
+    (0xf5, "dotlessi"),
+    (0xf8, "lslash"),
+    (0xf9, "oslash"),
+    (0xfa, "oe"),
+    (0xfb, "germandbls"),
+];
+
+// WinAnsiEncoding
+const WIN_ANSI: &[(u8, &str)] = &[
+    (0x20, "space"),
+    (0x21, "exclam"),
+    (0x22, "quotedbl"),
+    (0x23, "numbersign"),
+    (0x24, "dollar"),
+    (0x25, "percent"),
+    (0x26, "ampersand"),
+    (0x27, "quotesingle"),
+    (0x28, "parenleft"),
+    (0x29, "parenright"),
+    (0x2a, "asterisk"),
+    (0x2b, "plus"),
+    (0x2c, "comma"),
+    (0x2d, "hyphen"),
+    (0x2e, "period"),
+    (0x2f, "slash"),
+    (0x30, "zero"),
+    (0x31, "one"),
+    (0x32, "two"),
+    (0x33, "three"),
+    (0x34, "four"),
+    (0x35, "five"),
+    (0x36, "six"),
+    (0x37, "seven"),
+    (0x38, "eight"),
+    (0x39, "nine"),
+    (0x3a, "colon"),
+    (0x3b, "semicolon"),
+    (0x3c, "less"),
+    (0x3d, "equal"),
+    (0x3e, "greater"),
+    (0x3f, "question"),
+    (0x40, "at"),
+    (0x41, "A"),
+    (0x42, "B"),
+    (0x43, "C"),
+    (0x44, "D"),
+    (0x45, "E"),
+    (0x46, "F"),
+    (0x47, "G"),
+    (0x48, "H"),
+    (0x49, "I"),
+    (0x4a, "J"),
+    (0x4b, "K"),
+    (0x4c, "L"),
+    (0x4d, "M"),
+    (0x4e, "N"),
+    (0x4f, "O"),
+    (0x50, "P"),
+    (0x51, "Q"),
+    (0x52, "R"),
+    (0x53, "S"),
+    (0x54, "T"),
+    (0x55, "U"),
+    (0x56, "V"),
+    (0x57, "W"),
+    (0x58, "X"),
+    (0x59, "Y"),
+    (0x5a, "Z"),
+    (0x5b, "bracketleft"),
+    (0x5c, "backslash"),
+    (0x5d, "bracketright"),
+    (0x5e, "asciicircum"),
+    (0x5f, "underscore"),
+    (0x60, "grave"),
+    (0x61, "a"),
+    (0x62, "b"),
+    (0x63, "c"),
+    (0x64, "d"),
+    (0x65, "e"),
+    (0x66, "f"),
+    (0x67, "g"),
+    (0x68, "h"),
+    (0x69, "i"),
+    (0x6a, "j"),
+    (0x6b, "k"),
+    (0x6c, "l"),
+    (0x6d, "m"),
+    (0x6e, "n"),
+    (0x6f, "o"),
+    (0x70, "p"),
+    (0x71, "q"),
+    (0x72, "r"),
+    (0x73, "s"),
+    (0x74, "t"),
+    (0x75, "u"),
+    (0x76, "v"),
+    (0x77, "w"),
+    (0x78, "x"),
+    (0x79, "y"),
+    (0x7a, "z"),
+    (0x7b, "braceleft"),
+    (0x7c, "bar"),
+    (0x7d, "braceright"),
+    (0x7e, "asciitilde"),
+    (0x80, "Euro"),
+    (0x82, "quotesinglbase"),
+    (0x83, "florin"),
+    (0x84, "quotedblbase"),
+    (0x85, "ellipsis"),
+    (0x86, "dagger"),
+    (0x87, "daggerdbl"),
+    (0x88, "circumflex"),
+    (0x89, "perthousand"),
+    (0x8a, "Scaron"),
+    (0x8b, "guilsinglleft"),
+    (0x8c, "OE"),
+    (0x8e, "Zcaron"),
+    (0x91, "quoteleft"),
+    (0x92, "quoteright"),
+    (0x93, "quotedblleft"),
+    (0x94, "quotedblright"),
+    (0x95, "bullet"),
+    (0x96, "endash"),
+    (0x97, "emdash"),
+    (0x98, "tilde"),
+    (0x99, "trademark"),
+    (0x9a, "scaron"),
+    (0x9b, "guilsinglright"),
+    (0x9c, "oe"),
+    (0x9e, "zcaron"),
+    (0x9f, "Ydieresis"),
+    (0xa0, "space"),
+    (0xa1, "exclamdown"),
+    (0xa2, "cent"),
+    (0xa3, "sterling"),
+    (0xa4, "currency"),
+    (0xa5, "yen"),
+    (0xa6, "brokenbar"),
+    (0xa7, "section"),
+    (0xa8, "dieresis"),
+    (0xa9, "copyright"),
+    (0xaa, "ordfeminine"),
+    (0xab, "guillemotleft"),
+    (0xac, "logicalnot"),
+    (0xad, "hyphen"),
+    (0xae, "registered"),
+    (0xaf, "macron"),
+    (0xb0, "degree"),
+    (0xb1, "plusminus"),
+    (0xb2, "twosuperior"),
+    (0xb3, "threesuperior"),
+    (0xb4, "acute"),
+    (0xb5, "mu"),
+    (0xb6, "paragraph"),
+    (0xb7, "periodcentered"),
+    (0xb8, "cedilla"),
+    (0xb9, "onesuperior"),
+    (0xba, "ordmasculine"),
+    (0xbb, "guillemotright"),
+    (0xbc, "onequarter"),
+    (0xbd, "onehalf"),
+    (0xbe, "threequarters"),
+    (0xbf, "questiondown"),
+    (0xc0, "Agrave"),
+    (0xc1, "Aacute"),
+    (0xc2, "Acircumflex"),
+    (0xc3, "Atilde"),
+    (0xc4, "Adieresis"),
+    (0xc5, "Aring"),
+    (0xc6, "AE"),
+    (0xc7, "Ccedilla"),
+    (0xc8, "Egrave"),
+    (0xc9, "Eacute"),
+    (0xca, "Ecircumflex"),
+    (0xcb, "Edieresis"),
+    (0xcc, "Igrave"),
+    (0xcd, "Iacute"),
+    (0xce, "Icircumflex"),
+    (0xcf, "Idieresis"),
+    (0xd0, "Eth"),
+    (0xd1, "Ntilde"),
+    (0xd2, "Ograve"),
+    (0xd3, "Oacute"),
+    (0xd4, "Ocircumflex"),
+    (0xd5, "Otilde"),
+    (0xd6, "Odieresis"),
+    (0xd7, "multiply"),
+    (0xd8, "Oslash"),
+    (0xd9, "Ugrave"),
+    (0xda, "Uacute"),
+    (0xdb, "Ucircumflex"),
+    (0xdc, "Udieresis"),
+    (0xdd, "Yacute"),
+    (0xde, "Thorn"),
+    (0xdf, "germandbls"),
+    (0xe0, "agrave"),
+    (0xe1, "aacute"),
+    (0xe2, "acircumflex"),
+    (0xe3, "atilde"),
+    (0xe4, "adieresis"),
+    (0xe5, "aring"),
+    (0xe6, "ae"),
+    (0xe7, "ccedilla"),
+    (0xe8, "egrave"),
+    (0xe9, "eacute"),
+    (0xea, "ecircumflex"),
+    (0xeb, "edieresis"),
+    (0xec, "igrave"),
+    (0xed, "iacute"),
+    (0xee, "icircumflex"),
+    (0xef, "idieresis"),
+    (0xf0, "eth"),
+    (0xf1, "ntilde"),
+    (0xf2, "ograve"),
+    (0xf3, "oacute"),
+    (0xf4, "ocircumflex"),
+    (0xf5, "otilde"),
+    (0xf6, "odieresis"),
+    (0xf7, "divide"),
+    (0xf8, "oslash"),
+    (0xf9, "ugrave"),
+    (0xfa, "uacute"),
+    (0xfb, "ucircumflex"),
+    (0xfc, "udieresis"),
+    (0xfd, "yacute"),
+    (0xfe, "thorn"),
+    (0xff, "ydieresis"),
+];
+
+// MacRomanEncoding
+// PDFの仕様にない数学記号もMac OSの文字集合に合わせて含める
+const MAC_ROMAN: &[(u8, &str)] = &[
+    (0x20, "space"),
+    (0x21, "exclam"),
+    (0x22, "quotedbl"),
+    (0x23, "numbersign"),
+    (0x24, "dollar"),
+    (0x25, "percent"),
+    (0x26, "ampersand"),
+    (0x27, "quotesingle"),
+    (0x28, "parenleft"),
+    (0x29, "parenright"),
+    (0x2a, "asterisk"),
+    (0x2b, "plus"),
+    (0x2c, "comma"),
+    (0x2d, "hyphen"),
+    (0x2e, "period"),
+    (0x2f, "slash"),
+    (0x30, "zero"),
+    (0x31, "one"),
+    (0x32, "two"),
+    (0x33, "three"),
+    (0x34, "four"),
+    (0x35, "five"),
+    (0x36, "six"),
+    (0x37, "seven"),
+    (0x38, "eight"),
+    (0x39, "nine"),
+    (0x3a, "colon"),
+    (0x3b, "semicolon"),
+    (0x3c, "less"),
+    (0x3d, "equal"),
+    (0x3e, "greater"),
+    (0x3f, "question"),
+    (0x40, "at"),
+    (0x41, "A"),
+    (0x42, "B"),
+    (0x43, "C"),
+    (0x44, "D"),
+    (0x45, "E"),
+    (0x46, "F"),
+    (0x47, "G"),
+    (0x48, "H"),
+    (0x49, "I"),
+    (0x4a, "J"),
+    (0x4b, "K"),
+    (0x4c, "L"),
+    (0x4d, "M"),
+    (0x4e, "N"),
+    (0x4f, "O"),
+    (0x50, "P"),
+    (0x51, "Q"),
+    (0x52, "R"),
+    (0x53, "S"),
+    (0x54, "T"),
+    (0x55, "U"),
+    (0x56, "V"),
+    (0x57, "W"),
+    (0x58, "X"),
+    (0x59, "Y"),
+    (0x5a, "Z"),
+    (0x5b, "bracketleft"),
+    (0x5c, "backslash"),
+    (0x5d, "bracketright"),
+    (0x5e, "asciicircum"),
+    (0x5f, "underscore"),
+    (0x60, "grave"),
+    (0x61, "a"),
+    (0x62, "b"),
+    (0x63, "c"),
+    (0x64, "d"),
+    (0x65, "e"),
+    (0x66, "f"),
+    (0x67, "g"),
+    (0x68, "h"),
+    (0x69, "i"),
+    (0x6a, "j"),
+    (0x6b, "k"),
+    (0x6c, "l"),
+    (0x6d, "m"),
+    (0x6e, "n"),
+    (0x6f, "o"),
+    (0x70, "p"),
+    (0x71, "q"),
+    (0x72, "r"),
+    (0x73, "s"),
+    (0x74, "t"),
+    (0x75, "u"),
+    (0x76, "v"),
+    (0x77, "w"),
+    (0x78, "x"),
+    (0x79, "y"),
+    (0x7a, "z"),
+    (0x7b, "braceleft"),
+    (0x7c, "bar"),
+    (0x7d, "braceright"),
+    (0x7e, "asciitilde"),
+    (0x80, "Adieresis"),
+    (0x81, "Aring"),
+    (0x82, "Ccedilla"),
+    (0x83, "Eacute"),
+    (0x84, "Ntilde"),
+    (0x85, "Odieresis"),
+    (0x86, "Udieresis"),
+    (0x87, "aacute"),
+    (0x88, "agrave"),
+    (0x89, "acircumflex"),
+    (0x8a, "adieresis"),
+    (0x8b, "atilde"),
+    (0x8c, "aring"),
+    (0x8d, "ccedilla"),
+    (0x8e, "eacute"),
+    (0x8f, "egrave"),
+    (0x90, "ecircumflex"),
+    (0x91, "edieresis"),
+    (0x92, "iacute"),
+    (0x93, "igrave"),
+    (0x94, "icircumflex"),
+    (0x95, "idieresis"),
+    (0x96, "ntilde"),
+    (0x97, "oacute"),
+    (0x98, "ograve"),
+    (0x99, "ocircumflex"),
+    (0x9a, "odieresis"),
+    (0x9b, "otilde"),
+    (0x9c, "uacute"),
+    (0x9d, "ugrave"),
+    (0x9e, "ucircumflex"),
+    (0x9f, "udieresis"),
+    (0xa0, "dagger"),
+    (0xa1, "degree"),
+    (0xa2, "cent"),
+    (0xa3, "sterling"),
+    (0xa4, "section"),
+    (0xa5, "bullet"),
+    (0xa6, "paragraph"),
+    (0xa7, "germandbls"),
+    (0xa8, "registered"),
+    (0xa9, "copyright"),
+    (0xaa, "trademark"),
+    (0xab, "acute"),
+    (0xac, "dieresis"),
+    (0xad, "notequal"),
+    (0xae, "AE"),
+    (0xaf, "Oslash"),
+    (0xb0, "infinity"),
+    (0xb1, "plusminus"),
+    (0xb2, "lessequal"),
+    (0xb3, "greaterequal"),
+    (0xb4, "yen"),
+    (0xb5, "mu"),
+    (0xb6, "partialdiff"),
+    (0xb7, "summation"),
+    (0xb8, "product"),
+    (0xb9, "pi"),
+    (0xba, "integral"),
+    (0xbb, "ordfeminine"),
+    (0xbc, "ordmasculine"),
+    (0xbd, "Omega"),
+    (0xbe, "ae"),
+    (0xbf, "oslash"),
+    (0xc0, "questiondown"),
+    (0xc1, "exclamdown"),
+    (0xc2, "logicalnot"),
+    (0xc3, "radical"),
+    (0xc4, "florin"),
+    (0xc5, "approxequal"),
+    (0xc6, "Delta"),
+    (0xc7, "guillemotleft"),
+    (0xc8, "guillemotright"),
+    (0xc9, "ellipsis"),
+    (0xca, "space"),
+    (0xcb, "Agrave"),
+    (0xcc, "Atilde"),
+    (0xcd, "Otilde"),
+    (0xce, "OE"),
+    (0xcf, "oe"),
+    (0xd0, "endash"),
+    (0xd1, "emdash"),
+    (0xd2, "quotedblleft"),
+    (0xd3, "quotedblright"),
+    (0xd4, "quoteleft"),
+    (0xd5, "quoteright"),
+    (0xd6, "divide"),
+    (0xd7, "lozenge"),
+    (0xd8, "ydieresis"),
+    (0xd9, "Ydieresis"),
+    (0xda, "fraction"),
+    (0xdb, "currency"),
+    (0xdc, "guilsinglleft"),
+    (0xdd, "guilsinglright"),
+    (0xde, "fi"),
+    (0xdf, "fl"),
+    (0xe0, "daggerdbl"),
+    (0xe1, "periodcentered"),
+    (0xe2, "quotesinglbase"),
+    (0xe3, "quotedblbase"),
+    (0xe4, "perthousand"),
+    (0xe5, "Acircumflex"),
+    (0xe6, "Ecircumflex"),
+    (0xe7, "Aacute"),
+    (0xe8, "Edieresis"),
+    (0xe9, "Egrave"),
+    (0xea, "Iacute"),
+    (0xeb, "Icircumflex"),
+    (0xec, "Idieresis"),
+    (0xed, "Igrave"),
+    (0xee, "Oacute"),
+    (0xef, "Ocircumflex"),
+    (0xf1, "Ograve"),
+    (0xf2, "Uacute"),
+    (0xf3, "Ucircumflex"),
+    (0xf4, "Ugrave"),
+    (0xf5, "dotlessi"),
+    (0xf6, "circumflex"),
+    (0xf7, "tilde"),
+    (0xf8, "macron"),
+    (0xf9, "breve"),
+    (0xfa, "dotaccent"),
+    (0xfb, "ring"),
+    (0xfc, "cedilla"),
+    (0xfd, "hungarumlaut"),
+    (0xfe, "ogonek"),
+    (0xff, "caron"),
+];
+
+// Symbolの組み込みの符号化
+const SYMBOL: &[(u8, &str)] = &[
+    (0x20, "space"),
+    (0x21, "exclam"),
+    (0x22, "universal"),
+    (0x23, "numbersign"),
+    (0x24, "existential"),
+    (0x25, "percent"),
+    (0x26, "ampersand"),
+    (0x27, "suchthat"),
+    (0x28, "parenleft"),
+    (0x29, "parenright"),
+    (0x2a, "asteriskmath"),
+    (0x2b, "plus"),
+    (0x2c, "comma"),
+    (0x2d, "minus"),
+    (0x2e, "period"),
+    (0x2f, "slash"),
+    (0x30, "zero"),
+    (0x31, "one"),
+    (0x32, "two"),
+    (0x33, "three"),
+    (0x34, "four"),
+    (0x35, "five"),
+    (0x36, "six"),
+    (0x37, "seven"),
+    (0x38, "eight"),
+    (0x39, "nine"),
+    (0x3a, "colon"),
+    (0x3b, "semicolon"),
+    (0x3c, "less"),
+    (0x3d, "equal"),
+    (0x3e, "greater"),
+    (0x3f, "question"),
+    (0x40, "congruent"),
+    (0x41, "Alpha"),
+    (0x42, "Beta"),
+    (0x43, "Chi"),
+    (0x44, "Delta"),
+    (0x45, "Epsilon"),
+    (0x46, "Phi"),
+    (0x47, "Gamma"),
+    (0x48, "Eta"),
+    (0x49, "Iota"),
+    (0x4a, "theta1"),
+    (0x4b, "Kappa"),
+    (0x4c, "Lambda"),
+    (0x4d, "Mu"),
+    (0x4e, "Nu"),
+    (0x4f, "Omicron"),
+    (0x50, "Pi"),
+    (0x51, "Theta"),
+    (0x52, "Rho"),
+    (0x53, "Sigma"),
+    (0x54, "Tau"),
+    (0x55, "Upsilon"),
+    (0x56, "sigma1"),
+    (0x57, "Omega"),
+    (0x58, "Xi"),
+    (0x59, "Psi"),
+    (0x5a, "Zeta"),
+    (0x5b, "bracketleft"),
+    (0x5c, "therefore"),
+    (0x5d, "bracketright"),
+    (0x5e, "perpendicular"),
+    (0x5f, "underscore"),
+    (0x60, "radicalex"),
+    (0x61, "alpha"),
+    (0x62, "beta"),
+    (0x63, "chi"),
+    (0x64, "delta"),
+    (0x65, "epsilon"),
+    (0x66, "phi"),
+    (0x67, "gamma"),
+    (0x68, "eta"),
+    (0x69, "iota"),
+    (0x6a, "phi1"),
+    (0x6b, "kappa"),
+    (0x6c, "lambda"),
+    (0x6d, "mu"),
+    (0x6e, "nu"),
+    (0x6f, "omicron"),
+    (0x70, "pi"),
+    (0x71, "theta"),
+    (0x72, "rho"),
+    (0x73, "sigma"),
+    (0x74, "tau"),
+    (0x75, "upsilon"),
+    (0x76, "omega1"),
+    (0x77, "omega"),
+    (0x78, "xi"),
+    (0x79, "psi"),
+    (0x7a, "zeta"),
+    (0x7b, "braceleft"),
+    (0x7c, "bar"),
+    (0x7d, "braceright"),
+    (0x7e, "similar"),
+    (0xa0, "Euro"),
+    (0xa1, "Upsilon1"),
+    (0xa2, "minute"),
+    (0xa3, "lessequal"),
+    (0xa4, "fraction"),
+    (0xa5, "infinity"),
+    (0xa6, "florin"),
+    (0xa7, "club"),
+    (0xa8, "diamond"),
+    (0xa9, "heart"),
+    (0xaa, "spade"),
+    (0xab, "arrowboth"),
+    (0xac, "arrowleft"),
+    (0xad, "arrowup"),
+    (0xae, "arrowright"),
+    (0xaf, "arrowdown"),
+    (0xb0, "degree"),
+    (0xb1, "plusminus"),
+    (0xb2, "second"),
+    (0xb3, "greaterequal"),
+    (0xb4, "multiply"),
+    (0xb5, "proportional"),
+    (0xb6, "partialdiff"),
+    (0xb7, "bullet"),
+    (0xb8, "divide"),
+    (0xb9, "notequal"),
+    (0xba, "equivalence"),
+    (0xbb, "approxequal"),
+    (0xbc, "ellipsis"),
+    (0xbd, "arrowvertex"),
+    (0xbe, "arrowhorizex"),
+    (0xbf, "carriagereturn"),
+    (0xc0, "aleph"),
+    (0xc1, "Ifraktur"),
+    (0xc2, "Rfraktur"),
+    (0xc3, "weierstrass"),
+    (0xc4, "circlemultiply"),
+    (0xc5, "circleplus"),
+    (0xc6, "emptyset"),
+    (0xc7, "intersection"),
+    (0xc8, "union"),
+    (0xc9, "propersuperset"),
+    (0xca, "reflexsuperset"),
+    (0xcb, "notsubset"),
+    (0xcc, "propersubset"),
+    (0xcd, "reflexsubset"),
+    (0xce, "element"),
+    (0xcf, "notelement"),
+    (0xd0, "angle"),
+    (0xd1, "gradient"),
+    (0xd2, "registerserif"),
+    (0xd3, "copyrightserif"),
+    (0xd4, "trademarkserif"),
+    (0xd5, "product"),
+    (0xd6, "radical"),
+    (0xd7, "dotmath"),
+    (0xd8, "logicalnot"),
+    (0xd9, "logicaland"),
+    (0xda, "logicalor"),
+    (0xdb, "arrowdblboth"),
+    (0xdc, "arrowdblleft"),
+    (0xdd, "arrowdblup"),
+    (0xde, "arrowdblright"),
+    (0xdf, "arrowdbldown"),
+    (0xe0, "lozenge"),
+    (0xe1, "angleleft"),
+    (0xe2, "registersans"),
+    (0xe3, "copyrightsans"),
+    (0xe4, "trademarksans"),
+    (0xe5, "summation"),
+    (0xe6, "parenlefttp"),
+    (0xe7, "parenleftex"),
+    (0xe8, "parenleftbt"),
+    (0xe9, "bracketlefttp"),
+    (0xea, "bracketleftex"),
+    (0xeb, "bracketleftbt"),
+    (0xec, "bracelefttp"),
+    (0xed, "braceleftmid"),
+    (0xee, "braceleftbt"),
+    (0xef, "braceex"),
+    (0xf1, "angleright"),
+    (0xf2, "integral"),
+    (0xf3, "integraltp"),
+    (0xf4, "integralex"),
+    (0xf5, "integralbt"),
+    (0xf6, "parenrighttp"),
+    (0xf7, "parenrightex"),
+    (0xf8, "parenrightbt"),
+    (0xf9, "bracketrighttp"),
+    (0xfa, "bracketrightex"),
+    (0xfb, "bracketrightbt"),
+    (0xfc, "bracerighttp"),
+    (0xfd, "bracerightmid"),
+    (0xfe, "bracerightbt"),
+];
+
+// ZapfDingbatsの組み込みの符号化
+const ZAPF_DINGBATS: &[(u8, &str)] = &[
+    (0x20, "space"),
+    (0x21, "a1"),
+    (0x22, "a2"),
+    (0x23, "a202"),
+    (0x24, "a3"),
+    (0x25, "a4"),
+    (0x26, "a5"),
+    (0x27, "a119"),
+    (0x28, "a118"),
+    (0x29, "a117"),
+    (0x2a, "a11"),
+    (0x2b, "a12"),
+    (0x2c, "a13"),
+    (0x2d, "a14"),
+    (0x2e, "a15"),
+    (0x2f, "a16"),
+    (0x30, "a105"),
+    (0x31, "a17"),
+    (0x32, "a18"),
+    (0x33, "a19"),
+    (0x34, "a20"),
+    (0x35, "a21"),
+    (0x36, "a22"),
+    (0x37, "a23"),
+    (0x38, "a24"),
+    (0x39, "a25"),
+    (0x3a, "a26"),
+    (0x3b, "a27"),
+    (0x3c, "a28"),
+    (0x3d, "a6"),
+    (0x3e, "a7"),
+    (0x3f, "a8"),
+    (0x40, "a9"),
+    (0x41, "a10"),
+    (0x42, "a29"),
+    (0x43, "a30"),
+    (0x44, "a31"),
+    (0x45, "a32"),
+    (0x46, "a33"),
+    (0x47, "a34"),
+    (0x48, "a35"),
+    (0x49, "a36"),
+    (0x4a, "a37"),
+    (0x4b, "a38"),
+    (0x4c, "a39"),
+    (0x4d, "a40"),
+    (0x4e, "a41"),
+    (0x4f, "a42"),
+    (0x50, "a43"),
+    (0x51, "a44"),
+    (0x52, "a45"),
+    (0x53, "a46"),
+    (0x54, "a47"),
+    (0x55, "a48"),
+    (0x56, "a49"),
+    (0x57, "a50"),
+    (0x58, "a51"),
+    (0x59, "a52"),
+    (0x5a, "a53"),
+    (0x5b, "a54"),
+    (0x5c, "a55"),
+    (0x5d, "a56"),
+    (0x5e, "a57"),
+    (0x5f, "a58"),
+    (0x60, "a59"),
+    (0x61, "a60"),
+    (0x62, "a61"),
+    (0x63, "a62"),
+    (0x64, "a63"),
+    (0x65, "a64"),
+    (0x66, "a65"),
+    (0x67, "a66"),
+    (0x68, "a67"),
+    (0x69, "a68"),
+    (0x6a, "a69"),
+    (0x6b, "a70"),
+    (0x6c, "a71"),
+    (0x6d, "a72"),
+    (0x6e, "a73"),
+    (0x6f, "a74"),
+    (0x70, "a203"),
+    (0x71, "a75"),
+    (0x72, "a204"),
+    (0x73, "a76"),
+    (0x74, "a77"),
+    (0x75, "a78"),
+    (0x76, "a79"),
+    (0x77, "a81"),
+    (0x78, "a82"),
+    (0x79, "a83"),
+    (0x7a, "a84"),
+    (0x7b, "a97"),
+    (0x7c, "a98"),
+    (0x7d, "a99"),
+    (0x7e, "a100"),
+    (0x80, "a89"),
+    (0x81, "a90"),
+    (0x82, "a93"),
+    (0x83, "a94"),
+    (0x84, "a91"),
+    (0x85, "a92"),
+    (0x86, "a205"),
+    (0x87, "a85"),
+    (0x88, "a206"),
+    (0x89, "a86"),
+    (0x8a, "a87"),
+    (0x8b, "a88"),
+    (0x8c, "a95"),
+    (0x8d, "a96"),
+    (0xa1, "a101"),
+    (0xa2, "a102"),
+    (0xa3, "a103"),
+    (0xa4, "a104"),
+    (0xa5, "a106"),
+    (0xa6, "a107"),
+    (0xa7, "a108"),
+    (0xa8, "a112"),
+    (0xa9, "a111"),
+    (0xaa, "a110"),
+    (0xab, "a109"),
+    (0xac, "a120"),
+    (0xad, "a121"),
+    (0xae, "a122"),
+    (0xaf, "a123"),
+    (0xb0, "a124"),
+    (0xb1, "a125"),
+    (0xb2, "a126"),
+    (0xb3, "a127"),
+    (0xb4, "a128"),
+    (0xb5, "a129"),
+    (0xb6, "a130"),
+    (0xb7, "a131"),
+    (0xb8, "a132"),
+    (0xb9, "a133"),
+    (0xba, "a134"),
+    (0xbb, "a135"),
+    (0xbc, "a136"),
+    (0xbd, "a137"),
+    (0xbe, "a138"),
+    (0xbf, "a139"),
+    (0xc0, "a140"),
+    (0xc1, "a141"),
+    (0xc2, "a142"),
+    (0xc3, "a143"),
+    (0xc4, "a144"),
+    (0xc5, "a145"),
+    (0xc6, "a146"),
+    (0xc7, "a147"),
+    (0xc8, "a148"),
+    (0xc9, "a149"),
+    (0xca, "a150"),
+    (0xcb, "a151"),
+    (0xcc, "a152"),
+    (0xcd, "a153"),
+    (0xce, "a154"),
+    (0xcf, "a155"),
+    (0xd0, "a156"),
+    (0xd1, "a157"),
+    (0xd2, "a158"),
+    (0xd3, "a159"),
+    (0xd4, "a160"),
+    (0xd5, "a161"),
+    (0xd6, "a163"),
+    (0xd7, "a164"),
+    (0xd8, "a196"),
+    (0xd9, "a165"),
+    (0xda, "a192"),
+    (0xdb, "a166"),
+    (0xdc, "a167"),
+    (0xdd, "a168"),
+    (0xde, "a169"),
+    (0xdf, "a170"),
+    (0xe0, "a171"),
+    (0xe1, "a172"),
+    (0xe2, "a173"),
+    (0xe3, "a162"),
+    (0xe4, "a174"),
+    (0xe5, "a175"),
+    (0xe6, "a176"),
+    (0xe7, "a177"),
+    (0xe8, "a178"),
+    (0xe9, "a179"),
+    (0xea, "a193"),
+    (0xeb, "a180"),
+    (0xec, "a199"),
+    (0xed, "a181"),
+    (0xee, "a200"),
+    (0xef, "a182"),
+    (0xf1, "a201"),
+    (0xf2, "a183"),
+    (0xf3, "a184"),
+    (0xf4, "a197"),
+    (0xf5, "a185"),
+    (0xf6, "a194"),
+    (0xf7, "a198"),
+    (0xf8, "a186"),
+    (0xf9, "a195"),
+    (0xfa, "a187"),
+    (0xfb, "a188"),
+    (0xfc, "a189"),
+    (0xfd, "a190"),
+    (0xfe, "a191"),
+];
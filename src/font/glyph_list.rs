@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Adobe Glyph List (https://github.com/adobe-type-tools/agl-aglfn)
+const GLYPH_LIST: &str = include_str!("glyphlist.txt");
+// ZapfDingbatsのグリフ名（a1など）は別の表になっている
+const ZAPF_DINGBATS_GLYPH_LIST: &str = include_str!("zapfdingbats.txt");
+
+// 1つのグリフ名が "05D3 05B2" のように複数の文字に対応することもある
+fn parse_glyph_list(list: &'static str) -> HashMap<&'static str, String> {
+    list.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, codes) = line.split_once(';')?;
+            let text = codes
+                .split(' ')
+                .map(|code| char::from_u32(u32::from_str_radix(code, 16).ok()?))
+                .collect::<Option<String>>()?;
+            Some((name, text))
+        })
+        .collect()
+}
+
+fn lookup(name: &str, is_zapf_dingbats: bool) -> Option<&'static str> {
+    static GLYPHS: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+    static ZAPF_DINGBATS_GLYPHS: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+    if is_zapf_dingbats {
+        let glyphs =
+            ZAPF_DINGBATS_GLYPHS.get_or_init(|| parse_glyph_list(ZAPF_DINGBATS_GLYPH_LIST));
+        if let Some(text) = glyphs.get(name) {
+            return Some(text);
+        }
+    }
+
+    GLYPHS
+        .get_or_init(|| parse_glyph_list(GLYPH_LIST))
+        .get(name)
+        .map(|text| text.as_str())
+}
+
+// グリフ名をUnicodeの文字列にする
+// 表に無い名前はAGLの規則で uniXXXX，uXXXX[XX]，"." 以降の接尾辞，"_" でつないだ合字を解釈する
+pub fn glyph_name_to_unicode(name: &str, is_zapf_dingbats: bool) -> Option<String> {
+    let name = match name.split_once('.') {
+        Some((base, _)) => base,
+        None => name,
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    if name.contains('_') {
+        return name
+            .split('_')
+            .map(|component| component_to_unicode(component, is_zapf_dingbats))
+            .collect();
+    }
+
+    component_to_unicode(name, is_zapf_dingbats)
+}
+
+fn component_to_unicode(name: &str, is_zapf_dingbats: bool) -> Option<String> {
+    if let Some(text) = lookup(name, is_zapf_dingbats) {
+        return Some(String::from(text));
+    }
+
+    let is_hex = |s: &str| s.bytes().all(|b| b.is_ascii_hexdigit());
+
+    // uni の後に4桁ずつの16進数が並ぶ
+    if let Some(hex) = name.strip_prefix("uni") {
+        if !hex.is_empty() && hex.len().is_multiple_of(4) && is_hex(hex) {
+            return (0..hex.len())
+                .step_by(4)
+                .map(|i| {
+                    u32::from_str_radix(&hex[i..i + 4], 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                })
+                .collect();
+        }
+    }
+
+    // u の後に4から6桁の16進数
+    if let Some(hex) = name.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) && is_hex(hex) {
+            return u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| c.to_string());
+        }
+    }
+
+    None
+}
@@ -0,0 +1,132 @@
+use std::fs::File;
+
+use super::{afm, parse_cap_height, strip_subset_prefix, Error};
+use crate::cross_reference;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// フォントプログラムを読まずに辞書から分かるフォントの情報（pdffontsと同じ項目）
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontInfo {
+    // (オブジェクト番号, 世代番号)．/Resourcesに直接書かれたフォント辞書はNone
+    pub object_ref: Option<(usize, usize)>,
+    // /BaseFont．サブセットの接頭辞も含む．Type3では省略されることがある
+    pub name: Option<String>,
+    // Type1，TrueType，Type0，Type3など
+    pub subtype: String,
+    // Type0の子孫のCIDフォントの/Subtype（CIDFontType0かCIDFontType2）
+    pub descendant_subtype: Option<String>,
+    // 符号化の名前．辞書で指定されていれば "Custom"，省略されていれば "Builtin"
+    pub encoding: String,
+    // フォントプログラムが埋め込まれているか．Type3は常に真
+    pub is_embedded: bool,
+    // 名前に "ABCDEF+" の接頭辞があるか
+    pub is_subset: bool,
+    pub has_to_unicode: bool,
+    // ベースラインから大文字の上端まで（1000分の1単位）
+    // /FontDescriptorの/CapHeight，無ければ標準14フォントのAFMから求める
+    pub cap_height: Option<f64>,
+}
+
+impl FontInfo {
+    pub fn new(
+        object_ref: Option<(usize, usize)>,
+        font_dict: &object::PdfDict,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<FontInfo, Error> {
+        let name = match font_dict.get("BaseFont") {
+            Some(obj) => Some(String::from(
+                object::PdfName::ensure(&object::resolve(obj, file, xref)?)?.as_str(),
+            )),
+            None => None,
+        };
+        let subtype = match font_dict.get("Subtype") {
+            Some(obj) => String::from(object::PdfName::ensure(obj)?.as_str()),
+            None => String::new(),
+        };
+
+        let encoding = match font_dict.get("Encoding") {
+            Some(obj) => Some(object::resolve(obj, file, xref)?),
+            None => None,
+        };
+
+        // Type0では子孫のCIDフォントが/FontDescriptorを持つ
+        let descendant = match font_dict.get("DescendantFonts") {
+            Some(obj) if subtype == "Type0" => {
+                let descendants = object::resolve(obj, file, xref)?;
+                match object::PdfArray::ensure(&descendants)?.get(0) {
+                    Some(obj) => Some(object::resolve(obj, file, xref)?),
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        let descendant = match &descendant {
+            Some(obj) => Some(object::PdfDict::ensure_with_key(obj, vec![])?),
+            None => None,
+        };
+        let descendant_subtype = match descendant.and_then(|d| d.get("Subtype")) {
+            Some(obj) => Some(String::from(object::PdfName::ensure(obj)?.as_str())),
+            None => None,
+        };
+
+        let is_embedded =
+            subtype == "Type3" || has_font_file(descendant.unwrap_or(font_dict), file, xref)?;
+        let cap_height = match parse_cap_height(descendant.unwrap_or(font_dict), file, xref)? {
+            Some(cap_height) => Some(cap_height),
+            None if subtype == "Type1" || subtype == "TrueType" => name
+                .as_deref()
+                .and_then(|name| afm::standard_font_metrics(strip_subset_prefix(name)))
+                .and_then(|metrics| metrics.cap_height),
+            None => None,
+        };
+        let is_subset = name
+            .as_deref()
+            .is_some_and(|name| strip_subset_prefix(name) != name);
+
+        Ok(FontInfo {
+            object_ref,
+            name,
+            subtype,
+            descendant_subtype,
+            encoding: encoding_name(encoding.as_ref()),
+            is_embedded,
+            is_subset,
+            has_to_unicode: font_dict.get("ToUnicode").is_some(),
+            cap_height,
+        })
+    }
+}
+
+// /Encodingは名前，/Differencesを持つ辞書，Type0では埋め込まれたCMapのストリーム
+fn encoding_name(encoding: Option<&Object>) -> String {
+    match encoding {
+        Some(Object::Name(name)) => String::from(name.as_str()),
+        Some(Object::StreamObj(stream)) => match stream.dict.get("CMapName") {
+            Some(Object::Name(name)) => String::from(name.as_str()),
+            _ => String::from("Custom"),
+        },
+        Some(_) => String::from("Custom"),
+        None => String::from("Builtin"),
+    }
+}
+
+fn has_font_file(
+    font_dict: &object::PdfDict,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<bool, Error> {
+    let descriptor = match font_dict.get("FontDescriptor") {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(false),
+    };
+    let descriptor = object::PdfDict::ensure_with_key(&descriptor, vec![])?;
+
+    Ok(["FontFile", "FontFile2", "FontFile3"]
+        .iter()
+        .any(|key| descriptor.get(key).is_some()))
+}
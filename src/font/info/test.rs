@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::test_util;
+
+fn dict(entries: Vec<(&str, Object)>) -> object::PdfDict {
+    let hm: HashMap<String, Object> = entries
+        .into_iter()
+        .map(|(key, obj)| (String::from(key), obj))
+        .collect();
+
+    object::PdfDict::new(hm, 0)
+}
+
+fn name(s: &str) -> Object {
+    Object::Name(object::PdfName::new(String::from(s), 0))
+}
+
+#[test]
+fn encoding_names() {
+    assert_eq!(encoding_name(None), "Builtin");
+    assert_eq!(
+        encoding_name(Some(&name("WinAnsiEncoding"))),
+        "WinAnsiEncoding"
+    );
+
+    // /Differencesを持つ辞書
+    let differences = dict(vec![("BaseEncoding", name("MacRomanEncoding"))]);
+    assert_eq!(encoding_name(Some(&Object::Dict(differences))), "Custom");
+
+    // 埋め込まれたCMapは/CMapNameで示す
+    let cmap = object::PdfStreamObj::new(
+        Object::Dict(dict(vec![
+            ("Length", Object::Integer(object::PdfInteger::new(0, 0))),
+            ("CMapName", name("Adobe-Japan1-UCS2")),
+        ])),
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        encoding_name(Some(&Object::StreamObj(cmap))),
+        "Adobe-Japan1-UCS2"
+    );
+}
+
+#[test]
+fn font_infos() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        test_util::stream("", b"truetype"),
+        b"<< /Type /Font /Subtype /CIDFontType2 /BaseFont /ABCDEF+MSGothic \
+          /FontDescriptor << /FontFile2 1 0 R /CapHeight 860 >> >>"
+            .to_vec(),
+        test_util::stream("", b""),
+    ]);
+    let info = |font: &str, file: &mut File| {
+        let font_obj = test_util::parse(font);
+        let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![]).unwrap();
+        FontInfo::new(Some((10, 0)), font_dict, file, &xref).unwrap()
+    };
+
+    // Type0は子孫のCIDフォントの/FontDescriptorで埋め込みを調べる
+    assert_eq!(
+        info(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /ABCDEF+MSGothic \
+             /Encoding /Identity-H /DescendantFonts [2 0 R] /ToUnicode 3 0 R >>",
+            &mut file,
+        ),
+        FontInfo {
+            object_ref: Some((10, 0)),
+            name: Some(String::from("ABCDEF+MSGothic")),
+            subtype: String::from("Type0"),
+            descendant_subtype: Some(String::from("CIDFontType2")),
+            encoding: String::from("Identity-H"),
+            is_embedded: true,
+            is_subset: true,
+            has_to_unicode: true,
+            cap_height: Some(860.0),
+        }
+    );
+
+    assert_eq!(
+        info(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica \
+             /Encoding << /Differences [65 /alpha] >> >>",
+            &mut file,
+        ),
+        FontInfo {
+            object_ref: Some((10, 0)),
+            name: Some(String::from("Helvetica")),
+            subtype: String::from("Type1"),
+            descendant_subtype: None,
+            encoding: String::from("Custom"),
+            is_embedded: false,
+            is_subset: false,
+            has_to_unicode: false,
+            cap_height: Some(718.0),
+        }
+    );
+
+    // Type3は/BaseFontが無くても，常に埋め込まれているものとする
+    let type3 = info(
+        "<< /Type /Font /Subtype /Type3 /FontBBox [0 0 1 1] /FontMatrix [1 0 0 1 0 0] \
+         /CharProcs << >> >>",
+        &mut file,
+    );
+    assert_eq!(type3.name, None);
+    assert_eq!(type3.encoding, "Builtin");
+    assert!(type3.is_embedded);
+    assert_eq!(type3.cap_height, None);
+
+    // /CapHeightがAFMより優先し，0は分からないものとする
+    let times = info(
+        "<< /Type /Font /Subtype /TrueType /BaseFont /TimesNewRomanPSMT \
+         /FontDescriptor << /CapHeight 650 >> >>",
+        &mut file,
+    );
+    assert_eq!(times.cap_height, Some(650.0));
+    let symbol = info(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Symbol /FontDescriptor << /CapHeight 0 >> >>",
+        &mut file,
+    );
+    assert_eq!(symbol.cap_height, None);
+}
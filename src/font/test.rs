@@ -0,0 +1,163 @@
+use super::*;
+use crate::test_util;
+
+fn name(s: &str) -> Object {
+    Object::Name(object::PdfName::new(String::from(s), 0))
+}
+
+fn integer(i: isize) -> Object {
+    Object::Integer(object::PdfInteger::new(i, 0))
+}
+
+#[test]
+fn base_encodings() {
+    let standard = SimpleEncoding::new(BaseEncoding::Standard);
+    let win_ansi = SimpleEncoding::new(BaseEncoding::WinAnsi);
+    let mac_roman = SimpleEncoding::new(BaseEncoding::MacRoman);
+
+    assert_eq!(standard.to_unicode(0x27).as_deref(), Some("\u{2019}"));
+    assert_eq!(standard.to_unicode(0xae).as_deref(), Some("\u{fb01}"));
+    assert_eq!(win_ansi.to_unicode(0x27).as_deref(), Some("'"));
+    assert_eq!(win_ansi.to_unicode(0x80).as_deref(), Some("\u{20ac}"));
+    assert_eq!(win_ansi.to_unicode(0xe9).as_deref(), Some("é"));
+    assert_eq!(mac_roman.to_unicode(0x8e).as_deref(), Some("é"));
+    assert_eq!(standard.to_unicode(0x80), None);
+}
+
+#[test]
+fn builtin_symbol_and_zapf_dingbats() {
+    assert_eq!(BaseEncoding::builtin("Symbol"), BaseEncoding::Symbol);
+    assert_eq!(
+        BaseEncoding::builtin("ZapfDingbats"),
+        BaseEncoding::ZapfDingbats
+    );
+    assert_eq!(BaseEncoding::builtin("Helvetica"), BaseEncoding::Standard);
+
+    let symbol = SimpleEncoding::new(BaseEncoding::Symbol);
+    assert_eq!(symbol.to_unicode(0x61).as_deref(), Some("α"));
+    assert_eq!(symbol.to_unicode(0x53).as_deref(), Some("Σ"));
+
+    let zapf_dingbats = SimpleEncoding::new(BaseEncoding::ZapfDingbats);
+    assert_eq!(zapf_dingbats.glyph_name(0x21), Some("a1"));
+    assert_eq!(zapf_dingbats.to_unicode(0x21).as_deref(), Some("\u{2701}"));
+    assert_eq!(zapf_dingbats.to_unicode(0x6c).as_deref(), Some("\u{25cf}"));
+}
+
+#[test]
+fn differences() {
+    let mut encoding = SimpleEncoding::new(BaseEncoding::WinAnsi);
+    let differences = object::PdfArray::new(
+        vec![
+            integer(65),
+            name("alpha"),
+            name("uni263A"),
+            integer(128),
+            name("f_f_i"),
+            name("A.sc"),
+            name("g12"),
+            name("afii10017"),
+            name("afii10110"),
+            name("dalethatafpatah"),
+        ],
+        0,
+    );
+
+    encoding.apply_differences(&differences).unwrap();
+
+    assert_eq!(encoding.to_unicode(65).as_deref(), Some("α"));
+    assert_eq!(encoding.to_unicode(66).as_deref(), Some("\u{263a}"));
+    assert_eq!(encoding.to_unicode(67).as_deref(), Some("C"));
+    assert_eq!(encoding.to_unicode(128).as_deref(), Some("ffi"));
+    assert_eq!(encoding.to_unicode(129).as_deref(), Some("A"));
+    assert_eq!(encoding.to_unicode(130), None);
+    // キリル文字やヘブライ文字もAdobe Glyph Listで引ける
+    assert_eq!(encoding.to_unicode(131).as_deref(), Some("А"));
+    assert_eq!(encoding.to_unicode(132).as_deref(), Some("ў"));
+    assert_eq!(encoding.to_unicode(133).as_deref(), Some("\u{5d3}\u{5b2}"));
+}
+
+#[test]
+fn subset_prefix() {
+    assert_eq!(strip_subset_prefix("ABCDEF+Helvetica"), "Helvetica");
+    assert_eq!(strip_subset_prefix("Helvetica"), "Helvetica");
+    assert_eq!(strip_subset_prefix("abcdef+Helvetica"), "abcdef+Helvetica");
+}
+
+#[test]
+fn standard_font_metrics() {
+    let helvetica = afm::standard_font_metrics("Helvetica").unwrap();
+    assert_eq!(helvetica.width("space"), Some(278.0));
+    assert_eq!(helvetica.width("W"), Some(944.0));
+    // 符号化されていないアクセント付きの文字もAFMの字幅を使う
+    assert_eq!(helvetica.width("eacute"), Some(556.0));
+    assert_eq!(helvetica.width("Aacute"), Some(667.0));
+    assert_eq!(helvetica.width("Idieresis"), Some(278.0));
+    assert_eq!(helvetica.extent(), (718.0, -207.0));
+    assert_eq!(helvetica.cap_height, Some(718.0));
+    assert_eq!(helvetica.bbox, [-166.0, -225.0, 1000.0, 931.0]);
+
+    // 別名とスタイル
+    let bold = afm::standard_font_metrics("Arial,Bold").unwrap();
+    assert_eq!(bold.width("b"), Some(611.0));
+    let times = afm::standard_font_metrics("TimesNewRomanPS-ItalicMT").unwrap();
+    assert_eq!(times.width("A"), Some(611.0));
+    let courier = afm::standard_font_metrics("CourierNew").unwrap();
+    assert_eq!(courier.width("m"), Some(600.0));
+
+    // AscenderとDescenderの無いフォントはFontBBoxを使う
+    let symbol = afm::standard_font_metrics("Symbol").unwrap();
+    assert_eq!(symbol.width("alpha"), Some(631.0));
+    assert_eq!(symbol.width("Omega"), Some(768.0));
+    assert_eq!(symbol.width("apple"), Some(790.0));
+    assert_eq!(symbol.extent(), (1010.0, -293.0));
+    assert_eq!(symbol.cap_height, None);
+    let dingbats = afm::standard_font_metrics("ZapfDingbats").unwrap();
+    assert_eq!(dingbats.width("a191"), Some(918.0));
+
+    assert!(afm::standard_font_metrics("MSGothic").is_none());
+}
+
+#[test]
+fn fall_back_on_broken_to_unicode() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        test_util::stream("/Filter /FlateDecode", b"not deflated"),
+        test_util::stream("", b"begincmap 1 beginbfchar 65 <0042> endbfchar endcmap"),
+    ]);
+
+    for to_unicode in ["1 0 R", "2 0 R"] {
+        let font_obj = test_util::parse(&format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /ToUnicode {} >>",
+            to_unicode
+        ));
+        let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![]).unwrap();
+        let font = Font::new(font_dict, &mut file, &xref).unwrap();
+
+        let glyphs = font.decode(b"A");
+        assert_eq!(glyphs.len(), 1);
+        assert_eq!(glyphs[0].text, "A");
+    }
+}
+
+#[test]
+fn unknown_cmap_uses_to_unicode() {
+    let (mut file, xref) = test_util::write_pdf(&[test_util::stream(
+        "",
+        b"begincmap 1 begincodespacerange <0000> <FFFF> endcodespacerange \
+          1 beginbfchar <B0A1> <554A> endbfchar endcmap",
+    )]);
+
+    let font_obj = test_util::parse(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /SimSun /Encoding /GBK-EUC-H \
+         /DescendantFonts [<< /Type /Font /Subtype /CIDFontType0 /BaseFont /SimSun \
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> >>] \
+         /ToUnicode 1 0 R >>",
+    );
+    let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![]).unwrap();
+    let font = Font::new(font_dict, &mut file, &xref).unwrap();
+
+    // Identityとみなすと<B0A1>はCID 45217になるが，/ToUnicodeの文字だけを使う
+    let glyphs = font.decode(&[0xb0, 0xa1, 0xb0, 0xa2]);
+    assert_eq!(glyphs.len(), 2);
+    assert_eq!(glyphs[0].text, "啊");
+    assert_eq!(glyphs[1].text, "");
+}
@@ -0,0 +1,521 @@
+use std::fmt;
+use std::fs::File;
+
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::parser::Object;
+use postscript::Program;
+
+mod postscript;
+#[cfg(test)]
+mod test;
+
+// 型3関数の/Functionsを入れ子にできる深さ
+// 自身を参照するような壊れた関数で無限に再帰しないようにする
+const MAX_STITCHING_DEPTH: usize = 16;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    Filter(filter::Error),
+    UnsupporttedFunctionType(isize),
+    InvalidFunction(&'static str),
+    PostScript(postscript::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::Filter(e) => write!(f, "filter: {}", e),
+            Error::UnsupporttedFunctionType(t) => {
+                write!(f, "function type `{}` is not supportted", t)
+            }
+            Error::InvalidFunction(s) => write!(f, "invalid function: {}", s),
+            Error::PostScript(e) => write!(f, "postscript calculator: {}", e),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+impl From<filter::Error> for Error {
+    fn from(e: filter::Error) -> Self {
+        Self::Filter(e)
+    }
+}
+impl From<postscript::Error> for Error {
+    fn from(e: postscript::Error) -> Self {
+        Self::PostScript(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FunctionKind {
+    // 型0: サンプル値の表を補間する
+    Sampled {
+        size: Vec<usize>,
+        bits_per_sample: usize,
+        encode: Vec<f64>,
+        decode: Vec<f64>,
+        samples: Vec<u8>,
+    },
+    // 型2: C0 + x^N * (C1 - C0)
+    Exponential {
+        c0: Vec<f64>,
+        c1: Vec<f64>,
+        n: f64,
+    },
+    // 型3: 定義域を分割してそれぞれに別の関数を割り当てる
+    Stitching {
+        functions: Vec<Function>,
+        bounds: Vec<f64>,
+        encode: Vec<f64>,
+    },
+    // 型4: PostScript言語のサブセットで書かれた関数
+    PostScriptCalculator(Program),
+}
+
+// 関数辞書や関数ストリームで定義されるPDFの関数
+// cf. 仕様書 3.9 Functions
+#[derive(Debug, Clone)]
+pub struct Function {
+    domain: Vec<f64>,
+    range: Option<Vec<f64>>,
+    kind: FunctionKind,
+}
+
+impl Function {
+    pub fn parse(
+        function_obj: &Object,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<Function, Error> {
+        Self::parse_nested(function_obj, file, xref, 0)
+    }
+
+    fn parse_nested(
+        function_obj: &Object,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+        depth: usize,
+    ) -> Result<Function, Error> {
+        let function_obj = object::resolve(function_obj, file, xref)?;
+
+        // 型0と型4はストリームで，それ以外は辞書で与えられる
+        let dict = match &function_obj {
+            Object::StreamObj(stream_obj) => &stream_obj.dict,
+            obj => object::PdfDict::ensure_with_key(obj, vec![])?,
+        };
+
+        dict.assert_with_key(vec!["FunctionType", "Domain"])?;
+
+        let function_type = object::PdfInteger::ensure(dict.get("FunctionType").unwrap())?;
+        let domain = parse_number_array(dict.get("Domain").unwrap())?;
+        let range = match dict.get("Range") {
+            Some(range_obj) => Some(parse_number_array(range_obj)?),
+            None => None,
+        };
+
+        if domain.is_empty() || domain.len() % 2 != 0 {
+            return Err(Error::InvalidFunction("domain must have 2 * m numbers"));
+        }
+
+        let kind = match function_type.unpack() {
+            0 => {
+                let stream_obj = object::PdfStreamObj::ensure_stream(&function_obj)?;
+                let samples = filter::decode_stream(stream_obj, file, xref)?;
+
+                parse_sampled(dict, &domain, &range, samples)?
+            }
+            2 => parse_exponential(dict)?,
+            3 => parse_stitching(dict, &domain, file, xref, depth)?,
+            4 => {
+                let stream_obj = object::PdfStreamObj::ensure_stream(&function_obj)?;
+                let program = filter::decode_stream(stream_obj, file, xref)?;
+
+                FunctionKind::PostScriptCalculator(Program::parse(&program)?)
+            }
+            t => return Err(Error::UnsupporttedFunctionType(t)),
+        };
+
+        // 型0と型4では/Rangeが必須
+        if matches!(
+            kind,
+            FunctionKind::Sampled { .. } | FunctionKind::PostScriptCalculator(_)
+        ) && range.is_none()
+        {
+            return Err(Error::InvalidFunction("range is required"));
+        }
+
+        // 出力の数は/Rangeから決めるので，関数が実際に返す値の数と一致していなければならない
+        let n_outputs = match &kind {
+            FunctionKind::Sampled { decode, .. } => Some(decode.len() / 2),
+            FunctionKind::Exponential { c0, .. } => Some(c0.len()),
+            FunctionKind::Stitching { functions, .. } => Some(functions[0].n_outputs()),
+            FunctionKind::PostScriptCalculator(_) => None,
+        };
+        if let (Some(range), Some(n)) = (&range, n_outputs) {
+            if range.len() != 2 * n {
+                return Err(Error::InvalidFunction("range and outputs are inconsistent"));
+            }
+        }
+
+        Ok(Function {
+            domain,
+            range,
+            kind,
+        })
+    }
+
+    pub fn n_inputs(&self) -> usize {
+        self.domain.len() / 2
+    }
+
+    pub fn n_outputs(&self) -> usize {
+        match &self.range {
+            Some(range) => range.len() / 2,
+            None => match &self.kind {
+                FunctionKind::Sampled { decode, .. } => decode.len() / 2,
+                FunctionKind::Exponential { c0, .. } => c0.len(),
+                FunctionKind::Stitching { functions, .. } => functions[0].n_outputs(),
+                // /Rangeが必須なのでここには来ない
+                FunctionKind::PostScriptCalculator(_) => 0,
+            },
+        }
+    }
+
+    pub fn eval(&self, input: &[f64]) -> Vec<f64> {
+        let input: Vec<f64> = (0..self.n_inputs())
+            .map(|i| {
+                let x = input.get(i).copied().unwrap_or(0.0);
+                clip(x, self.domain[2 * i], self.domain[2 * i + 1])
+            })
+            .collect();
+
+        let mut output = match &self.kind {
+            FunctionKind::Sampled {
+                size,
+                bits_per_sample,
+                encode,
+                decode,
+                samples,
+            } => eval_sampled(
+                &input,
+                &self.domain,
+                size,
+                *bits_per_sample,
+                encode,
+                decode,
+                samples,
+            ),
+            FunctionKind::Exponential { c0, c1, n } => {
+                let x = input[0].powf(*n);
+                c0.iter()
+                    .zip(c1.iter())
+                    .map(|(c0, c1)| c0 + x * (c1 - c0))
+                    .collect::<Vec<f64>>()
+            }
+            FunctionKind::Stitching {
+                functions,
+                bounds,
+                encode,
+            } => {
+                let x = input[0];
+
+                // x が属する区間を探す
+                let k = bounds.iter().filter(|b| x >= **b).count();
+
+                let low = if k == 0 {
+                    self.domain[0]
+                } else {
+                    bounds[k - 1]
+                };
+                let high = if k == bounds.len() {
+                    self.domain[1]
+                } else {
+                    bounds[k]
+                };
+
+                let x = interpolate(x, low, high, encode[2 * k], encode[2 * k + 1]);
+                functions[k].eval(&[x])
+            }
+            FunctionKind::PostScriptCalculator(program) => {
+                // 評価に失敗したときは値域の下限を返す
+                let n = self.n_outputs();
+                match program.execute(&input) {
+                    Ok(output) if output.len() >= n => output[(output.len() - n)..].to_vec(),
+                    _ => match &self.range {
+                        Some(range) => range.iter().step_by(2).copied().collect(),
+                        None => vec![0.0; n],
+                    },
+                }
+            }
+        };
+
+        if let Some(range) = &self.range {
+            for (i, v) in output.iter_mut().enumerate() {
+                if 2 * i + 1 < range.len() {
+                    *v = clip(*v, range[2 * i], range[2 * i + 1]);
+                }
+            }
+        }
+
+        output
+    }
+}
+
+fn parse_sampled(
+    dict: &object::PdfDict,
+    domain: &[f64],
+    range: &Option<Vec<f64>>,
+    samples: Vec<u8>,
+) -> Result<FunctionKind, Error> {
+    dict.assert_with_key(vec!["Size", "BitsPerSample"])?;
+
+    let m = domain.len() / 2;
+
+    let size_array = object::PdfArray::ensure(dict.get("Size").unwrap())?;
+    let mut size = vec![];
+    for size_obj in size_array {
+        let s = object::PdfInteger::ensure(size_obj)?;
+        s.assert_natural()?;
+        size.push(s.unpack() as usize);
+    }
+    if size.len() != m {
+        return Err(Error::InvalidFunction("size must have m integers"));
+    }
+
+    let bits_per_sample = object::PdfInteger::ensure(dict.get("BitsPerSample").unwrap())?.unpack();
+    if !matches!(bits_per_sample, 1 | 2 | 4 | 8 | 12 | 16 | 24 | 32) {
+        return Err(Error::InvalidFunction("invalid bits per sample"));
+    }
+    let bits_per_sample = bits_per_sample as usize;
+
+    // /Orderが3（3次スプライン補間）であっても線形補間で近似する
+    let encode = match dict.get("Encode") {
+        Some(obj) => parse_number_array(obj)?,
+        None => size
+            .iter()
+            .flat_map(|s| vec![0.0, (s - 1) as f64])
+            .collect(),
+    };
+    if encode.len() != 2 * m {
+        return Err(Error::InvalidFunction("encode must have 2 * m numbers"));
+    }
+
+    let decode = match (dict.get("Decode"), range) {
+        (Some(obj), _) => parse_number_array(obj)?,
+        (None, Some(range)) => range.clone(),
+        (None, None) => return Err(Error::InvalidFunction("range is required")),
+    };
+    let n = decode.len() / 2;
+
+    // 巨大な/Sizeで桁あふれしないようにする
+    let sample_bits = size
+        .iter()
+        .try_fold(n * bits_per_sample, |bits, s| bits.checked_mul(*s))
+        .ok_or(Error::InvalidFunction("sample table is too large"))?;
+    if samples.len().saturating_mul(8) < sample_bits {
+        return Err(Error::InvalidFunction("sample data is too short"));
+    }
+
+    Ok(FunctionKind::Sampled {
+        size,
+        bits_per_sample,
+        encode,
+        decode,
+        samples,
+    })
+}
+
+fn eval_sampled(
+    input: &[f64],
+    domain: &[f64],
+    size: &[usize],
+    bits_per_sample: usize,
+    encode: &[f64],
+    decode: &[f64],
+    samples: &[u8],
+) -> Vec<f64> {
+    let m = size.len();
+    let n = decode.len() / 2;
+
+    // 各入力を表のインデックス（小数）に写す
+    let mut index = vec![];
+    let mut fraction = vec![];
+    for i in 0..m {
+        let e = interpolate(
+            input[i],
+            domain[2 * i],
+            domain[2 * i + 1],
+            encode[2 * i],
+            encode[2 * i + 1],
+        );
+        let e = clip(e, 0.0, (size[i] - 1) as f64);
+
+        let floor = (e.floor() as usize).min(size[i] - 1);
+        index.push(floor);
+        fraction.push(e - floor as f64);
+    }
+
+    // 周囲2^m点のサンプル値を多重線形補間する
+    // 入力が多すぎて2^mが表せないときは値域の下限を返す
+    let corners = match 1usize.checked_shl(m as u32) {
+        Some(corners) => corners,
+        None => return decode.iter().step_by(2).copied().collect(),
+    };
+    let max_sample = ((1u64 << bits_per_sample) - 1) as f64;
+    let mut output = vec![0.0; n];
+    for corner in 0..corners {
+        let mut weight = 1.0;
+        let mut offset = 0;
+        let mut stride = 1;
+
+        for i in 0..m {
+            let is_upper = corner & (1 << i) != 0;
+            let position = if is_upper {
+                weight *= fraction[i];
+                (index[i] + 1).min(size[i] - 1)
+            } else {
+                weight *= 1.0 - fraction[i];
+                index[i]
+            };
+
+            offset += position * stride;
+            stride *= size[i];
+        }
+
+        if weight == 0.0 {
+            continue;
+        }
+
+        for (j, v) in output.iter_mut().enumerate() {
+            let sample = read_sample(samples, (offset * n + j) * bits_per_sample, bits_per_sample);
+            *v += weight * sample as f64;
+        }
+    }
+
+    output
+        .iter()
+        .enumerate()
+        .map(|(j, v)| interpolate(*v, 0.0, max_sample, decode[2 * j], decode[2 * j + 1]))
+        .collect()
+}
+
+// ビット単位のオフセットから上位ビット優先でサンプル値を読む
+fn read_sample(samples: &[u8], bit_offset: usize, bits: usize) -> u32 {
+    let mut value: u32 = 0;
+
+    for bit in bit_offset..(bit_offset + bits) {
+        let byte = samples.get(bit / 8).copied().unwrap_or(0);
+        let b = (byte >> (7 - bit % 8)) & 1;
+        value = (value << 1) | b as u32;
+    }
+
+    value
+}
+
+fn parse_exponential(dict: &object::PdfDict) -> Result<FunctionKind, Error> {
+    dict.assert_with_key(vec!["N"])?;
+
+    let c0 = match dict.get("C0") {
+        Some(obj) => parse_number_array(obj)?,
+        None => vec![0.0],
+    };
+    let c1 = match dict.get("C1") {
+        Some(obj) => parse_number_array(obj)?,
+        None => vec![1.0],
+    };
+    let n = object::PdfReal::ensure_number(dict.get("N").unwrap())?;
+
+    if c0.len() != c1.len() {
+        return Err(Error::InvalidFunction("C0 and C1 must have same length"));
+    }
+
+    Ok(FunctionKind::Exponential { c0, c1, n })
+}
+
+fn parse_stitching(
+    dict: &object::PdfDict,
+    domain: &[f64],
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<FunctionKind, Error> {
+    dict.assert_with_key(vec!["Functions", "Bounds", "Encode"])?;
+
+    if depth >= MAX_STITCHING_DEPTH {
+        return Err(Error::InvalidFunction(
+            "stitching functions are nested too deeply",
+        ));
+    }
+
+    if domain.len() != 2 {
+        return Err(Error::InvalidFunction(
+            "stitching function must have 1 input",
+        ));
+    }
+
+    let functions_obj = object::resolve(dict.get("Functions").unwrap(), file, xref)?;
+    let mut functions = vec![];
+    for function_obj in object::PdfArray::ensure(&functions_obj)? {
+        functions.push(Function::parse_nested(function_obj, file, xref, depth + 1)?);
+    }
+
+    let bounds = parse_number_array(dict.get("Bounds").unwrap())?;
+    let encode = parse_number_array(dict.get("Encode").unwrap())?;
+
+    let k = functions.len();
+    if k == 0 || bounds.len() != k - 1 || encode.len() != 2 * k {
+        return Err(Error::InvalidFunction(
+            "functions, bounds and encode are inconsistent",
+        ));
+    }
+    if functions
+        .iter()
+        .any(|f| f.n_inputs() != 1 || f.n_outputs() != functions[0].n_outputs())
+    {
+        return Err(Error::InvalidFunction(
+            "stitched functions must have 1 input and same number of outputs",
+        ));
+    }
+
+    Ok(FunctionKind::Stitching {
+        functions,
+        bounds,
+        encode,
+    })
+}
+
+fn parse_number_array(obj: &Object) -> Result<Vec<f64>, Error> {
+    let array = object::PdfArray::ensure(obj)?;
+
+    let mut numbers = vec![];
+    for number_obj in array {
+        numbers.push(object::PdfReal::ensure_number(number_obj)?);
+    }
+
+    Ok(numbers)
+}
+
+// [x_min, x_max] から [y_min, y_max] へ線形に写す
+fn interpolate(x: f64, x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> f64 {
+    if x_max == x_min {
+        y_min
+    } else {
+        y_min + (x - x_min) * (y_max - y_min) / (x_max - x_min)
+    }
+}
+
+fn clip(x: f64, min: f64, max: f64) -> f64 {
+    if x < min {
+        min
+    } else if x > max {
+        max
+    } else {
+        x
+    }
+}
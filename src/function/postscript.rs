@@ -0,0 +1,475 @@
+use std::fmt;
+
+// 型4関数で使われるPostScript言語のサブセット
+// cf. 仕様書 3.9.4 Type 4 (PostScript Calculator) Functions
+
+// if/ifelseの手続きを入れ子にできる深さ
+const MAX_BLOCK_DEPTH: usize = 32;
+// オペランドスタックに積める値の数
+// cf. 仕様書 Appendix C Implementation Limits
+const MAX_STACK_SIZE: usize = 100;
+
+#[derive(Debug)]
+pub enum Error {
+    UnbalancedBrace,
+    TooDeepNesting,
+    UndefinedOperator(String),
+    StackUnderflow,
+    StackOverflow,
+    TypeCheck,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::UnbalancedBrace => write!(f, "braces are not balanced"),
+            Error::TooDeepNesting => write!(f, "procedures are nested too deeply"),
+            Error::UndefinedOperator(s) => write!(f, "operator `{}` is undefined", s),
+            Error::StackUnderflow => write!(f, "stack underflow"),
+            Error::StackOverflow => write!(f, "stack overflow"),
+            Error::TypeCheck => write!(f, "operand type missmatch"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Number(f64),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Abs,
+    Add,
+    Atan,
+    Ceiling,
+    Cos,
+    Cvi,
+    Cvr,
+    Div,
+    Exp,
+    Floor,
+    Idiv,
+    Ln,
+    Log,
+    Mod,
+    Mul,
+    Neg,
+    Round,
+    Sin,
+    Sqrt,
+    Sub,
+    Truncate,
+    And,
+    Bitshift,
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Ne,
+    Not,
+    Or,
+    Xor,
+    Copy,
+    Dup,
+    Exch,
+    Index,
+    Pop,
+    Roll,
+}
+
+impl Operator {
+    fn from_name(name: &str) -> Option<Operator> {
+        Some(match name {
+            "abs" => Operator::Abs,
+            "add" => Operator::Add,
+            "atan" => Operator::Atan,
+            "ceiling" => Operator::Ceiling,
+            "cos" => Operator::Cos,
+            "cvi" => Operator::Cvi,
+            "cvr" => Operator::Cvr,
+            "div" => Operator::Div,
+            "exp" => Operator::Exp,
+            "floor" => Operator::Floor,
+            "idiv" => Operator::Idiv,
+            "ln" => Operator::Ln,
+            "log" => Operator::Log,
+            "mod" => Operator::Mod,
+            "mul" => Operator::Mul,
+            "neg" => Operator::Neg,
+            "round" => Operator::Round,
+            "sin" => Operator::Sin,
+            "sqrt" => Operator::Sqrt,
+            "sub" => Operator::Sub,
+            "truncate" => Operator::Truncate,
+            "and" => Operator::And,
+            "bitshift" => Operator::Bitshift,
+            "eq" => Operator::Eq,
+            "ge" => Operator::Ge,
+            "gt" => Operator::Gt,
+            "le" => Operator::Le,
+            "lt" => Operator::Lt,
+            "ne" => Operator::Ne,
+            "not" => Operator::Not,
+            "or" => Operator::Or,
+            "xor" => Operator::Xor,
+            "copy" => Operator::Copy,
+            "dup" => Operator::Dup,
+            "exch" => Operator::Exch,
+            "index" => Operator::Index,
+            "pop" => Operator::Pop,
+            "roll" => Operator::Roll,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Instruction {
+    Push(Value),
+    Operator(Operator),
+    If(Vec<Instruction>),
+    IfElse(Vec<Instruction>, Vec<Instruction>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn parse(buffer: &[u8]) -> Result<Program, Error> {
+        let tokens = tokenize(buffer);
+        let mut i = 0;
+
+        // プログラム全体は { } で囲まれている
+        if tokens.first().map(|t| t.as_str()) != Some("{") {
+            return Err(Error::UnbalancedBrace);
+        }
+        i += 1;
+
+        let instructions = parse_block(&tokens, &mut i, 0)?;
+
+        Ok(Program { instructions })
+    }
+
+    pub fn execute(&self, input: &[f64]) -> Result<Vec<f64>, Error> {
+        let mut stack: Vec<Value> = input.iter().map(|v| Value::Number(*v)).collect();
+        if stack.len() > MAX_STACK_SIZE {
+            return Err(Error::StackOverflow);
+        }
+
+        execute_block(&self.instructions, &mut stack)?;
+
+        let mut output = vec![];
+        for value in stack {
+            match value {
+                Value::Number(v) => output.push(v),
+                Value::Boolean(_) => return Err(Error::TypeCheck),
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn tokenize(buffer: &[u8]) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+
+    for byte in buffer {
+        let c = char::from(*byte);
+
+        if c.is_ascii_whitespace() || c == '{' || c == '}' {
+            if !token.is_empty() {
+                tokens.push(token);
+                token = String::new();
+            }
+
+            if c == '{' || c == '}' {
+                tokens.push(String::from(c));
+            }
+        } else {
+            token.push(c);
+        }
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+// 開き括弧の直後から対応する閉じ括弧までを読む
+fn parse_block(tokens: &[String], i: &mut usize, depth: usize) -> Result<Vec<Instruction>, Error> {
+    if depth > MAX_BLOCK_DEPTH {
+        return Err(Error::TooDeepNesting);
+    }
+
+    let mut instructions = vec![];
+    // if/ifelseの手続きとして使われる直前のブロック
+    let mut procedures: Vec<Vec<Instruction>> = vec![];
+
+    while *i < tokens.len() {
+        let token = tokens[*i].as_str();
+        *i += 1;
+
+        match token {
+            "}" => {
+                if !procedures.is_empty() {
+                    return Err(Error::UnbalancedBrace);
+                }
+                return Ok(instructions);
+            }
+            "{" => procedures.push(parse_block(tokens, i, depth + 1)?),
+            "if" => {
+                if procedures.len() != 1 {
+                    return Err(Error::TypeCheck);
+                }
+                instructions.push(Instruction::If(procedures.pop().unwrap()));
+            }
+            "ifelse" => {
+                if procedures.len() != 2 {
+                    return Err(Error::TypeCheck);
+                }
+                let else_proc = procedures.pop().unwrap();
+                let if_proc = procedures.pop().unwrap();
+                instructions.push(Instruction::IfElse(if_proc, else_proc));
+            }
+            "true" => instructions.push(Instruction::Push(Value::Boolean(true))),
+            "false" => instructions.push(Instruction::Push(Value::Boolean(false))),
+            _ => {
+                if !procedures.is_empty() {
+                    return Err(Error::TypeCheck);
+                }
+
+                // Rustの解析では inf や NaN も数になるので有限の値だけを受け付ける
+                if let Some(number) = token.parse::<f64>().ok().filter(|v| v.is_finite()) {
+                    instructions.push(Instruction::Push(Value::Number(number)));
+                } else if let Some(operator) = Operator::from_name(token) {
+                    instructions.push(Instruction::Operator(operator));
+                } else {
+                    return Err(Error::UndefinedOperator(String::from(token)));
+                }
+            }
+        }
+    }
+
+    Err(Error::UnbalancedBrace)
+}
+
+fn execute_block(instructions: &[Instruction], stack: &mut Vec<Value>) -> Result<(), Error> {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Push(value) => stack.push(*value),
+            Instruction::Operator(operator) => execute_operator(*operator, stack)?,
+            Instruction::If(proc) => {
+                if pop_boolean(stack)? {
+                    execute_block(proc, stack)?;
+                }
+            }
+            Instruction::IfElse(if_proc, else_proc) => {
+                if pop_boolean(stack)? {
+                    execute_block(if_proc, stack)?;
+                } else {
+                    execute_block(else_proc, stack)?;
+                }
+            }
+        }
+
+        if stack.len() > MAX_STACK_SIZE {
+            return Err(Error::StackOverflow);
+        }
+    }
+
+    Ok(())
+}
+
+fn pop(stack: &mut Vec<Value>) -> Result<Value, Error> {
+    match stack.pop() {
+        Some(value) => Ok(value),
+        None => Err(Error::StackUnderflow),
+    }
+}
+
+fn pop_number(stack: &mut Vec<Value>) -> Result<f64, Error> {
+    match pop(stack)? {
+        Value::Number(v) => Ok(v),
+        Value::Boolean(_) => Err(Error::TypeCheck),
+    }
+}
+
+fn pop_boolean(stack: &mut Vec<Value>) -> Result<bool, Error> {
+    match pop(stack)? {
+        Value::Boolean(b) => Ok(b),
+        Value::Number(_) => Err(Error::TypeCheck),
+    }
+}
+
+fn pop_integer(stack: &mut Vec<Value>) -> Result<i64, Error> {
+    Ok(pop_number(stack)?.trunc() as i64)
+}
+
+fn execute_operator(operator: Operator, stack: &mut Vec<Value>) -> Result<(), Error> {
+    use Operator::*;
+
+    match operator {
+        Abs | Ceiling | Cos | Cvi | Cvr | Floor | Ln | Log | Neg | Round | Sin | Sqrt
+        | Truncate => {
+            let x = pop_number(stack)?;
+            let y = match operator {
+                Abs => x.abs(),
+                Ceiling => x.ceil(),
+                // 引数は度数法
+                Cos => x.to_radians().cos(),
+                Cvi | Truncate => x.trunc(),
+                Cvr => x,
+                Floor => x.floor(),
+                Ln => x.ln(),
+                Log => x.log10(),
+                Neg => -x,
+                // PostScriptのroundは .5 を大きい方へ丸める
+                Round => (x + 0.5).floor(),
+                Sin => x.to_radians().sin(),
+                Sqrt => x.sqrt(),
+                _ => unreachable!(),
+            };
+            stack.push(Value::Number(y));
+        }
+        Add | Atan | Div | Exp | Mul | Sub => {
+            let b = pop_number(stack)?;
+            let a = pop_number(stack)?;
+            let y = match operator {
+                Add => a + b,
+                // 結果は0以上360未満の角度
+                Atan => {
+                    let angle = a.atan2(b).to_degrees();
+                    if angle < 0.0 {
+                        angle + 360.0
+                    } else {
+                        angle
+                    }
+                }
+                Div => a / b,
+                Exp => a.powf(b),
+                Mul => a * b,
+                Sub => a - b,
+                _ => unreachable!(),
+            };
+            stack.push(Value::Number(y));
+        }
+        Idiv | Mod | Bitshift => {
+            let b = pop_integer(stack)?;
+            let a = pop_integer(stack)?;
+            let y = match operator {
+                // 0除算と i64::MIN / -1 は結果が定まらない
+                Idiv => a.checked_div(b).ok_or(Error::TypeCheck)?,
+                Mod => a.checked_rem(b).ok_or(Error::TypeCheck)?,
+                // 64ビット以上ずらすとすべてのビットがあふれる
+                Bitshift if b >= 0 => u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_shl(b))
+                    .unwrap_or(0),
+                Bitshift => u32::try_from(b.unsigned_abs())
+                    .ok()
+                    .and_then(|b| a.checked_shr(b))
+                    .unwrap_or(0),
+                _ => unreachable!(),
+            };
+            stack.push(Value::Number(y as f64));
+        }
+        Eq | Ne => {
+            let b = pop(stack)?;
+            let a = pop(stack)?;
+            let y = a == b;
+            stack.push(Value::Boolean(if operator == Eq { y } else { !y }));
+        }
+        Ge | Gt | Le | Lt => {
+            let b = pop_number(stack)?;
+            let a = pop_number(stack)?;
+            let y = match operator {
+                Ge => a >= b,
+                Gt => a > b,
+                Le => a <= b,
+                Lt => a < b,
+                _ => unreachable!(),
+            };
+            stack.push(Value::Boolean(y));
+        }
+        And | Or | Xor => {
+            let b = pop(stack)?;
+            let a = pop(stack)?;
+            let y = match (a, b) {
+                (Value::Boolean(a), Value::Boolean(b)) => Value::Boolean(match operator {
+                    And => a && b,
+                    Or => a || b,
+                    _ => a ^ b,
+                }),
+                (Value::Number(a), Value::Number(b)) => {
+                    let (a, b) = (a as i64, b as i64);
+                    Value::Number(match operator {
+                        And => a & b,
+                        Or => a | b,
+                        _ => a ^ b,
+                    } as f64)
+                }
+                _ => return Err(Error::TypeCheck),
+            };
+            stack.push(y);
+        }
+        Not => {
+            let y = match pop(stack)? {
+                Value::Boolean(b) => Value::Boolean(!b),
+                Value::Number(v) => Value::Number(!(v as i64) as f64),
+            };
+            stack.push(y);
+        }
+        Copy => {
+            let n = pop_integer(stack)?;
+            if n < 0 || n as usize > stack.len() {
+                return Err(Error::StackUnderflow);
+            }
+            let start = stack.len() - n as usize;
+            let copied = stack[start..].to_vec();
+            stack.extend(copied);
+        }
+        Dup => {
+            let v = pop(stack)?;
+            stack.push(v);
+            stack.push(v);
+        }
+        Exch => {
+            let b = pop(stack)?;
+            let a = pop(stack)?;
+            stack.push(b);
+            stack.push(a);
+        }
+        Index => {
+            let n = pop_integer(stack)?;
+            if n < 0 || n as usize >= stack.len() {
+                return Err(Error::StackUnderflow);
+            }
+            let v = stack[stack.len() - 1 - n as usize];
+            stack.push(v);
+        }
+        Pop => {
+            pop(stack)?;
+        }
+        Roll => {
+            let j = pop_integer(stack)?;
+            let n = pop_integer(stack)?;
+            if n < 0 || n as usize > stack.len() {
+                return Err(Error::StackUnderflow);
+            }
+            if n > 0 {
+                let start = stack.len() - n as usize;
+                let shift = j.rem_euclid(n) as usize;
+                stack[start..].rotate_right(shift);
+            }
+        }
+    }
+
+    Ok(())
+}
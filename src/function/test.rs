@@ -0,0 +1,272 @@
+use super::*;
+use crate::test_util;
+
+fn assert_near(actual: &[f64], expected: &[f64]) {
+    let is_near = actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, e)| (a - e).abs() < 1e-6);
+
+    if !is_near {
+        panic!("left: {:?} right: {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn eval_exponential() {
+    let function = Function {
+        domain: vec![0.0, 1.0],
+        range: None,
+        kind: FunctionKind::Exponential {
+            c0: vec![0.0, 0.0, 0.0, 0.0],
+            c1: vec![0.0, 0.5, 1.0, 0.0],
+            n: 1.0,
+        },
+    };
+
+    assert_near(&function.eval(&[0.5]), &[0.0, 0.25, 0.5, 0.0]);
+    // 定義域外の入力はクリップされる
+    assert_near(&function.eval(&[2.0]), &[0.0, 0.5, 1.0, 0.0]);
+}
+
+#[test]
+fn eval_sampled_1_input() {
+    let function = Function {
+        domain: vec![0.0, 1.0],
+        range: Some(vec![0.0, 1.0]),
+        kind: FunctionKind::Sampled {
+            size: vec![3],
+            bits_per_sample: 8,
+            encode: vec![0.0, 2.0],
+            decode: vec![0.0, 1.0],
+            samples: vec![0, 255, 0],
+        },
+    };
+
+    assert_near(&function.eval(&[0.0]), &[0.0]);
+    assert_near(&function.eval(&[0.25]), &[0.5]);
+    assert_near(&function.eval(&[0.5]), &[1.0]);
+    assert_near(&function.eval(&[1.0]), &[0.0]);
+}
+
+#[test]
+fn eval_sampled_2_input_4_bit() {
+    // 2x2の表で，サンプル値は 0, 15, 15, 0 (4ビット)
+    let function = Function {
+        domain: vec![0.0, 1.0, 0.0, 1.0],
+        range: Some(vec![0.0, 1.0]),
+        kind: FunctionKind::Sampled {
+            size: vec![2, 2],
+            bits_per_sample: 4,
+            encode: vec![0.0, 1.0, 0.0, 1.0],
+            decode: vec![0.0, 1.0],
+            samples: vec![0x0f, 0xf0],
+        },
+    };
+
+    assert_near(&function.eval(&[1.0, 0.0]), &[1.0]);
+    assert_near(&function.eval(&[0.0, 1.0]), &[1.0]);
+    assert_near(&function.eval(&[0.5, 0.5]), &[0.5]);
+}
+
+#[test]
+fn eval_stitching() {
+    let linear = |c0: f64, c1: f64| Function {
+        domain: vec![0.0, 1.0],
+        range: None,
+        kind: FunctionKind::Exponential {
+            c0: vec![c0],
+            c1: vec![c1],
+            n: 1.0,
+        },
+    };
+
+    let function = Function {
+        domain: vec![0.0, 1.0],
+        range: None,
+        kind: FunctionKind::Stitching {
+            functions: vec![linear(0.0, 1.0), linear(1.0, 0.0)],
+            bounds: vec![0.5],
+            encode: vec![0.0, 1.0, 0.0, 1.0],
+        },
+    };
+
+    assert_near(&function.eval(&[0.25]), &[0.5]);
+    assert_near(&function.eval(&[0.5]), &[1.0]);
+    assert_near(&function.eval(&[0.75]), &[0.5]);
+}
+
+#[test]
+fn eval_postscript_calculator() {
+    // 特色インキをCMYKへ変換する典型的な型4関数
+    let program = Program::parse(b"{dup 0.2 mul exch dup 0.9 mul exch 0 exch 0.5 mul}").unwrap();
+
+    let function = Function {
+        domain: vec![0.0, 1.0],
+        range: Some(vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]),
+        kind: FunctionKind::PostScriptCalculator(program),
+    };
+
+    assert_near(&function.eval(&[1.0]), &[0.2, 0.9, 0.0, 0.5]);
+}
+
+#[test]
+fn eval_postscript_calculator_ifelse() {
+    let program = Program::parse(b"{ dup 0.5 gt { pop 1 } { 2 mul } ifelse }").unwrap();
+
+    let function = Function {
+        domain: vec![0.0, 1.0],
+        range: Some(vec![0.0, 1.0]),
+        kind: FunctionKind::PostScriptCalculator(program),
+    };
+
+    assert_near(&function.eval(&[0.75]), &[1.0]);
+    assert_near(&function.eval(&[0.25]), &[0.5]);
+}
+
+#[test]
+fn parse_postscript_calculator_undefined_operator() {
+    assert!(matches!(
+        Program::parse(b"{ 1 foo }"),
+        Err(postscript::Error::UndefinedOperator(_))
+    ));
+}
+
+#[test]
+fn execute_postscript_calculator_integer_overflow() {
+    let execute = |program: &[u8]| Program::parse(program).unwrap().execute(&[]);
+
+    assert!(matches!(
+        execute(b"{ -9223372036854775808 -1 idiv }"),
+        Err(postscript::Error::TypeCheck)
+    ));
+    assert!(matches!(
+        execute(b"{ -9223372036854775808 -1 mod }"),
+        Err(postscript::Error::TypeCheck)
+    ));
+    assert_near(&execute(b"{ 1 64 bitshift }").unwrap(), &[0.0]);
+    assert_near(&execute(b"{ 1 -64 bitshift }").unwrap(), &[0.0]);
+    assert_near(
+        &execute(b"{ 1 -9223372036854775808 bitshift }").unwrap(),
+        &[0.0],
+    );
+    assert_near(
+        &execute(b"{ 1 3 bitshift 16 -2 bitshift }").unwrap(),
+        &[8.0, 4.0],
+    );
+}
+
+#[test]
+fn parse_postscript_calculator_too_deep_nesting() {
+    let mut program = b"{ ".repeat(100_000);
+    program.extend(b"} ".repeat(100_000));
+
+    assert!(matches!(
+        Program::parse(&program),
+        Err(postscript::Error::TooDeepNesting)
+    ));
+}
+
+#[test]
+fn parse_exponential_with_inconsistent_range() {
+    // C0とC1は3つの値を返すのに/Rangeは1つ分しかない
+    let (mut file, xref) = test_util::write_pdf(&[]);
+    let function_obj = test_util::parse(
+        "<< /FunctionType 2 /Domain [0 1] /Range [0 1] /C0 [0 0 0] /C1 [1 1 1] /N 1 >>",
+    );
+
+    assert!(matches!(
+        Function::parse(&function_obj, &mut file, &xref),
+        Err(Error::InvalidFunction(_))
+    ));
+}
+
+#[test]
+fn parse_stitching_with_inconsistent_outputs() {
+    let (mut file, xref) = test_util::write_pdf(&[]);
+    let function_obj = test_util::parse(
+        "<< /FunctionType 3 /Domain [0 1] /Bounds [0.5] /Encode [0 1 0 1] /Functions [
+            << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>
+            << /FunctionType 2 /Domain [0 1] /C0 [0 0] /C1 [1 1] /N 1 >>
+        ] >>",
+    );
+
+    assert!(matches!(
+        Function::parse(&function_obj, &mut file, &xref),
+        Err(Error::InvalidFunction(_))
+    ));
+}
+
+#[test]
+fn execute_postscript_calculator_stack_overflow() {
+    // オペランドスタックには100個までしか積めない
+    let execute = |program: &[u8]| Program::parse(program).unwrap().execute(&[]);
+
+    assert!(execute(&[b"{ ".as_slice(), &b"1 ".repeat(100), b"}"].concat()).is_ok());
+    assert!(matches!(
+        execute(&[b"{ ".as_slice(), &b"1 ".repeat(101), b"}"].concat()),
+        Err(postscript::Error::StackOverflow)
+    ));
+    assert!(matches!(
+        execute(&[b"{ ".as_slice(), &b"1 ".repeat(60), b"60 copy }"].concat()),
+        Err(postscript::Error::StackOverflow)
+    ));
+}
+
+#[test]
+fn parse_postscript_calculator_non_finite_number() {
+    // inf や NaN はPostScriptの数ではない
+    for program in [&b"{ inf }"[..], b"{ NaN }", b"{ 1e400 }"] {
+        assert!(matches!(
+            Program::parse(program),
+            Err(postscript::Error::UndefinedOperator(_))
+        ));
+    }
+}
+
+#[test]
+fn parse_sampled_with_too_large_size() {
+    // サンプル数の計算が桁あふれする/Size
+    let (mut file, xref) = test_util::write_pdf(&[test_util::stream(
+        "/FunctionType 0 /Domain [0 1 0 1] /Range [0 1]
+            /Size [4294967296 4294967296] /BitsPerSample 8",
+        b"\x00",
+    )]);
+
+    assert!(matches!(
+        Function::parse(&test_util::parse("1 0 R"), &mut file, &xref),
+        Err(Error::InvalidFunction(_))
+    ));
+}
+
+#[test]
+fn eval_sampled_with_too_many_inputs() {
+    // 2^m点の補間ができないほど入力が多いときは値域の下限を返す
+    let function = Function {
+        domain: [0.0, 1.0].repeat(64),
+        range: Some(vec![0.25, 1.0]),
+        kind: FunctionKind::Sampled {
+            size: vec![1; 64],
+            bits_per_sample: 8,
+            encode: [0.0, 0.0].repeat(64),
+            decode: vec![0.25, 1.0],
+            samples: vec![255],
+        },
+    };
+
+    assert_near(&function.eval(&[0.5; 64]), &[0.25]);
+}
+
+#[test]
+fn parse_self_referencing_stitching() {
+    // /Functionsが自身を参照していても無限に再帰しない
+    let (mut file, xref) = test_util::write_pdf(&[
+        b"<< /FunctionType 3 /Domain [0 1] /Bounds [] /Encode [0 1] /Functions [1 0 R] >>".to_vec(),
+    ]);
+
+    assert!(matches!(
+        Function::parse(&test_util::parse("1 0 R"), &mut file, &xref),
+        Err(Error::InvalidFunction(_))
+    ));
+}
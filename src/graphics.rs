@@ -0,0 +1,622 @@
+use std::fmt;
+use std::fs::File;
+
+use crate::content;
+use crate::cross_reference;
+use crate::filter;
+use crate::image;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    Filter(filter::Error),
+    Content(content::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::Filter(e) => write!(f, "filter: {}", e),
+            Error::Content(e) => write!(f, "content: {}", e),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+impl From<filter::Error> for Error {
+    fn from(e: filter::Error) -> Self {
+        Self::Filter(e)
+    }
+}
+impl From<content::Error> for Error {
+    fn from(e: content::Error) -> Self {
+        Self::Content(e)
+    }
+}
+
+// Form XObjectの入れ子の深さの上限
+// 自身を参照するような壊れたFormで無限に再帰しないようにする
+const MAX_FORM_DEPTH: usize = 16;
+
+// 変換行列 [a b c d e f]
+// 点 (x, y) を (a x + c y + e, b x + d y + f) に写す
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix(pub [f64; 6]);
+
+impl Matrix {
+    pub fn identity() -> Matrix {
+        Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+
+    // 配列の6つの数値から行列を作る
+    pub fn from_operands(operands: &[Object]) -> Option<Matrix> {
+        if operands.len() != 6 {
+            return None;
+        }
+
+        let mut m = [0.0; 6];
+        for (v, obj) in m.iter_mut().zip(operands) {
+            *v = object::PdfReal::ensure_number(obj).ok()?;
+        }
+
+        Some(Matrix(m))
+    }
+
+    // selfを適用してからotherを適用する変換
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+
+        Matrix([
+            a1 * a2 + b1 * c2,
+            a1 * b2 + b1 * d2,
+            c1 * a2 + d1 * c2,
+            c1 * b2 + d1 * d2,
+            e1 * a2 + f1 * c2 + e2,
+            e1 * b2 + f1 * d2 + f2,
+        ])
+    }
+
+    pub fn transform_point(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+}
+
+// 文字の描画に関わるグラフィックス状態
+#[derive(Debug, Clone)]
+pub struct TextState {
+    // Tc
+    pub char_spacing: f64,
+    // Tw
+    pub word_spacing: f64,
+    // Tz (100で割った値)
+    pub horizontal_scaling: f64,
+    // TL
+    pub leading: f64,
+    // Tfで指定されたリソース中のフォント名と大きさ
+    pub font_name: Option<String>,
+    pub font_size: f64,
+    // Tr
+    pub render_mode: isize,
+    // Ts
+    pub rise: f64,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        TextState {
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            font_name: None,
+            font_size: 0.0,
+            render_mode: 0,
+            rise: 0.0,
+        }
+    }
+}
+
+// 塗りの色．色空間の名前と，sc，scnなどで指定された成分
+// リソースで名前を付けた色空間は "Separation(Spot)" のような説明になる
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+    pub color_space: String,
+    pub components: Vec<f64>,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color {
+            color_space: String::from("DeviceGray"),
+            components: vec![0.0],
+        }
+    }
+}
+
+impl Color {
+    // csで色空間を選んだときの初期値
+    // リソースから引けなかった名前の色空間は成分の数が分からないので空にしておく
+    fn initial(color_space: &str) -> Color {
+        let components = match color_space {
+            "DeviceGray" | "CalGray" | "G" => vec![0.0],
+            "DeviceRGB" | "CalRGB" | "RGB" => vec![0.0, 0.0, 0.0],
+            "DeviceCMYK" | "CMYK" => vec![0.0, 0.0, 0.0, 1.0],
+            _ => vec![],
+        };
+
+        Color {
+            color_space: String::from(color_space),
+            components,
+        }
+    }
+
+    // 成分の数が合わない指定は無視する
+    fn with_components(mut self, components: Vec<f64>) -> Color {
+        if components.len() == self.components.len() {
+            self.components = components;
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphicsState {
+    pub ctm: Matrix,
+    pub text: TextState,
+    pub fill_color: Color,
+}
+
+// q/Qで退避と復帰をするグラフィックス状態
+pub struct GraphicsStateStack {
+    current: GraphicsState,
+    stack: Vec<GraphicsState>,
+}
+
+impl GraphicsStateStack {
+    pub fn new(ctm: Matrix) -> GraphicsStateStack {
+        GraphicsStateStack {
+            current: GraphicsState {
+                ctm,
+                text: TextState::default(),
+                fill_color: Color::default(),
+            },
+            stack: vec![],
+        }
+    }
+
+    pub fn current_mut(&mut self) -> &mut GraphicsState {
+        &mut self.current
+    }
+
+    // q，Q，cm，塗りの色と文字の状態を設定する演算子を処理したら真を返す
+    // 不正なオペランドの操作は無視する
+    pub fn apply(&mut self, operation: &content::Operation) -> bool {
+        let number = |i: usize| {
+            operation
+                .operands
+                .get(i)
+                .and_then(|obj| object::PdfReal::ensure_number(obj).ok())
+        };
+        // scnではパターンの名前が最後に付くことがある
+        let numbers = || {
+            operation
+                .operands
+                .iter()
+                .filter_map(|obj| object::PdfReal::ensure_number(obj).ok())
+                .collect::<Vec<f64>>()
+        };
+        let fill_color = &mut self.current.fill_color;
+        let text = &mut self.current.text;
+
+        match operation.operator.as_str() {
+            "q" => self.save(),
+            "Q" => self.restore(),
+            "cm" => {
+                if let Some(matrix) = Matrix::from_operands(&operation.operands) {
+                    self.concat(&matrix);
+                }
+            }
+            "g" => *fill_color = Color::initial("DeviceGray").with_components(numbers()),
+            "rg" => *fill_color = Color::initial("DeviceRGB").with_components(numbers()),
+            "k" => *fill_color = Color::initial("DeviceCMYK").with_components(numbers()),
+            "cs" => {
+                if let Some(Object::Name(name)) = operation.operands.first() {
+                    *fill_color = Color::initial(name.as_str());
+                }
+            }
+            "sc" | "scn" => fill_color.components = numbers(),
+            "Tc" => text.char_spacing = number(0).unwrap_or(text.char_spacing),
+            "Tw" => text.word_spacing = number(0).unwrap_or(text.word_spacing),
+            "Tz" => {
+                text.horizontal_scaling = number(0).map_or(text.horizontal_scaling, |v| v / 100.0)
+            }
+            "TL" => text.leading = number(0).unwrap_or(text.leading),
+            "Ts" => text.rise = number(0).unwrap_or(text.rise),
+            "Tr" => text.render_mode = number(0).map_or(text.render_mode, |v| v as isize),
+            "Tf" => {
+                if let (Some(Object::Name(name)), Some(size)) =
+                    (operation.operands.first(), number(1))
+                {
+                    text.font_name = Some(String::from(name.as_str()));
+                    text.font_size = size;
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    pub fn save(&mut self) {
+        self.stack.push(self.current.clone());
+    }
+
+    // 対応するqのないQは無視する
+    pub fn restore(&mut self) {
+        if let Some(state) = self.stack.pop() {
+            self.current = state;
+        }
+    }
+
+    pub fn concat(&mut self, matrix: &Matrix) {
+        self.current.ctm = matrix.multiply(&self.current.ctm);
+    }
+}
+
+// グラフィックス状態を追いながらコンテンツストリームを解釈する際に，状態の変更以外の操作を受け取る
+// TDのように状態も変える操作があるので，状態は書き換えられるようにしておく
+pub trait OperationHandler {
+    type Error: From<Error>;
+
+    fn handle(
+        &mut self,
+        operation: &content::Operation,
+        state: &mut GraphicsState,
+        resources: Option<&object::PdfDict>,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<(), Self::Error>;
+}
+
+// q，Q，cmと文字の状態の設定を処理し，それ以外の操作をhandlerに渡す
+// Form XObjectのDoでは，その/Matrixを適用してFormのコンテンツストリームも解釈する
+pub fn walk_operations<H: OperationHandler>(
+    operations: &[content::Operation],
+    resources: Option<&object::PdfDict>,
+    handler: &mut H,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<(), H::Error> {
+    let mut states = GraphicsStateStack::new(Matrix::identity());
+
+    walk_operations_in(operations, resources, &mut states, 0, handler, file, xref)
+}
+
+fn walk_operations_in<H: OperationHandler>(
+    operations: &[content::Operation],
+    resources: Option<&object::PdfDict>,
+    states: &mut GraphicsStateStack,
+    depth: usize,
+    handler: &mut H,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<(), H::Error> {
+    for operation in operations {
+        // リソースの/ColorSpaceで名前を付けた色空間は，その定義を引いて色空間の名前にする
+        if operation.operator == "cs" {
+            if let Some(color) = lookup_color_space(operation, resources, file, xref) {
+                states.current_mut().fill_color = color;
+                continue;
+            }
+        }
+
+        if states.apply(operation) {
+            continue;
+        }
+
+        if operation.operator == "Do" && depth < MAX_FORM_DEPTH {
+            if let Some(form) = get_xobject(operation, resources, file, xref)?
+                .filter(|xobj| is_subtype(&xobj.dict, "Form"))
+            {
+                let form_operations = content::parse_operations(
+                    &filter::decode_stream(&form, file, xref).map_err(Error::from)?,
+                )
+                .map_err(Error::from)?;
+
+                // Formが/Resourcesを持たないときは呼び出し元のリソースを使う
+                let form_resources = match form.dict.get("Resources") {
+                    Some(obj) => {
+                        let obj = object::resolve(obj, file, xref).map_err(Error::from)?;
+                        Some(
+                            object::PdfDict::ensure_with_key(&obj, vec![])
+                                .map_err(Error::from)?
+                                .clone(),
+                        )
+                    }
+                    None => resources.cloned(),
+                };
+
+                states.save();
+                if let Some(Object::Array(matrix)) = form.dict.get("Matrix") {
+                    let operands: Vec<Object> = matrix.into_iter().cloned().collect();
+                    if let Some(matrix) = Matrix::from_operands(&operands) {
+                        states.concat(&matrix);
+                    }
+                }
+
+                walk_operations_in(
+                    &form_operations,
+                    form_resources.as_ref(),
+                    states,
+                    depth + 1,
+                    handler,
+                    file,
+                    xref,
+                )?;
+                states.restore();
+
+                continue;
+            }
+        }
+
+        handler.handle(operation, states.current_mut(), resources, file, xref)?;
+    }
+
+    Ok(())
+}
+
+// Doのオペランドの名前をリソースの/XObjectから引く
+pub fn get_xobject(
+    operation: &content::Operation,
+    resources: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<object::PdfStreamObj>, Error> {
+    let name = match operation.operands.first() {
+        Some(Object::Name(name)) => name,
+        _ => return Ok(None),
+    };
+
+    let xobjects = match resources {
+        Some(resources) => get_sub_dict(resources, "XObject", file, xref)?,
+        None => None,
+    };
+
+    let xobj = match xobjects
+        .as_ref()
+        .and_then(|xobjects| xobjects.get(name.as_str()))
+    {
+        Some(xobj) => object::resolve(xobj, file, xref)?,
+        None => return Ok(None),
+    };
+
+    match xobj {
+        Object::StreamObj(xobj) => Ok(Some(xobj)),
+        _ => Ok(None),
+    }
+}
+
+// csのオペランドの名前をリソースの/ColorSpaceから引く
+// 引けないときや壊れた色空間はNoneにして，名前のまま扱う
+fn lookup_color_space(
+    operation: &content::Operation,
+    resources: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Option<Color> {
+    let name = match operation.operands.first() {
+        Some(Object::Name(name)) => name,
+        _ => return None,
+    };
+
+    let color_spaces = get_sub_dict(resources?, "ColorSpace", file, xref).ok()??;
+    let colorspace_obj = color_spaces.get(name.as_str())?;
+    let (color_space, components) = image::initial_color(colorspace_obj, file, xref).ok()?;
+
+    Some(Color {
+        color_space,
+        components,
+    })
+}
+
+fn is_subtype(dict: &object::PdfDict, subtype: &str) -> bool {
+    match dict.get("Subtype") {
+        Some(Object::Name(name)) => name.as_str() == subtype,
+        _ => false,
+    }
+}
+
+// 画像がページのどこにどの大きさで描かれるか
+#[derive(Debug, Clone)]
+pub struct ImagePlacement {
+    // (オブジェクト番号, 世代番号)
+    // インライン画像のときはNone
+    pub object_ref: Option<(usize, usize)>,
+    pub width: u32,
+    pub height: u32,
+    // 画像の単位正方形をページ空間に写す行列
+    pub ctm: [f64; 6],
+    // ページ空間での [x_min y_min x_max y_max]
+    pub bbox: [f64; 4],
+    // 画像の横方向と縦方向がページ上で1インチあたり何ピクセルになるか
+    pub horizontal_dpi: f64,
+    pub vertical_dpi: f64,
+}
+
+impl ImagePlacement {
+    pub fn new(
+        object_ref: Option<(usize, usize)>,
+        width: u32,
+        height: u32,
+        ctm: &Matrix,
+    ) -> ImagePlacement {
+        let bbox = bounding_box(
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+                .map(|(x, y)| ctm.transform_point(x, y)),
+        );
+
+        // 画像の横方向と縦方向の単位ベクトルがページ上で何ポイントになるか
+        let [a, b, c, d, _, _] = ctm.0;
+        let horizontal_length = (a * a + b * b).sqrt();
+        let vertical_length = (c * c + d * d).sqrt();
+
+        let dpi = |pixels: u32, length: f64| {
+            if length == 0.0 {
+                0.0
+            } else {
+                pixels as f64 / (length / 72.0)
+            }
+        };
+
+        ImagePlacement {
+            object_ref,
+            width,
+            height,
+            ctm: ctm.0,
+            bbox,
+            horizontal_dpi: dpi(width, horizontal_length),
+            vertical_dpi: dpi(height, vertical_length),
+        }
+    }
+}
+
+// 点をすべて囲む [x_min y_min x_max y_max]
+pub fn bounding_box(points: impl IntoIterator<Item = (f64, f64)>) -> [f64; 4] {
+    let mut bbox = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for (x, y) in points {
+        bbox[0] = bbox[0].min(x);
+        bbox[1] = bbox[1].min(y);
+        bbox[2] = bbox[2].max(x);
+        bbox[3] = bbox[3].max(y);
+    }
+
+    bbox
+}
+
+// 画像XObjectのDoとインライン画像の置かれる位置を集める
+struct ImagePlacementFinder {
+    placements: Vec<ImagePlacement>,
+}
+
+impl OperationHandler for ImagePlacementFinder {
+    type Error = Error;
+
+    fn handle(
+        &mut self,
+        operation: &content::Operation,
+        state: &mut GraphicsState,
+        resources: Option<&object::PdfDict>,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<(), Error> {
+        match operation.operator.as_str() {
+            "BI" => {
+                if let Some(placement) = inline_image_placement(operation, &state.ctm) {
+                    self.placements.push(placement);
+                }
+            }
+            "Do" => {
+                let xobj_ref = match operation.operands.first() {
+                    Some(Object::Name(name)) => {
+                        get_xobject_ref(name.as_str(), resources, file, xref)?
+                    }
+                    _ => None,
+                };
+
+                if let Some(xobj) = get_xobject(operation, resources, file, xref)? {
+                    if is_subtype(&xobj.dict, "Image") {
+                        let (width, height) = get_size(&xobj.dict)?;
+                        self.placements
+                            .push(ImagePlacement::new(xobj_ref, width, height, &state.ctm));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+pub fn find_image_placements(
+    operations: &[content::Operation],
+    resources: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Vec<ImagePlacement>, Error> {
+    let mut finder = ImagePlacementFinder { placements: vec![] };
+
+    walk_operations(operations, resources, &mut finder, file, xref)?;
+
+    Ok(finder.placements)
+}
+
+fn get_xobject_ref(
+    name: &str,
+    resources: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<(usize, usize)>, Error> {
+    let xobjects = match resources {
+        Some(resources) => get_sub_dict(resources, "XObject", file, xref)?,
+        None => None,
+    };
+
+    Ok(
+        match xobjects.as_ref().and_then(|xobjects| xobjects.get(name)) {
+            Some(Object::IndirectRef(xobj_ref)) => Some(xobj_ref.unpack()),
+            _ => None,
+        },
+    )
+}
+
+fn inline_image_placement(operation: &content::Operation, ctm: &Matrix) -> Option<ImagePlacement> {
+    let image_dict = match operation.operands.first() {
+        Some(Object::Dict(image_dict)) => image::expand_inline_image_dict(image_dict, None),
+        _ => return None,
+    };
+
+    let (width, height) = get_size(&image_dict).ok()?;
+
+    Some(ImagePlacement::new(None, width, height, ctm))
+}
+
+fn get_size(image_dict: &object::PdfDict) -> Result<(u32, u32), Error> {
+    image_dict.assert_with_key(vec!["Width", "Height"])?;
+
+    let width = object::PdfInteger::ensure(image_dict.get("Width").unwrap())?;
+    let height = object::PdfInteger::ensure(image_dict.get("Height").unwrap())?;
+
+    width.assert_not_negative()?;
+    height.assert_not_negative()?;
+
+    Ok((width.unpack() as u32, height.unpack() as u32))
+}
+
+// リソース辞書の配下の/XObjectや/Fontなどの辞書を返す
+pub fn get_sub_dict(
+    resources: &object::PdfDict,
+    key: &'static str,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<object::PdfDict>, Error> {
+    let sub_obj = match resources.get(key) {
+        Some(sub_obj) => object::resolve(sub_obj, file, xref)?,
+        None => return Ok(None),
+    };
+
+    Ok(Some(
+        object::PdfDict::ensure_with_key(&sub_obj, vec![])?.clone(),
+    ))
+}
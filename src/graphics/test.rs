@@ -0,0 +1,180 @@
+use super::*;
+use crate::test_util;
+
+fn assert_near(actual: &[f64], expected: &[f64]) {
+    let is_near = actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, e)| (a - e).abs() < 1e-6);
+
+    if !is_near {
+        panic!("left: {:?} right: {:?}", actual, expected);
+    }
+}
+
+fn operation(operator: &str, numbers: &[f64]) -> content::Operation {
+    content::Operation {
+        operator: String::from(operator),
+        operands: numbers
+            .iter()
+            .map(|n| Object::Real(object::PdfReal::new(*n, 0)))
+            .collect(),
+    }
+}
+
+#[test]
+fn multiply_matrix() {
+    let scale = Matrix([2.0, 0.0, 0.0, 3.0, 0.0, 0.0]);
+    let translate = Matrix([1.0, 0.0, 0.0, 1.0, 10.0, 20.0]);
+
+    // 拡大してから平行移動する
+    assert_near(
+        &scale.multiply(&translate).0,
+        &[2.0, 0.0, 0.0, 3.0, 10.0, 20.0],
+    );
+    // 平行移動してから拡大する
+    assert_near(
+        &translate.multiply(&scale).0,
+        &[2.0, 0.0, 0.0, 3.0, 20.0, 60.0],
+    );
+}
+
+#[test]
+fn graphics_state_stack_save_restore() {
+    let mut states = GraphicsStateStack::new(Matrix::identity());
+
+    states.apply(&operation("q", &[]));
+    states.apply(&operation("cm", &[1.0, 0.0, 0.0, 1.0, 10.0, 20.0]));
+    states.apply(&operation("cm", &[2.0, 0.0, 0.0, 2.0, 0.0, 0.0]));
+    assert_near(
+        &states.current_mut().ctm.0,
+        &[2.0, 0.0, 0.0, 2.0, 10.0, 20.0],
+    );
+
+    states.apply(&operation("Q", &[]));
+    assert_near(&states.current_mut().ctm.0, &Matrix::identity().0);
+
+    // 対応するqのないQは無視する
+    states.apply(&operation("Q", &[]));
+    assert_near(&states.current_mut().ctm.0, &Matrix::identity().0);
+}
+
+#[test]
+fn image_placement_bbox_and_dpi() {
+    // 幅300ピクセルの画像を横144ポイント(2インチ)，縦72ポイントに描く
+    let ctm = Matrix([144.0, 0.0, 0.0, 72.0, 50.0, 100.0]);
+
+    let placement = ImagePlacement::new(Some((4, 0)), 300, 150, &ctm);
+
+    assert_near(&placement.bbox, &[50.0, 100.0, 194.0, 172.0]);
+    assert_near(
+        &[placement.horizontal_dpi, placement.vertical_dpi],
+        &[150.0, 150.0],
+    );
+}
+
+#[test]
+fn image_placement_rotated() {
+    // 90度回転させて描くと横幅は縦方向の大きさになる
+    let ctm = Matrix([0.0, 72.0, -36.0, 0.0, 100.0, 100.0]);
+
+    let placement = ImagePlacement::new(None, 72, 36, &ctm);
+
+    assert_near(&placement.bbox, &[64.0, 100.0, 100.0, 172.0]);
+    assert_near(
+        &[placement.horizontal_dpi, placement.vertical_dpi],
+        &[72.0, 72.0],
+    );
+}
+
+#[test]
+fn fill_color_operators() {
+    let mut states = GraphicsStateStack::new(Matrix::identity());
+    assert_eq!(states.current_mut().fill_color, Color::default());
+
+    states.apply(&operation("q", &[]));
+    states.apply(&operation("rg", &[1.0, 0.5, 0.0]));
+    assert_eq!(
+        states.current_mut().fill_color,
+        Color {
+            color_space: String::from("DeviceRGB"),
+            components: vec![1.0, 0.5, 0.0],
+        }
+    );
+
+    // 色空間を選ぶと初期値に戻り，scで成分を指定する
+    let mut cs = operation("cs", &[]);
+    cs.operands.push(Object::Name(object::PdfName::new(
+        String::from("DeviceCMYK"),
+        0,
+    )));
+    states.apply(&cs);
+    assert_eq!(
+        states.current_mut().fill_color.components,
+        [0.0, 0.0, 0.0, 1.0]
+    );
+    states.apply(&operation("sc", &[0.1, 0.2, 0.3, 0.4]));
+    assert_eq!(
+        states.current_mut().fill_color.components,
+        [0.1, 0.2, 0.3, 0.4]
+    );
+
+    states.apply(&operation("Q", &[]));
+    assert_eq!(states.current_mut().fill_color, Color::default());
+}
+
+// 塗りの操作ごとにその時の色を記録する
+struct FillColors(Vec<Color>);
+
+impl OperationHandler for FillColors {
+    type Error = Error;
+
+    fn handle(
+        &mut self,
+        operation: &content::Operation,
+        state: &mut GraphicsState,
+        _resources: Option<&object::PdfDict>,
+        _file: &mut File,
+        _xref: &cross_reference::XRef,
+    ) -> Result<(), Error> {
+        if operation.operator == "f" {
+            self.0.push(state.fill_color.clone());
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn named_color_spaces() {
+    let (mut file, xref) = test_util::write_pdf(&[b"<< /ColorSpace << /CS0 \
+          [/Separation /Spot /DeviceCMYK \
+           << /FunctionType 2 /Domain [0 1] /C0 [0 0 0 0] /C1 [0 0 0 1] /N 1 >>] \
+          /CS1 [/Indexed /DeviceRGB 1 <000000FFFFFF>] >> >>"
+        .to_vec()]);
+    let resources = test_util::parse("1 0 R");
+    let resources = object::resolve(&resources, &mut file, &xref).unwrap();
+    let resources = object::PdfDict::ensure_with_key(&resources, vec![]).unwrap();
+
+    let operations =
+        content::parse_operations(b"/CS0 cs f 0.5 sc f /CS1 cs f /CS9 cs f /DeviceRGB cs f")
+            .unwrap();
+    let mut colors = FillColors(vec![]);
+    walk_operations(&operations, Some(resources), &mut colors, &mut file, &xref).unwrap();
+
+    let color = |color_space: &str, components: Vec<f64>| Color {
+        color_space: String::from(color_space),
+        components,
+    };
+    assert_eq!(
+        colors.0,
+        [
+            color("Separation(Spot)", vec![1.0]),
+            color("Separation(Spot)", vec![0.5]),
+            color("Indexed(DeviceRGB, 1)", vec![0.0]),
+            // リソースに無い名前はそのまま
+            color("CS9", vec![]),
+            color("DeviceRGB", vec![0.0, 0.0, 0.0]),
+        ]
+    );
+}
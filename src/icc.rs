@@ -0,0 +1,316 @@
+use std::fmt;
+
+use crate::cie;
+
+#[cfg(test)]
+mod test;
+
+// ICCプロファイルのうちマトリクス/TRC形式のRGBプロファイルとグレープロファイルのみを扱う
+// cf. ICC.1:2004-10
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidProfile,
+    UnsupporttedProfile,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::InvalidProfile => write!(f, "icc profile is invalid"),
+            Error::UnsupporttedProfile => write!(f, "icc profile is not supportted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Curve {
+    Identity,
+    Gamma(f64),
+    Table(Vec<f64>),
+    // y = (a*x + b)^g + e (x >= d), c*x + f (x < d)
+    Parametric {
+        g: f64,
+        a: f64,
+        b: f64,
+        c: f64,
+        d: f64,
+        e: f64,
+        f: f64,
+    },
+}
+
+impl Curve {
+    fn eval(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+
+        match self {
+            Curve::Identity => x,
+            Curve::Gamma(g) => x.powf(*g),
+            Curve::Table(table) => {
+                let pos = x * (table.len() - 1) as f64;
+                let i = (pos.floor() as usize).min(table.len() - 1);
+                let j = (i + 1).min(table.len() - 1);
+                let t = pos - i as f64;
+
+                table[i] + (table[j] - table[i]) * t
+            }
+            Curve::Parametric {
+                g,
+                a,
+                b,
+                c,
+                d,
+                e,
+                f,
+            } => {
+                if x >= *d {
+                    let base = a * x + b;
+                    if base <= 0.0 {
+                        *e
+                    } else {
+                        base.powf(*g) + e
+                    }
+                } else {
+                    c * x + f
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ProfileKind {
+    Gray(Curve),
+    // matrixはrXYZ，gXYZ，bXYZをそれぞれ列に持つ
+    Rgb([Curve; 3], [[f64; 3]; 3]),
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    kind: ProfileKind,
+}
+
+impl Profile {
+    pub fn parse(data: &[u8]) -> Result<Profile, Error> {
+        const HEADER_SIZE: usize = 128;
+
+        if data.len() < HEADER_SIZE + 4 || &data[36..40] != b"acsp" {
+            return Err(Error::InvalidProfile);
+        }
+
+        // PCSがLabのものはLUTベースなので扱わない
+        if &data[20..24] != b"XYZ " {
+            return Err(Error::UnsupporttedProfile);
+        }
+
+        let tags = TagTable::parse(data)?;
+
+        let kind = match &data[16..20] {
+            b"GRAY" => ProfileKind::Gray(tags.curve(b"kTRC")?),
+            b"RGB " => {
+                let trc = [
+                    tags.curve(b"rTRC")?,
+                    tags.curve(b"gTRC")?,
+                    tags.curve(b"bTRC")?,
+                ];
+                let r = tags.xyz(b"rXYZ")?;
+                let g = tags.xyz(b"gXYZ")?;
+                let b = tags.xyz(b"bXYZ")?;
+
+                let matrix = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+
+                ProfileKind::Rgb(trc, matrix)
+            }
+            _ => return Err(Error::UnsupporttedProfile),
+        };
+
+        Ok(Profile { kind })
+    }
+
+    pub fn n_components(&self) -> usize {
+        match self.kind {
+            ProfileKind::Gray(_) => 1,
+            ProfileKind::Rgb(_, _) => 3,
+        }
+    }
+
+    // 0.0~1.0の色成分をsRGBの0.0~1.0に変換する
+    pub fn to_srgb(&self, components: &[f64]) -> [f64; 3] {
+        match &self.kind {
+            ProfileKind::Gray(trc) => {
+                // PCSの白色点はD50だが，D65へ順応させると無彩色は無彩色のままになる
+                let v = cie::srgb_gamma(trc.eval(components[0]));
+                [v, v, v]
+            }
+            ProfileKind::Rgb(trc, matrix) => {
+                let linear = [
+                    trc[0].eval(components[0]),
+                    trc[1].eval(components[1]),
+                    trc[2].eval(components[2]),
+                ];
+
+                let mut xyz = [0.0; 3];
+                for (row, v) in matrix.iter().zip(xyz.iter_mut()) {
+                    *v = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+                }
+
+                cie::xyz_to_srgb(xyz, cie::D50)
+            }
+        }
+    }
+}
+
+struct TagTable<'a> {
+    data: &'a [u8],
+    entries: Vec<([u8; 4], usize, usize)>,
+}
+
+impl<'a> TagTable<'a> {
+    fn parse(data: &'a [u8]) -> Result<TagTable<'a>, Error> {
+        let count = read_u32(data, 128)? as usize;
+
+        let mut entries = vec![];
+        for i in 0..count {
+            let entry_offset = 132 + i * 12;
+            let signature = match data.get(entry_offset..(entry_offset + 4)) {
+                Some(sig) => [sig[0], sig[1], sig[2], sig[3]],
+                None => return Err(Error::InvalidProfile),
+            };
+            let offset = read_u32(data, entry_offset + 4)? as usize;
+            let size = read_u32(data, entry_offset + 8)? as usize;
+
+            entries.push((signature, offset, size));
+        }
+
+        Ok(TagTable { data, entries })
+    }
+
+    fn get(&self, signature: &[u8; 4]) -> Result<&'a [u8], Error> {
+        let (_, offset, size) = match self.entries.iter().find(|e| &e.0 == signature) {
+            Some(entry) => entry,
+            None => return Err(Error::UnsupporttedProfile),
+        };
+
+        match self.data.get(*offset..(offset + size)) {
+            Some(tag) if tag.len() >= 8 => Ok(tag),
+            _ => Err(Error::InvalidProfile),
+        }
+    }
+
+    fn xyz(&self, signature: &[u8; 4]) -> Result<[f64; 3], Error> {
+        let tag = self.get(signature)?;
+        if &tag[0..4] != b"XYZ " {
+            return Err(Error::InvalidProfile);
+        }
+
+        Ok([
+            read_s15fixed16(tag, 8)?,
+            read_s15fixed16(tag, 12)?,
+            read_s15fixed16(tag, 16)?,
+        ])
+    }
+
+    fn curve(&self, signature: &[u8; 4]) -> Result<Curve, Error> {
+        let tag = self.get(signature)?;
+
+        match &tag[0..4] {
+            b"curv" => {
+                let count = read_u32(tag, 8)? as usize;
+                match count {
+                    0 => Ok(Curve::Identity),
+                    // u8Fixed8Number形式のガンマ値
+                    1 => Ok(Curve::Gamma(read_u16(tag, 12)? as f64 / 256.0)),
+                    _ => {
+                        let mut table = vec![];
+                        for i in 0..count {
+                            table.push(read_u16(tag, 12 + i * 2)? as f64 / 65535.0);
+                        }
+                        Ok(Curve::Table(table))
+                    }
+                }
+            }
+            b"para" => {
+                let function_type = read_u16(tag, 8)?;
+                let param_num = match function_type {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    4 => 7,
+                    _ => return Err(Error::UnsupporttedProfile),
+                };
+
+                let mut p = [0.0; 7];
+                for (i, v) in p.iter_mut().enumerate().take(param_num) {
+                    *v = read_s15fixed16(tag, 12 + i * 4)?;
+                }
+                let [g, a, b, c, d, e, f] = p;
+                // 関数型1と2は -b/a を境目にするので，aが0だと定義できない
+                if matches!(function_type, 1 | 2) && a == 0.0 {
+                    return Err(Error::InvalidProfile);
+                }
+
+                // どの関数型も一般形で表せるように係数を揃える
+                Ok(match function_type {
+                    0 => Curve::Gamma(g),
+                    1 => Curve::Parametric {
+                        g,
+                        a,
+                        b,
+                        c: 0.0,
+                        d: -b / a,
+                        e: 0.0,
+                        f: 0.0,
+                    },
+                    2 => Curve::Parametric {
+                        g,
+                        a,
+                        b,
+                        c: 0.0,
+                        d: -b / a,
+                        e: c,
+                        f: c,
+                    },
+                    3 => Curve::Parametric {
+                        g,
+                        a,
+                        b,
+                        c,
+                        d,
+                        e: 0.0,
+                        f: 0.0,
+                    },
+                    _ => Curve::Parametric {
+                        g,
+                        a,
+                        b,
+                        c,
+                        d,
+                        e,
+                        f,
+                    },
+                })
+            }
+            _ => Err(Error::UnsupporttedProfile),
+        }
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    match data.get(offset..(offset + 2)) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err(Error::InvalidProfile),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    match data.get(offset..(offset + 4)) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::InvalidProfile),
+    }
+}
+
+fn read_s15fixed16(data: &[u8], offset: usize) -> Result<f64, Error> {
+    let v = read_u32(data, offset)? as i32;
+    Ok(v as f64 / 65536.0)
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+fn build_profile(colorspace: &[u8; 4], tags: Vec<(&[u8; 4], Vec<u8>)>) -> Vec<u8> {
+    let mut data = vec![0; 128];
+    data[16..20].copy_from_slice(colorspace);
+    data[20..24].copy_from_slice(b"XYZ ");
+    data[36..40].copy_from_slice(b"acsp");
+
+    data.extend((tags.len() as u32).to_be_bytes());
+
+    let mut offset = 128 + 4 + tags.len() * 12;
+    let mut body: Vec<u8> = vec![];
+    for (signature, tag) in &tags {
+        data.extend(signature.iter());
+        data.extend((offset as u32).to_be_bytes());
+        data.extend((tag.len() as u32).to_be_bytes());
+
+        offset += tag.len();
+        body.extend(tag);
+    }
+
+    data.extend(body);
+    data
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for v in xyz {
+        tag.extend(((v * 65536.0).round() as i32).to_be_bytes());
+    }
+    tag
+}
+
+fn gamma_tag(gamma: f64) -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend(1u32.to_be_bytes());
+    tag.extend(((gamma * 256.0) as u16).to_be_bytes());
+    tag
+}
+
+fn assert_near(actual: [f64; 3], expected: [f64; 3]) {
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        if (a - e).abs() > 0.01 {
+            panic!("left: {:?} right: {:?}", actual, expected);
+        }
+    }
+}
+
+#[test]
+fn parse_gray_profile() {
+    let data = build_profile(b"GRAY", vec![(b"kTRC", gamma_tag(1.0))]);
+
+    let profile = Profile::parse(&data).unwrap();
+
+    assert_eq!(profile.n_components(), 1);
+    assert_near(profile.to_srgb(&[1.0]), [1.0, 1.0, 1.0]);
+    assert_near(profile.to_srgb(&[0.0]), [0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn parse_rgb_profile() {
+    // sRGBの原色をD50へ順応させた値
+    let data = build_profile(
+        b"RGB ",
+        vec![
+            (b"rXYZ", xyz_tag([0.4361, 0.2225, 0.0139])),
+            (b"gXYZ", xyz_tag([0.3851, 0.7169, 0.0971])),
+            (b"bXYZ", xyz_tag([0.1431, 0.0606, 0.7141])),
+            (b"rTRC", gamma_tag(2.2)),
+            (b"gTRC", gamma_tag(2.2)),
+            (b"bTRC", gamma_tag(2.2)),
+        ],
+    );
+
+    let profile = Profile::parse(&data).unwrap();
+
+    assert_eq!(profile.n_components(), 3);
+    assert_near(profile.to_srgb(&[1.0, 1.0, 1.0]), [1.0, 1.0, 1.0]);
+    assert_near(profile.to_srgb(&[1.0, 0.0, 0.0]), [1.0, 0.0, 0.0]);
+    assert_near(profile.to_srgb(&[0.0, 0.0, 1.0]), [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn reject_lut_based_profile() {
+    let data = build_profile(b"CMYK", vec![]);
+
+    assert!(matches!(
+        Profile::parse(&data),
+        Err(Error::UnsupporttedProfile)
+    ));
+}
+
+#[test]
+fn reject_parametric_curve_without_slope() {
+    // 関数型1で a = 0
+    let mut tag = b"para\0\0\0\0".to_vec();
+    tag.extend(1u16.to_be_bytes());
+    tag.extend([0, 0]);
+    for v in [2.2, 0.0, 0.5] {
+        tag.extend(((v * 65536.0) as i32).to_be_bytes());
+    }
+    let data = build_profile(b"GRAY", vec![(b"kTRC", tag)]);
+
+    assert!(matches!(Profile::parse(&data), Err(Error::InvalidProfile)));
+}
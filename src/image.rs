@@ -1,25 +1,55 @@
-use flate2::read::ZlibDecoder;
-use image::{DynamicImage, ImageBuffer, RgbImage};
-use jpeg_decoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageBuffer, Luma, Rgb, Rgba};
 use std::fmt;
 use std::fs::File;
-use std::io::Read;
 
 use crate::cross_reference;
+use crate::filter;
+use crate::function;
 use crate::object;
+pub use colorspace::initial_color;
+use colorspace::ColorSpace;
+pub use encoded::{CcittFaxParam, EncodedFormat, EncodedImage};
+pub use info::{ImageInfo, MaskType};
+pub use inline::expand_inline_image_dict;
+
+mod colorspace;
+mod encoded;
+mod info;
+mod inline;
+#[cfg(test)]
+mod test;
 
 #[derive(Debug)]
 pub enum Error {
     Object(object::Error),
+    Filter(filter::Error),
+    Function(function::Error),
+    Dct(jpeg_decoder::Error),
     UnsupporttedColorSpace,
-    UnsupporttedFilter,
+    TooDeepColorSpace,
+    UnsupporttedBitsPerComponent(isize),
+    InvalidDecode,
+    InvalidColorKey,
+    InvalidImageData,
+    NotImage,
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
             Error::Object(e) => write!(f, "object: {}", e),
+            Error::Filter(e) => write!(f, "filter: {}", e),
+            Error::Function(e) => write!(f, "function: {}", e),
+            Error::Dct(e) => write!(f, "dct: {}", e),
             Error::UnsupporttedColorSpace => write!(f, "colorspace is not supportted"),
-            Error::UnsupporttedFilter => write!(f, "filter is not supportted"),
+            Error::TooDeepColorSpace => write!(f, "colorspace is nested too deeply"),
+            Error::UnsupporttedBitsPerComponent(bpc) => {
+                write!(f, "bits per component `{}` is not supportted", bpc)
+            }
+            Error::InvalidDecode => write!(f, "decode array is invalid"),
+            Error::InvalidColorKey => write!(f, "color key mask is invalid"),
+            Error::InvalidImageData => write!(f, "image data is shorter than expected"),
+            Error::NotImage => write!(f, "subtype is not image"),
         }
     }
 }
@@ -28,22 +58,102 @@ impl From<object::Error> for Error {
         Self::Object(e)
     }
 }
+impl From<filter::Error> for Error {
+    fn from(e: filter::Error) -> Self {
+        Self::Filter(e)
+    }
+}
+impl From<function::Error> for Error {
+    fn from(e: function::Error) -> Self {
+        Self::Function(e)
+    }
+}
+
+// 画像をデコードする際の挙動を指定する
+#[derive(Debug, Clone, Default)]
+pub struct ImageOption {
+    // ICCBased色空間で埋め込まれたICCプロファイルを使って色変換するかどうか
+    // 偽なら代替色空間として変換する
+    pub use_icc_profile: bool,
+    // 16ビット画像を8ビットに落とさずに出力するかどうか
+    pub keep_16bit_precision: bool,
+    // ステンシルマスクを塗る色
+    // 指定すると塗る部分をこの色，それ以外を透明にしたRGBA画像を出力する
+    // 指定しなければ塗る部分を黒，それ以外を白にした2値の画像を出力する
+    pub stencil_fill_color: Option<[u8; 3]>,
+    // /SMaskや/Maskを合成して透明度付きの画像を出力するかどうか
+    pub apply_mask: bool,
+    // 画像XObjectに加えて，コンテンツストリーム中のインライン画像も取り出すかどうか
+    // コンテンツストリームを読むので，それが壊れているときは失敗する
+    pub extract_inline_images: bool,
+}
+
+// 色空間の変換をせずに画像の成分をそのまま持つ画像
+// DeviceGray/CalGray，DeviceRGB/CalRGB，DeviceCMYKと，それぞれの成分数のICCBasedは
+// /Decodeを適用した値を8ビットか16ビットで持つ
+#[derive(Debug, Clone)]
+pub enum NativeImage {
+    Luma8(ImageBuffer<Luma<u8>, Vec<u8>>),
+    Luma16(ImageBuffer<Luma<u16>, Vec<u16>>),
+    Rgb8(ImageBuffer<Rgb<u8>, Vec<u8>>),
+    Rgb16(ImageBuffer<Rgb<u16>, Vec<u16>>),
+    Cmyk8(RawImage<u8>),
+    Cmyk16(RawImage<u16>),
+    // Lab，Separation，DeviceN，Indexedなどは/Decodeを適用する前のサンプル値をそのまま持つ
+    Other {
+        bits_per_component: usize,
+        image: RawImage<u16>,
+    },
+}
+
+impl NativeImage {
+    pub fn width(&self) -> u32 {
+        match self {
+            NativeImage::Luma8(image) => image.width(),
+            NativeImage::Luma16(image) => image.width(),
+            NativeImage::Rgb8(image) => image.width(),
+            NativeImage::Rgb16(image) => image.width(),
+            NativeImage::Cmyk8(image) => image.width,
+            NativeImage::Cmyk16(image) => image.width,
+            NativeImage::Other { image, .. } => image.width,
+        }
+    }
 
-enum Filter {
-    Flate,
-    DCT,
+    pub fn height(&self) -> u32 {
+        match self {
+            NativeImage::Luma8(image) => image.height(),
+            NativeImage::Luma16(image) => image.height(),
+            NativeImage::Rgb8(image) => image.height(),
+            NativeImage::Rgb16(image) => image.height(),
+            NativeImage::Cmyk8(image) => image.height,
+            NativeImage::Cmyk16(image) => image.height,
+            NativeImage::Other { image, .. } => image.height,
+        }
+    }
 }
 
-enum ColorSpace {
-    DeviceGray,
-    DeviceRGB,
+// 画素ごとにchannel個の成分を並べた画像
+#[derive(Debug, Clone)]
+pub struct RawImage<T> {
+    pub width: u32,
+    pub height: u32,
+    pub channel: usize,
+    pub data: Vec<T>,
 }
 
 pub struct ImageDecodeParam {
     width: u32,
     height: u32,
-    colorspace: ColorSpace,
-    filter: Filter,
+    // ステンシルマスク（/ImageMask true）のときは色空間を持たない
+    colorspace: Option<ColorSpace>,
+    bits_per_component: usize,
+    decode: Vec<f64>,
+    // /Maskが配列のときは色キーマスク [min0 max0 min1 max1 ...]
+    color_key: Option<Vec<u16>>,
+    // /Filterが省略されたときは空
+    filters: Vec<String>,
+    // filtersのそれぞれに対応する/DecodeParms
+    decode_parms: Vec<Option<object::PdfDict>>,
 }
 
 impl ImageDecodeParam {
@@ -56,10 +166,10 @@ impl ImageDecodeParam {
 
         let subtype = object::PdfName::ensure(image_dict.get("Subtype").unwrap())?;
         if subtype != "Image" {
-            panic!("subtype is not image");
+            return Err(Error::NotImage);
         }
 
-        image_dict.assert_with_key(vec!["Width", "Height", "Filter"])?;
+        image_dict.assert_with_key(vec!["Width", "Height"])?;
 
         let width = object::PdfInteger::ensure(image_dict.get("Width").unwrap())?;
         let height = object::PdfInteger::ensure(image_dict.get("Height").unwrap())?;
@@ -70,16 +180,57 @@ impl ImageDecodeParam {
         height.assert_natural()?;
         let height = height.unpack() as u32;
 
-        let colorspace = get_colorspace(image_dict, file, xref)?;
-        let filter = get_filter(image_dict)?;
+        let is_image_mask = match image_dict.get("ImageMask") {
+            Some(obj) => object::PdfBoolean::ensure(obj)?.unpack(),
+            None => false,
+        };
+
+        // ステンシルマスクは常に1ビットで，Decodeの既定値は [0 1]
+        let (colorspace, bits_per_component) = if is_image_mask {
+            (None, 1)
+        } else {
+            (
+                Some(get_colorspace(image_dict, file, xref)?),
+                get_bits_per_component(image_dict)?,
+            )
+        };
+
+        let default_decode = match &colorspace {
+            Some(colorspace) => colorspace.default_decode(bits_per_component),
+            None => vec![0.0, 1.0],
+        };
+        let decode = get_decode(image_dict, default_decode)?;
+
+        let color_key = match &colorspace {
+            Some(colorspace) => get_color_key(image_dict, colorspace.n_components())?,
+            None => None,
+        };
+
+        let filters = filter::get_filter_names(image_dict)?;
+        let decode_parms = filter::get_decode_parms(image_dict, filters.len(), file, xref)?;
 
         Ok(ImageDecodeParam {
             width,
             height,
             colorspace,
-            filter,
+            bits_per_component,
+            decode,
+            color_key,
+            filters,
+            decode_parms,
         })
     }
+
+    // 画像の色空間での色をsRGBの0.0~1.0に変換する
+    // ステンシルマスクは色空間を持たないのでNoneを返す
+    pub fn color_to_rgb(&self, components: &[f64], option: &ImageOption) -> Option<[f64; 3]> {
+        match &self.colorspace {
+            Some(colorspace) if components.len() == colorspace.n_components() => {
+                Some(colorspace.to_rgb(components, option))
+            }
+            _ => None,
+        }
+    }
 }
 
 fn get_colorspace(
@@ -90,72 +241,441 @@ fn get_colorspace(
     image_dict.assert_with_key(vec!["ColorSpace"])?;
     let colorspace_obj = image_dict.get("ColorSpace").unwrap();
 
-    let colorspace = if let Ok(name) = object::PdfName::ensure(colorspace_obj) {
-        name.clone()
-    } else {
-        let indirect_ref = object::PdfIndirectRef::ensure(colorspace_obj)?;
-        let indirect_obj = indirect_ref.get_indirect_obj(file, xref)?;
+    colorspace::parse_colorspace(colorspace_obj, file, xref)
+}
+
+fn get_bits_per_component(image_dict: &object::PdfDict) -> Result<usize, Error> {
+    // 本来は必須だが省略されているものもあるので8ビットとみなす
+    let bits_per_component = match image_dict.get("BitsPerComponent") {
+        Some(obj) => object::PdfInteger::ensure(obj)?.unpack(),
+        None => 8,
+    };
 
-        let may_name = object::PdfIndirectObj::ensure(&indirect_obj)?.get_object();
-        let name = object::PdfName::ensure(may_name)?;
+    match bits_per_component {
+        1 | 2 | 4 | 8 | 16 => Ok(bits_per_component as usize),
+        _ => Err(Error::UnsupporttedBitsPerComponent(bits_per_component)),
+    }
+}
 
-        name.clone()
+// /Decodeは各成分のサンプル値を写す範囲 [Dmin0 Dmax0 Dmin1 Dmax1 ...]
+fn get_decode(image_dict: &object::PdfDict, default_decode: Vec<f64>) -> Result<Vec<f64>, Error> {
+    let decode_obj = match image_dict.get("Decode") {
+        Some(obj) => object::PdfArray::ensure(obj)?,
+        None => return Ok(default_decode),
     };
 
-    Ok(match colorspace.as_str() {
-        "DeviceRGB" => ColorSpace::DeviceRGB,
-        "DeviceGray" => ColorSpace::DeviceGray,
-        _ => return Err(Error::UnsupporttedColorSpace),
-    })
+    let mut decode = vec![];
+    for obj in decode_obj {
+        decode.push(object::PdfReal::ensure_number(obj)?);
+    }
+
+    if decode.len() != default_decode.len() {
+        return Err(Error::InvalidDecode);
+    }
+
+    Ok(decode)
 }
 
-fn get_filter(image_dict: &object::PdfDict) -> Result<Filter, Error> {
-    image_dict.assert_with_key(vec!["Filter"])?;
-    let filter_obj = image_dict.get("Filter").unwrap();
+// /Maskはステンシルマスクの画像XObjectか色キーマスクの配列
+// ここでは配列の場合のみを扱う
+fn get_color_key(image_dict: &object::PdfDict, n: usize) -> Result<Option<Vec<u16>>, Error> {
+    // /SMaskがあるときは/Maskは無視される
+    if image_dict.get("SMask").is_some() {
+        return Ok(None);
+    }
 
-    let filter = if let Ok(array_obj) = object::PdfArray::ensure(filter_obj) {
-        match array_obj.get(0) {
-            Some(name) => object::PdfName::ensure(name)?,
-            None => return Err(Error::UnsupporttedFilter),
-        }
-    } else {
-        object::PdfName::ensure(filter_obj)?
+    let color_key_obj = match image_dict.get("Mask") {
+        Some(obj) => match object::PdfArray::ensure(obj) {
+            Ok(array) => array,
+            Err(_) => return Ok(None),
+        },
+        None => return Ok(None),
     };
 
-    Ok(match filter.as_str() {
-        "FlateDecode" => Filter::Flate,
-        "DCTDecode" => Filter::DCT,
-        _ => return Err(Error::UnsupporttedFilter),
-    })
+    let mut color_key = vec![];
+    for obj in color_key_obj {
+        let value = object::PdfInteger::ensure(obj)?;
+        value.assert_not_negative()?;
+        color_key.push(value.unpack().min(u16::MAX as isize) as u16);
+    }
+
+    if color_key.len() != 2 * n {
+        return Err(Error::InvalidColorKey);
+    }
+
+    Ok(Some(color_key))
+}
+
+// フィルタを解いたデータとそのビット数を返す
+fn decompress(image: &ImageDecodeParam, image_bytes: &[u8]) -> Result<(Vec<u8>, usize), Error> {
+    match image.filters.split_last() {
+        // DCTDecodeの出力は常に8ビット
+        Some((last_filter, preceding_filters)) if last_filter == "DCTDecode" => {
+            let jpeg_bytes = filter::apply_filters(
+                image_bytes.to_vec(),
+                preceding_filters,
+                &image.decode_parms,
+            )?;
+
+            let mut decoder = jpeg_decoder::Decoder::new(jpeg_bytes.as_slice());
+            Ok((decoder.decode().map_err(Error::Dct)?, 8))
+        }
+        _ => Ok((
+            filter::apply_filters(image_bytes.to_vec(), &image.filters, &image.decode_parms)?,
+            image.bits_per_component,
+        )),
+    }
 }
 
-pub fn decode_image(image: &ImageDecodeParam, image_bytes: &[u8]) -> Result<RgbImage, Error> {
-    let decoded = match image.filter {
-        Filter::Flate => {
-            let deflater = ZlibDecoder::new(image_bytes);
+pub fn decode_image(
+    image: &ImageDecodeParam,
+    image_bytes: &[u8],
+    option: &ImageOption,
+) -> Result<DynamicImage, Error> {
+    let (decoded, bits_per_component) = decompress(image, image_bytes)?;
+
+    let width = image.width;
+    let height = image.height;
+
+    let colorspace = match &image.colorspace {
+        Some(colorspace) => colorspace,
+        None => return decode_stencil_mask(image, &decoded, option),
+    };
 
-            let decoded: Result<Vec<u8>, _> = deflater.bytes().collect();
-            decoded.unwrap()
+    let n = colorspace.n_components();
+    let samples = unpack_samples(
+        &decoded,
+        width as usize,
+        height as usize,
+        n,
+        bits_per_component,
+    )?;
+
+    let max_sample = ((1u32 << bits_per_component) - 1) as f64;
+    let is_16bit = bits_per_component == 16 && option.keep_16bit_precision;
+
+    // 色キーマスクはDecodeを適用する前のサンプル値で判定する
+    let color_key_mask = match &image.color_key {
+        Some(color_key) if option.apply_mask => {
+            Some(build_color_key_mask(&samples, color_key, n, width, height))
         }
-        Filter::DCT => {
-            let mut decoder = jpeg_decoder::Decoder::new(image_bytes);
-            decoder.decode().unwrap()
+        _ => None,
+    };
+
+    // サンプル値を/Decodeに従って各成分の値に写す
+    let decode = &image.decode;
+    let components = samples.iter().enumerate().map(|(i, sample)| {
+        let (min, max) = (decode[2 * (i % n)], decode[2 * (i % n) + 1]);
+        min + (*sample as f64 / max_sample) * (max - min)
+    });
+
+    let image_result = match colorspace {
+        ColorSpace::DeviceGray => to_dynamic_image(width, height, components, 1, is_16bit),
+        ColorSpace::DeviceRGB => to_dynamic_image(width, height, components, 3, is_16bit),
+        _ => {
+            let components: Vec<f64> = components.collect();
+            let mut rgb: Vec<f64> = Vec::with_capacity(components.len() / n * 3);
+
+            for pixel in components.chunks_exact(n) {
+                rgb.extend(colorspace.to_rgb(pixel, option));
+            }
+
+            to_dynamic_image(width, height, rgb.into_iter(), 3, is_16bit)
         }
     };
 
+    Ok(match color_key_mask {
+        Some(mask) => apply_soft_mask(image_result, &mask, None),
+        None => image_result,
+    })
+}
+
+// 色空間を変換せずにデコードする
+// マスクは合成しない
+pub fn decode_image_native(
+    image: &ImageDecodeParam,
+    image_bytes: &[u8],
+) -> Result<NativeImage, Error> {
+    let (decoded, bits_per_component) = decompress(image, image_bytes)?;
+
+    to_native_image(image, &decoded, bits_per_component)
+}
+
+fn to_native_image(
+    image: &ImageDecodeParam,
+    decoded: &[u8],
+    bits_per_component: usize,
+) -> Result<NativeImage, Error> {
     let width = image.width;
     let height = image.height;
 
-    let image_result = match image.colorspace {
-        ColorSpace::DeviceRGB => DynamicImage::ImageRgb8(
-            ImageBuffer::<image::Rgb<u8>, Vec<u8>>::from_raw(width, height, decoded).unwrap(),
-        ),
-        ColorSpace::DeviceGray => DynamicImage::ImageLuma8(
-            ImageBuffer::<image::Luma<u8>, Vec<u8>>::from_raw(width, height, decoded).unwrap(),
-        ),
+    let colorspace = match &image.colorspace {
+        Some(colorspace) => colorspace,
+        // ステンシルマスクは塗る部分を黒とした2値のグレー画像にする
+        None => {
+            let mask = decode_stencil_mask(image, decoded, &ImageOption::default())?;
+            return Ok(NativeImage::Luma8(mask.into_luma8()));
+        }
+    };
+
+    let n = colorspace.n_components();
+    let samples = unpack_samples(
+        decoded,
+        width as usize,
+        height as usize,
+        n,
+        bits_per_component,
+    )?;
+
+    let is_device_like = !matches!(
+        colorspace,
+        ColorSpace::Lab(_)
+            | ColorSpace::Separation(_)
+            | ColorSpace::DeviceN(_)
+            | ColorSpace::Indexed(_)
+    );
+    if !is_device_like || !matches!(n, 1 | 3 | 4) {
+        return Ok(NativeImage::Other {
+            bits_per_component,
+            image: RawImage {
+                width,
+                height,
+                channel: n,
+                data: samples,
+            },
+        });
+    }
+
+    let max_sample = ((1u32 << bits_per_component) - 1) as f64;
+    let decode = &image.decode;
+    let components = samples.iter().enumerate().map(|(i, sample)| {
+        let (min, max) = (decode[2 * (i % n)], decode[2 * (i % n) + 1]);
+        (min + (*sample as f64 / max_sample) * (max - min)).clamp(0.0, 1.0)
+    });
+
+    Ok(if bits_per_component == 16 {
+        let buffer: Vec<u16> = components.map(|v| (v * 65535.0).round() as u16).collect();
+
+        match n {
+            1 => NativeImage::Luma16(ImageBuffer::from_raw(width, height, buffer).unwrap()),
+            3 => NativeImage::Rgb16(ImageBuffer::from_raw(width, height, buffer).unwrap()),
+            _ => NativeImage::Cmyk16(RawImage {
+                width,
+                height,
+                channel: 4,
+                data: buffer,
+            }),
+        }
+    } else {
+        let buffer: Vec<u8> = components.map(|v| (v * 255.0).round() as u8).collect();
+
+        match n {
+            1 => NativeImage::Luma8(ImageBuffer::from_raw(width, height, buffer).unwrap()),
+            3 => NativeImage::Rgb8(ImageBuffer::from_raw(width, height, buffer).unwrap()),
+            _ => NativeImage::Cmyk8(RawImage {
+                width,
+                height,
+                channel: 4,
+                data: buffer,
+            }),
+        }
+    })
+}
+
+// 全ての成分が色キーの範囲に入っている画素を透明にするマスクを作る
+fn build_color_key_mask(
+    samples: &[u16],
+    color_key: &[u16],
+    n: usize,
+    width: u32,
+    height: u32,
+) -> DynamicImage {
+    let alpha: Vec<u8> = samples
+        .chunks_exact(n)
+        .map(|pixel| {
+            let is_masked = pixel
+                .iter()
+                .enumerate()
+                .all(|(i, v)| color_key[2 * i] <= *v && *v <= color_key[2 * i + 1]);
+
+            if is_masked {
+                0
+            } else {
+                255
+            }
+        })
+        .collect();
+
+    DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, alpha).unwrap())
+}
+
+// ステンシルマスクを透明度として使えるように，塗る部分を不透明，それ以外を透明にする
+pub fn stencil_mask_to_alpha(stencil_mask: &DynamicImage) -> DynamicImage {
+    let mut alpha = stencil_mask.to_luma8();
+    image::imageops::invert(&mut alpha);
+
+    DynamicImage::ImageLuma8(alpha)
+}
+
+// ステンシルマスクは塗るかどうかだけを表す1ビットの画像
+// Decodeが [0 1] ならサンプル値0の部分を，[1 0] なら1の部分を塗る
+fn decode_stencil_mask(
+    image: &ImageDecodeParam,
+    decoded: &[u8],
+    option: &ImageOption,
+) -> Result<DynamicImage, Error> {
+    let samples = unpack_samples(decoded, image.width as usize, image.height as usize, 1, 1)?;
+
+    let paint_sample = if image.decode[0] < image.decode[1] {
+        0
+    } else {
+        1
     };
+    let is_painted = samples.iter().map(|sample| *sample == paint_sample);
+
+    Ok(match option.stencil_fill_color {
+        Some(color) => {
+            let rgba: Vec<u8> = is_painted
+                .flat_map(|painted| {
+                    if painted {
+                        [color[0], color[1], color[2], 255]
+                    } else {
+                        [0, 0, 0, 0]
+                    }
+                })
+                .collect();
+
+            DynamicImage::ImageRgba8(
+                ImageBuffer::from_raw(image.width, image.height, rgba).unwrap(),
+            )
+        }
+        None => {
+            let luma: Vec<u8> = is_painted
+                .map(|painted| if painted { 0 } else { 255 })
+                .collect();
+
+            DynamicImage::ImageLuma8(
+                ImageBuffer::from_raw(image.width, image.height, luma).unwrap(),
+            )
+        }
+    })
+}
+
+// ソフトマスクを透明度として合成したRGBA画像を作る
+// マスクの大きさが画像と異なる場合は画像の大きさに合わせて拡大縮小する
+// matteが与えられたときは，マスクで事前に乗算された色を元に戻す
+pub fn apply_soft_mask(
+    image: DynamicImage,
+    mask: &DynamicImage,
+    matte: Option<[f64; 3]>,
+) -> DynamicImage {
+    let width = image.width();
+    let height = image.height();
+
+    let mut mask = mask.to_luma16();
+    if mask.width() != width || mask.height() != height {
+        mask = image::imageops::resize(&mask, width, height, FilterType::Triangle);
+    }
+
+    let is_16bit = matches!(
+        image,
+        DynamicImage::ImageLuma16(_) | DynamicImage::ImageRgb16(_)
+    );
+
+    let mut rgba = image.into_rgba16();
+    for (pixel, alpha) in rgba.pixels_mut().zip(mask.pixels()) {
+        let alpha = alpha[0];
+
+        if let Some(matte) = matte {
+            let a = alpha as f64 / 65535.0;
+            if a > 0.0 {
+                for (c, m) in pixel.0.iter_mut().zip(matte.iter()) {
+                    let c_value = *c as f64 / 65535.0;
+                    let unmultiplied = m + (c_value - m) / a;
+                    *c = (unmultiplied.clamp(0.0, 1.0) * 65535.0).round() as u16;
+                }
+            }
+        }
+
+        *pixel = Rgba([pixel[0], pixel[1], pixel[2], alpha]);
+    }
+
+    let rgba = DynamicImage::ImageRgba16(rgba);
+    if is_16bit {
+        rgba
+    } else {
+        DynamicImage::ImageRgba8(rgba.into_rgba8())
+    }
+}
 
-    let image_result = image_result.into_rgb8();
+// 0.0~1.0の値の列を8ビットか16ビットの画像にする
+// channelが1ならグレー，3ならRGBとして扱う
+fn to_dynamic_image(
+    width: u32,
+    height: u32,
+    values: impl Iterator<Item = f64>,
+    channel: usize,
+    is_16bit: bool,
+) -> DynamicImage {
+    if is_16bit {
+        let buffer: Vec<u16> = values
+            .map(|v| (v.clamp(0.0, 1.0) * 65535.0).round() as u16)
+            .collect();
+
+        if channel == 1 {
+            DynamicImage::ImageLuma16(ImageBuffer::from_raw(width, height, buffer).unwrap())
+        } else {
+            DynamicImage::ImageRgb16(ImageBuffer::from_raw(width, height, buffer).unwrap())
+        }
+    } else {
+        let buffer: Vec<u8> = values
+            .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+
+        if channel == 1 {
+            DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, buffer).unwrap())
+        } else {
+            DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, buffer).unwrap())
+        }
+    }
+}
+
+// 1/2/4/8/16ビットで詰められたサンプル列を1サンプルずつに分ける
+// 各行の先頭はバイト境界に揃えられているので行末の余りビットは読み飛ばす
+fn unpack_samples(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    n: usize,
+    bits_per_component: usize,
+) -> Result<Vec<u16>, Error> {
+    let samples_per_row = width * n;
+    let bytes_per_row = (samples_per_row * bits_per_component).div_ceil(8);
+
+    if data.len() < bytes_per_row * height {
+        return Err(Error::InvalidImageData);
+    }
+
+    let mut samples = Vec::with_capacity(samples_per_row * height);
+    for row in data.chunks_exact(bytes_per_row).take(height) {
+        match bits_per_component {
+            8 => samples.extend(row.iter().map(|b| *b as u16)),
+            16 => samples.extend(
+                row.chunks_exact(2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]])),
+            ),
+            _ => {
+                let mask = ((1u16 << bits_per_component) - 1) as u8;
+                let samples_per_byte = 8 / bits_per_component;
+
+                for i in 0..samples_per_row {
+                    let byte = row[i / samples_per_byte];
+                    let shift = 8 - bits_per_component * (i % samples_per_byte + 1);
+                    samples.push(((byte >> shift) & mask) as u16);
+                }
+            }
+        }
+    }
 
-    Ok(image_result)
+    Ok(samples)
 }
@@ -0,0 +1,595 @@
+use std::fs::File;
+
+use super::{Error, ImageOption};
+use crate::cie;
+use crate::cross_reference;
+use crate::filter;
+use crate::function::Function;
+use crate::icc;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// Indexedの基底やSeparationの代替色空間として入れ子にできる深さ
+// 自身を参照するような壊れた色空間で無限に再帰しないようにする
+const MAX_COLORSPACE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRGB,
+    DeviceCMYK,
+    CalGray(CalGray),
+    CalRGB(CalRGB),
+    Lab(Lab),
+    ICCBased(Box<IccBased>),
+    Separation(Box<Separation>),
+    DeviceN(Box<DeviceN>),
+    Indexed(Box<Indexed>),
+}
+
+#[derive(Debug, Clone)]
+pub struct CalGray {
+    white_point: [f64; 3],
+    gamma: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalRGB {
+    white_point: [f64; 3],
+    gamma: [f64; 3],
+    // [XA YA ZA XB YB ZB XC YC ZC]
+    matrix: [f64; 9],
+}
+
+#[derive(Debug, Clone)]
+pub struct Lab {
+    white_point: [f64; 3],
+    // [amin amax bmin bmax]
+    range: [f64; 4],
+}
+
+#[derive(Debug, Clone)]
+pub struct IccBased {
+    n: usize,
+    alternate: ColorSpace,
+    profile: Option<icc::Profile>,
+}
+
+// 特色1色を代替色空間での色に変換する
+#[derive(Debug, Clone)]
+pub struct Separation {
+    // /Noneのときは何も描画しない
+    is_none: bool,
+    alternate: ColorSpace,
+    tint_transform: Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceN {
+    n: usize,
+    alternate: ColorSpace,
+    tint_transform: Function,
+}
+
+// 色番号から基底色空間の色を引く
+#[derive(Debug, Clone)]
+pub struct Indexed {
+    base: ColorSpace,
+    hival: usize,
+    // 各色について基底色空間の成分を0~255で並べたもの
+    lookup: Vec<u8>,
+}
+
+impl ColorSpace {
+    pub fn n_components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray => 1,
+            ColorSpace::DeviceRGB => 3,
+            ColorSpace::DeviceCMYK => 4,
+            ColorSpace::CalGray(_) => 1,
+            ColorSpace::CalRGB(_) => 3,
+            ColorSpace::Lab(_) => 3,
+            ColorSpace::ICCBased(icc_based) => icc_based.n,
+            ColorSpace::Separation(_) => 1,
+            ColorSpace::DeviceN(device_n) => device_n.n,
+            ColorSpace::Indexed(_) => 1,
+        }
+    }
+
+    // 各成分の値域 [min0 max0 min1 max1 ...]
+    // 画像のサンプル値は既定でこの範囲に写される
+    pub fn default_decode(&self, bits_per_component: usize) -> Vec<f64> {
+        match self {
+            // 色番号はサンプル値そのもの
+            ColorSpace::Indexed(_) => vec![0.0, ((1u32 << bits_per_component) - 1) as f64],
+            ColorSpace::Lab(lab) => vec![
+                0.0,
+                100.0,
+                lab.range[0],
+                lab.range[1],
+                lab.range[2],
+                lab.range[3],
+            ],
+            _ => [0.0, 1.0].repeat(self.n_components()),
+        }
+    }
+
+    // default_decodeの範囲にある色成分をsRGBの0.0~1.0に変換する
+    pub fn to_rgb(&self, components: &[f64], option: &ImageOption) -> [f64; 3] {
+        match self {
+            ColorSpace::DeviceGray => [components[0], components[0], components[0]],
+            ColorSpace::DeviceRGB => [components[0], components[1], components[2]],
+            ColorSpace::DeviceCMYK => {
+                let k = components[3];
+                [
+                    (1.0 - components[0]) * (1.0 - k),
+                    (1.0 - components[1]) * (1.0 - k),
+                    (1.0 - components[2]) * (1.0 - k),
+                ]
+            }
+            ColorSpace::CalGray(cal_gray) => {
+                let a = components[0].clamp(0.0, 1.0).powf(cal_gray.gamma);
+                let w = cal_gray.white_point;
+
+                cie::xyz_to_srgb([w[0] * a, w[1] * a, w[2] * a], w)
+            }
+            ColorSpace::CalRGB(cal_rgb) => {
+                let mut abc = [0.0; 3];
+                for (i, v) in abc.iter_mut().enumerate() {
+                    *v = components[i].clamp(0.0, 1.0).powf(cal_rgb.gamma[i]);
+                }
+
+                let m = cal_rgb.matrix;
+                let xyz = [
+                    m[0] * abc[0] + m[3] * abc[1] + m[6] * abc[2],
+                    m[1] * abc[0] + m[4] * abc[1] + m[7] * abc[2],
+                    m[2] * abc[0] + m[5] * abc[1] + m[8] * abc[2],
+                ];
+
+                cie::xyz_to_srgb(xyz, cal_rgb.white_point)
+            }
+            ColorSpace::Lab(lab) => {
+                let l = components[0].clamp(0.0, 100.0);
+                let a = components[1].clamp(lab.range[0], lab.range[1]);
+                let b = components[2].clamp(lab.range[2], lab.range[3]);
+
+                let m = (l + 16.0) / 116.0;
+                let g = |x: f64| {
+                    if x >= 6.0 / 29.0 {
+                        x * x * x
+                    } else {
+                        108.0 / 841.0 * (x - 4.0 / 29.0)
+                    }
+                };
+
+                let w = lab.white_point;
+                let xyz = [
+                    w[0] * g(m + a / 500.0),
+                    w[1] * g(m),
+                    w[2] * g(m - b / 200.0),
+                ];
+
+                cie::xyz_to_srgb(xyz, w)
+            }
+            ColorSpace::ICCBased(icc_based) => match &icc_based.profile {
+                Some(profile) if option.use_icc_profile => profile.to_srgb(components),
+                _ => icc_based.alternate.to_rgb(components, option),
+            },
+            ColorSpace::Separation(separation) => {
+                if separation.is_none {
+                    return [1.0, 1.0, 1.0];
+                }
+
+                let alternate_components = separation.tint_transform.eval(components);
+                separation.alternate.to_rgb(&alternate_components, option)
+            }
+            ColorSpace::DeviceN(device_n) => {
+                let alternate_components = device_n.tint_transform.eval(components);
+                device_n.alternate.to_rgb(&alternate_components, option)
+            }
+            ColorSpace::Indexed(indexed) => {
+                let index = (components[0].round().max(0.0) as usize).min(indexed.hival);
+
+                // 参照表の0~255を基底色空間の各成分の値域に写す
+                let m = indexed.base.n_components();
+                let base_decode = indexed.base.default_decode(8);
+                let base_components: Vec<f64> = (0..m)
+                    .map(|i| {
+                        let value = indexed.lookup.get(index * m + i).copied().unwrap_or(0);
+                        let (min, max) = (base_decode[2 * i], base_decode[2 * i + 1]);
+                        min + (value as f64 / 255.0) * (max - min)
+                    })
+                    .collect();
+
+                indexed.base.to_rgb(&base_components, option)
+            }
+        }
+    }
+}
+
+pub fn parse_colorspace(
+    colorspace_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<ColorSpace, Error> {
+    parse_nested_colorspace(colorspace_obj, file, xref, 0)
+}
+
+fn parse_nested_colorspace(
+    colorspace_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<ColorSpace, Error> {
+    if depth > MAX_COLORSPACE_DEPTH {
+        return Err(Error::TooDeepColorSpace);
+    }
+
+    let colorspace_obj = object::resolve(colorspace_obj, file, xref)?;
+
+    if let Ok(name) = object::PdfName::ensure(&colorspace_obj) {
+        return colorspace_from_name(name.as_str());
+    }
+
+    let array = object::PdfArray::ensure(&colorspace_obj)?;
+    let family = match array.get(0) {
+        Some(obj) => object::PdfName::ensure(obj)?,
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+
+    match family.as_str() {
+        "CalGray" => parse_cal_gray(array, file, xref),
+        "CalRGB" => parse_cal_rgb(array, file, xref),
+        "Lab" => parse_lab(array, file, xref),
+        "ICCBased" => parse_icc_based(array, file, xref, depth),
+        "Separation" => parse_separation(array, file, xref, depth),
+        "DeviceN" => parse_device_n(array, file, xref, depth),
+        "Indexed" => parse_indexed(array, file, xref, depth),
+        // 引数を取らない色空間が配列で指定されることもある
+        name => colorspace_from_name(name),
+    }
+}
+
+// 色空間を解釈せずに，人が読める説明にする
+// ICCプロファイルや色変換関数のストリームは読まない
+pub fn describe_colorspace(
+    colorspace_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<String, Error> {
+    describe_nested_colorspace(colorspace_obj, file, xref, 0)
+}
+
+fn describe_nested_colorspace(
+    colorspace_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<String, Error> {
+    if depth > MAX_COLORSPACE_DEPTH {
+        return Err(Error::TooDeepColorSpace);
+    }
+
+    let colorspace_obj = object::resolve(colorspace_obj, file, xref)?;
+
+    if let Ok(name) = object::PdfName::ensure(&colorspace_obj) {
+        return Ok(String::from(name.as_str()));
+    }
+
+    let array = object::PdfArray::ensure(&colorspace_obj)?;
+    let family = match array.get(0) {
+        Some(obj) => object::PdfName::ensure(obj)?.as_str(),
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+
+    Ok(match (family, array.get(1)) {
+        ("ICCBased", Some(stream_obj)) => {
+            let stream_obj = object::resolve(stream_obj, file, xref)?;
+            let stream_obj = object::PdfStreamObj::ensure_stream(&stream_obj)?;
+
+            match stream_obj.dict.get("N") {
+                Some(n) => format!("ICCBased(N={})", object::PdfInteger::ensure(n)?.unpack()),
+                None => String::from("ICCBased"),
+            }
+        }
+        ("Indexed", Some(base_obj)) => {
+            let base = describe_nested_colorspace(base_obj, file, xref, depth + 1)?;
+            match array.get(2) {
+                Some(hival) => format!(
+                    "Indexed({}, {})",
+                    base,
+                    object::PdfInteger::ensure(hival)?.unpack()
+                ),
+                None => format!("Indexed({})", base),
+            }
+        }
+        ("Separation", Some(name)) => {
+            format!("Separation({})", object::PdfName::ensure(name)?.as_str())
+        }
+        ("DeviceN", Some(names)) => {
+            let names = object::resolve(names, file, xref)?;
+            format!("DeviceN({})", object::PdfArray::ensure(&names)?.len())
+        }
+        (family, _) => String::from(family),
+    })
+}
+
+// csで色空間を選んだときの説明と成分の初期値
+// SeparationとDeviceNは1，それ以外は0から始まる
+// 解釈できない色空間（Patternなど）は成分の数が分からないので空にする
+pub fn initial_color(
+    colorspace_obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<(String, Vec<f64>), Error> {
+    let description = describe_colorspace(colorspace_obj, file, xref)?;
+
+    let components = match parse_colorspace(colorspace_obj, file, xref) {
+        Ok(colorspace @ (ColorSpace::Separation(_) | ColorSpace::DeviceN(_))) => {
+            vec![1.0; colorspace.n_components()]
+        }
+        Ok(colorspace) => vec![0.0; colorspace.n_components()],
+        Err(_) => vec![],
+    };
+
+    Ok((description, components))
+}
+
+fn colorspace_from_name(name: &str) -> Result<ColorSpace, Error> {
+    Ok(match name {
+        "DeviceGray" => ColorSpace::DeviceGray,
+        "DeviceRGB" => ColorSpace::DeviceRGB,
+        "DeviceCMYK" => ColorSpace::DeviceCMYK,
+        _ => return Err(Error::UnsupporttedColorSpace),
+    })
+}
+
+// CIEベースの色空間は [/CalGray dict] のように第2要素に辞書を持つ
+fn get_cie_dict(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<object::PdfDict, Error> {
+    let dict_obj = match array.get(1) {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+
+    // BlackPointは多くの場合 [0 0 0] なので考慮しない
+    let dict = object::PdfDict::ensure_with_key(&dict_obj, vec!["WhitePoint"])?;
+
+    Ok(dict.clone())
+}
+
+fn get_numbers<const N: usize>(
+    dict: &object::PdfDict,
+    key: &'static str,
+    default: [f64; N],
+) -> Result<[f64; N], Error> {
+    let array = match dict.get(key) {
+        Some(obj) => object::PdfArray::ensure(obj)?,
+        None => return Ok(default),
+    };
+
+    if array.len() != N {
+        return Err(Error::UnsupporttedColorSpace);
+    }
+
+    let mut numbers = [0.0; N];
+    for (number, obj) in numbers.iter_mut().zip(array) {
+        *number = object::PdfReal::ensure_number(obj)?;
+    }
+
+    Ok(numbers)
+}
+
+// [/CalGray << /WhitePoint [...] /Gamma g >>]
+fn parse_cal_gray(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<ColorSpace, Error> {
+    let dict = get_cie_dict(array, file, xref)?;
+
+    let white_point = get_numbers(&dict, "WhitePoint", [0.0; 3])?;
+    let gamma = match dict.get("Gamma") {
+        Some(obj) => object::PdfReal::ensure_number(obj)?,
+        None => 1.0,
+    };
+
+    Ok(ColorSpace::CalGray(CalGray { white_point, gamma }))
+}
+
+// [/CalRGB << /WhitePoint [...] /Gamma [...] /Matrix [...] >>]
+fn parse_cal_rgb(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<ColorSpace, Error> {
+    let dict = get_cie_dict(array, file, xref)?;
+
+    let white_point = get_numbers(&dict, "WhitePoint", [0.0; 3])?;
+    let gamma = get_numbers(&dict, "Gamma", [1.0; 3])?;
+    let matrix = get_numbers(
+        &dict,
+        "Matrix",
+        [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+    )?;
+
+    Ok(ColorSpace::CalRGB(CalRGB {
+        white_point,
+        gamma,
+        matrix,
+    }))
+}
+
+// [/Lab << /WhitePoint [...] /Range [...] >>]
+fn parse_lab(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<ColorSpace, Error> {
+    let dict = get_cie_dict(array, file, xref)?;
+
+    let white_point = get_numbers(&dict, "WhitePoint", [0.0; 3])?;
+    let range = get_numbers(&dict, "Range", [-100.0, 100.0, -100.0, 100.0])?;
+
+    Ok(ColorSpace::Lab(Lab { white_point, range }))
+}
+
+// [/ICCBased stream]
+fn parse_icc_based(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<ColorSpace, Error> {
+    let stream_obj = match array.get(1) {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+    let stream_obj = object::PdfStreamObj::ensure_stream(&stream_obj)?;
+
+    stream_obj.dict.assert_with_key(vec!["N"])?;
+    let n = object::PdfInteger::ensure(stream_obj.dict.get("N").unwrap())?;
+    n.assert_natural()?;
+    let n = n.unpack() as usize;
+
+    // /Alternateが省略されたときは成分数から対応するデバイス色空間を選ぶ
+    let alternate = match stream_obj.dict.get("Alternate") {
+        Some(alternate_obj) => parse_nested_colorspace(alternate_obj, file, xref, depth + 1)?,
+        None => match n {
+            1 => ColorSpace::DeviceGray,
+            3 => ColorSpace::DeviceRGB,
+            4 => ColorSpace::DeviceCMYK,
+            _ => return Err(Error::UnsupporttedColorSpace),
+        },
+    };
+
+    if alternate.n_components() != n {
+        return Err(Error::UnsupporttedColorSpace);
+    }
+
+    // プロファイルが読めなくても代替色空間で表示できるので失敗は無視する
+    let profile = filter::decode_stream(stream_obj, file, xref)
+        .ok()
+        .and_then(|data| icc::Profile::parse(&data).ok())
+        .filter(|profile| profile.n_components() == n);
+
+    Ok(ColorSpace::ICCBased(Box::new(IccBased {
+        n,
+        alternate,
+        profile,
+    })))
+}
+
+// 代替色空間と色変換関数を読み，色変換関数の出力数が代替色空間の成分数と合っていることを確かめる
+fn parse_alternate_and_tint_transform(
+    array: &object::PdfArray,
+    n: usize,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<(ColorSpace, Function), Error> {
+    let (alternate_obj, tint_transform_obj) = match (array.get(2), array.get(3)) {
+        (Some(alternate_obj), Some(tint_transform_obj)) => (alternate_obj, tint_transform_obj),
+        _ => return Err(Error::UnsupporttedColorSpace),
+    };
+
+    let alternate = parse_nested_colorspace(alternate_obj, file, xref, depth + 1)?;
+    let tint_transform = Function::parse(tint_transform_obj, file, xref)?;
+
+    if tint_transform.n_inputs() != n || tint_transform.n_outputs() != alternate.n_components() {
+        return Err(Error::UnsupporttedColorSpace);
+    }
+
+    Ok((alternate, tint_transform))
+}
+
+// [/Separation name alternateSpace tintTransform]
+fn parse_separation(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<ColorSpace, Error> {
+    let name = match array.get(1) {
+        Some(obj) => object::PdfName::ensure(obj)?,
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+    let is_none = name == "None";
+
+    let (alternate, tint_transform) =
+        parse_alternate_and_tint_transform(array, 1, file, xref, depth)?;
+
+    Ok(ColorSpace::Separation(Box::new(Separation {
+        is_none,
+        alternate,
+        tint_transform,
+    })))
+}
+
+// [/DeviceN names alternateSpace tintTransform attributes]
+fn parse_device_n(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<ColorSpace, Error> {
+    let names = match array.get(1) {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Err(Error::UnsupporttedColorSpace),
+    };
+    let n = object::PdfArray::ensure(&names)?.len();
+    if n == 0 {
+        return Err(Error::UnsupporttedColorSpace);
+    }
+
+    let (alternate, tint_transform) =
+        parse_alternate_and_tint_transform(array, n, file, xref, depth)?;
+
+    Ok(ColorSpace::DeviceN(Box::new(DeviceN {
+        n,
+        alternate,
+        tint_transform,
+    })))
+}
+
+// [/Indexed base hival lookup]
+// lookupは文字列かストリーム
+fn parse_indexed(
+    array: &object::PdfArray,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+    depth: usize,
+) -> Result<ColorSpace, Error> {
+    let (base_obj, hival_obj, lookup_obj) = match (array.get(1), array.get(2), array.get(3)) {
+        (Some(base_obj), Some(hival_obj), Some(lookup_obj)) => (base_obj, hival_obj, lookup_obj),
+        _ => return Err(Error::UnsupporttedColorSpace),
+    };
+
+    let base = parse_nested_colorspace(base_obj, file, xref, depth + 1)?;
+    // 色番号の色をさらに引くことはできない
+    if matches!(base, ColorSpace::Indexed(_)) {
+        return Err(Error::UnsupporttedColorSpace);
+    }
+
+    let hival = object::PdfInteger::ensure(hival_obj)?;
+    hival.assert_not_negative()?;
+    let hival = hival.unpack() as usize;
+
+    let lookup_obj = object::resolve(lookup_obj, file, xref)?;
+    let lookup = match &lookup_obj {
+        Object::StreamObj(stream_obj) => filter::decode_stream(stream_obj, file, xref)?,
+        obj => object::PdfString::ensure(obj)?.as_bytes().to_vec(),
+    };
+
+    Ok(ColorSpace::Indexed(Box::new(Indexed {
+        base,
+        hival,
+        lookup,
+    })))
+}
@@ -0,0 +1,154 @@
+use super::*;
+use crate::test_util;
+
+fn assert_near(actual: [f64; 3], expected: [f64; 3]) {
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        if (a - e).abs() > 0.01 {
+            panic!("left: {:?} right: {:?}", actual, expected);
+        }
+    }
+}
+
+#[test]
+fn cal_gray_to_rgb() {
+    let colorspace = ColorSpace::CalGray(CalGray {
+        white_point: cie::D50,
+        gamma: 2.2,
+    });
+    let option = ImageOption::default();
+
+    assert_near(colorspace.to_rgb(&[1.0], &option), [1.0, 1.0, 1.0]);
+    assert_near(colorspace.to_rgb(&[0.0], &option), [0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn cal_rgb_to_rgb() {
+    // sRGBの原色をD65基準で与えたCalRGB
+    let colorspace = ColorSpace::CalRGB(CalRGB {
+        white_point: cie::D65,
+        gamma: [1.0; 3],
+        matrix: [
+            0.4124, 0.2126, 0.0193, 0.3576, 0.7152, 0.1192, 0.1805, 0.0722, 0.9505,
+        ],
+    });
+    let option = ImageOption::default();
+
+    assert_near(
+        colorspace.to_rgb(&[1.0, 0.0, 0.0], &option),
+        [1.0, 0.0, 0.0],
+    );
+    assert_near(
+        colorspace.to_rgb(&[0.0, 1.0, 0.0], &option),
+        [0.0, 1.0, 0.0],
+    );
+    assert_near(
+        colorspace.to_rgb(&[1.0, 1.0, 1.0], &option),
+        [1.0, 1.0, 1.0],
+    );
+}
+
+#[test]
+fn lab_to_rgb() {
+    let colorspace = ColorSpace::Lab(Lab {
+        white_point: cie::D50,
+        range: [-128.0, 127.0, -128.0, 127.0],
+    });
+    let option = ImageOption::default();
+
+    assert_eq!(
+        colorspace.default_decode(8),
+        vec![0.0, 100.0, -128.0, 127.0, -128.0, 127.0]
+    );
+    assert_near(
+        colorspace.to_rgb(&[100.0, 0.0, 0.0], &option),
+        [1.0, 1.0, 1.0],
+    );
+    assert_near(
+        colorspace.to_rgb(&[0.0, 0.0, 0.0], &option),
+        [0.0, 0.0, 0.0],
+    );
+    // sRGBの赤はD50基準で L=54.29 a=80.80 b=69.89 程度
+    assert_near(
+        colorspace.to_rgb(&[54.29, 80.80, 69.89], &option),
+        [1.0, 0.0, 0.0],
+    );
+}
+
+#[test]
+fn indexed_to_rgb() {
+    let colorspace = ColorSpace::Indexed(Box::new(Indexed {
+        base: ColorSpace::DeviceRGB,
+        hival: 1,
+        lookup: vec![255, 0, 0, 0, 0, 255],
+    }));
+    let option = ImageOption::default();
+
+    assert_eq!(colorspace.default_decode(4), vec![0.0, 15.0]);
+    assert_near(colorspace.to_rgb(&[1.0], &option), [0.0, 0.0, 1.0]);
+    // hivalを超える色番号はhivalとみなす
+    assert_near(colorspace.to_rgb(&[7.0], &option), [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn separation_to_rgb() {
+    // 特色の濃度1を代替色空間のCMYK [0 1 1 0]（赤）へ写す
+    let (mut file, xref) = test_util::write_pdf(&[]);
+    let colorspace_obj = test_util::parse(
+        "[/Separation /Spot /DeviceCMYK
+            << /FunctionType 2 /Domain [0 1] /C0 [0 0 0 0] /C1 [0 1 1 0] /N 1 >>]",
+    );
+    let colorspace = parse_colorspace(&colorspace_obj, &mut file, &xref).unwrap();
+    let option = ImageOption::default();
+
+    assert_eq!(colorspace.n_components(), 1);
+    assert_near(colorspace.to_rgb(&[1.0], &option), [1.0, 0.0, 0.0]);
+    assert_near(colorspace.to_rgb(&[0.0], &option), [1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn device_n_to_rgb() {
+    // シアンとマゼンタの2色をCMYKへ写す型4関数
+    let (mut file, xref) = test_util::write_pdf(&[test_util::stream(
+        "/FunctionType 4 /Domain [0 1 0 1] /Range [0 1 0 1 0 1 0 1]",
+        b"{0 0}",
+    )]);
+    let colorspace_obj = test_util::parse("[/DeviceN [/Cyan /Magenta] /DeviceCMYK 1 0 R]");
+    let colorspace = parse_colorspace(&colorspace_obj, &mut file, &xref).unwrap();
+    let option = ImageOption::default();
+
+    assert_eq!(colorspace.n_components(), 2);
+    assert_near(colorspace.to_rgb(&[1.0, 0.0], &option), [0.0, 1.0, 1.0]);
+    assert_near(colorspace.to_rgb(&[1.0, 1.0], &option), [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn reject_tint_transform_with_wrong_outputs() {
+    // 代替色空間はRGBなのに色変換関数は1つしか値を返さない
+    let (mut file, xref) = test_util::write_pdf(&[]);
+    let colorspace_obj = test_util::parse(
+        "[/Separation /Spot /DeviceRGB
+            << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>]",
+    );
+
+    assert!(parse_colorspace(&colorspace_obj, &mut file, &xref).is_err());
+}
+
+#[test]
+fn reject_self_referencing_colorspace() {
+    // 代替色空間や基底が自身を参照していても無限に再帰しない
+    let (mut file, xref) = test_util::write_pdf(&[
+        b"[/Separation /Spot 1 0 R << /FunctionType 2 /Domain [0 1] /N 1 >>]".to_vec(),
+        b"[/Indexed 2 0 R 0 <00>]".to_vec(),
+    ]);
+
+    for colorspace_obj in [test_util::parse("1 0 R"), test_util::parse("2 0 R")] {
+        assert!(matches!(
+            parse_colorspace(&colorspace_obj, &mut file, &xref),
+            Err(Error::TooDeepColorSpace)
+        ));
+    }
+    assert!(matches!(
+        describe_colorspace(&test_util::parse("2 0 R"), &mut file, &xref),
+        Err(Error::TooDeepColorSpace)
+    ));
+}
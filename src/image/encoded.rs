@@ -0,0 +1,266 @@
+use std::fs::File;
+
+use super::Error;
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+
+#[cfg(test)]
+mod test;
+
+// 画像XObjectのストリームがどの形式で符号化されているか
+#[derive(Debug, Clone)]
+pub enum EncodedFormat {
+    // DCTDecode
+    Jpeg,
+    // JPXDecode
+    Jpeg2000,
+    // CCITTFaxDecode
+    // 単体のファイルにするにはTIFFで包む必要がある
+    CcittFax(CcittFaxParam),
+    // JBIG2Decode
+    // 埋め込み形式のデータなので，共有セグメント(/JBIG2Globals)と合わせて使う
+    Jbig2 { globals: Option<Vec<u8>> },
+    // FlateDecodeなどそのまま画像ファイルとして保存できないもの
+    // データはストリームのバイト列そのまま
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct CcittFaxParam {
+    // 負ならG4，0ならG3の1次元，正ならG3の2次元符号化
+    pub k: isize,
+    pub columns: u32,
+    pub rows: u32,
+    pub encoded_byte_align: bool,
+    pub black_is_1: bool,
+    // 画像の/Decodeが [1 0] のとき真
+    pub inverted_decode: bool,
+}
+
+// 画像XObjectに埋め込まれている符号化されたままのデータ
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: EncodedFormat,
+    pub data: Vec<u8>,
+}
+
+impl EncodedImage {
+    // 画像を符号化しているフィルタより前のフィルタ(FlateDecodeなど)は解いておく
+    pub fn new(
+        image_dict: &object::PdfDict,
+        stream_bytes: Vec<u8>,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<EncodedImage, Error> {
+        image_dict.assert_with_key(vec!["Width", "Height"])?;
+
+        let width = object::PdfInteger::ensure(image_dict.get("Width").unwrap())?;
+        let height = object::PdfInteger::ensure(image_dict.get("Height").unwrap())?;
+
+        width.assert_natural()?;
+        let width = width.unpack() as u32;
+
+        height.assert_natural()?;
+        let height = height.unpack() as u32;
+
+        let filters = filter::get_filter_names(image_dict)?;
+        let (last_filter, preceding_filters) = match filters.split_last() {
+            Some(split) => split,
+            None => {
+                return Ok(EncodedImage {
+                    width,
+                    height,
+                    format: EncodedFormat::Other,
+                    data: stream_bytes,
+                })
+            }
+        };
+
+        let decode_parms = filter::get_decode_parms(image_dict, filters.len(), file, xref)?;
+        let (last_decode_parms, preceding_decode_parms) = decode_parms.split_last().unwrap();
+
+        let format = match last_filter.as_str() {
+            "DCTDecode" => EncodedFormat::Jpeg,
+            "JPXDecode" => EncodedFormat::Jpeg2000,
+            "CCITTFaxDecode" => EncodedFormat::CcittFax(get_ccitt_fax_param(
+                image_dict,
+                last_decode_parms.as_ref(),
+                width,
+                height,
+            )?),
+            "JBIG2Decode" => EncodedFormat::Jbig2 {
+                globals: get_jbig2_globals(last_decode_parms.as_ref(), file, xref)?,
+            },
+            _ => {
+                return Ok(EncodedImage {
+                    width,
+                    height,
+                    format: EncodedFormat::Other,
+                    data: stream_bytes,
+                })
+            }
+        };
+
+        let data = filter::apply_filters(stream_bytes, preceding_filters, preceding_decode_parms)?;
+
+        Ok(EncodedImage {
+            width,
+            height,
+            format,
+            data,
+        })
+    }
+
+    // 保存する際の拡張子
+    // 画像ファイルとして保存できないときはNoneを返す
+    pub fn extension(&self) -> Option<&'static str> {
+        match self.format {
+            EncodedFormat::Jpeg => Some("jpg"),
+            EncodedFormat::Jpeg2000 => Some("jp2"),
+            EncodedFormat::CcittFax(_) => Some("tif"),
+            EncodedFormat::Jbig2 { .. } => Some("jb2e"),
+            EncodedFormat::Other => None,
+        }
+    }
+
+    // CCITTファックス符号化されたデータを単一ストリップのTIFFとして包む
+    pub fn to_tiff(&self) -> Option<Vec<u8>> {
+        let param = match &self.format {
+            EncodedFormat::CcittFax(param) => param,
+            _ => return None,
+        };
+
+        let (compression, t4_options) = if param.k < 0 {
+            (4, None)
+        } else {
+            // bit0は2次元符号化，bit2は各行がバイト境界に揃えられていることを表す
+            let mut options = 0;
+            if param.k > 0 {
+                options |= 1;
+            }
+            if param.encoded_byte_align {
+                options |= 4;
+            }
+            (3, Some(options))
+        };
+
+        // BlackIs1が真だと白の連長が0になり，/Decodeが [1 0] ならさらに反転する
+        let photometric = if param.black_is_1 != param.inverted_decode {
+            1
+        } else {
+            0
+        };
+
+        let mut entries: Vec<(u16, u16, u32)> = vec![
+            (256, TIFF_LONG, param.columns),
+            (257, TIFF_LONG, param.rows),
+            (258, TIFF_SHORT, 1),
+            (259, TIFF_SHORT, compression),
+            (262, TIFF_SHORT, photometric),
+            // StripOffsetsは後で埋める
+            (273, TIFF_LONG, 0),
+            (277, TIFF_SHORT, 1),
+            (278, TIFF_LONG, param.rows),
+            (279, TIFF_LONG, self.data.len() as u32),
+        ];
+        if let Some(options) = t4_options {
+            entries.push((292, TIFF_LONG, options));
+        }
+
+        let data_offset = 8 + 2 + entries.len() * 12 + 4;
+        for entry in entries.iter_mut() {
+            if entry.0 == 273 {
+                entry.2 = data_offset as u32;
+            }
+        }
+
+        let mut tiff = Vec::with_capacity(data_offset + self.data.len());
+        tiff.extend(b"II*\0");
+        tiff.extend(8u32.to_le_bytes());
+
+        tiff.extend((entries.len() as u16).to_le_bytes());
+        for (tag, field_type, value) in entries {
+            tiff.extend(tag.to_le_bytes());
+            tiff.extend(field_type.to_le_bytes());
+            tiff.extend(1u32.to_le_bytes());
+            // 4バイトに収まる値は左詰めで直接書く
+            if field_type == TIFF_SHORT {
+                tiff.extend((value as u16).to_le_bytes());
+                tiff.extend([0, 0]);
+            } else {
+                tiff.extend(value.to_le_bytes());
+            }
+        }
+        tiff.extend(0u32.to_le_bytes());
+
+        tiff.extend(&self.data);
+
+        Some(tiff)
+    }
+}
+
+const TIFF_SHORT: u16 = 3;
+const TIFF_LONG: u16 = 4;
+
+fn get_ccitt_fax_param(
+    image_dict: &object::PdfDict,
+    decode_parms: Option<&object::PdfDict>,
+    width: u32,
+    height: u32,
+) -> Result<CcittFaxParam, Error> {
+    let get_integer = |key: &'static str| -> Result<Option<isize>, Error> {
+        match decode_parms.and_then(|dict| dict.get(key)) {
+            Some(obj) => Ok(Some(object::PdfInteger::ensure(obj)?.unpack())),
+            None => Ok(None),
+        }
+    };
+    let get_boolean = |key: &'static str| -> Result<bool, Error> {
+        match decode_parms.and_then(|dict| dict.get(key)) {
+            Some(obj) => Ok(object::PdfBoolean::ensure(obj)?.unpack()),
+            None => Ok(false),
+        }
+    };
+
+    let inverted_decode = match image_dict.get("Decode") {
+        Some(obj) => {
+            let decode = object::PdfArray::ensure(obj)?;
+            match (decode.get(0), decode.get(1)) {
+                (Some(min), Some(max)) => {
+                    object::PdfReal::ensure_number(min)? > object::PdfReal::ensure_number(max)?
+                }
+                _ => false,
+            }
+        }
+        None => false,
+    };
+
+    Ok(CcittFaxParam {
+        k: get_integer("K")?.unwrap_or(0),
+        columns: get_integer("Columns")?.map_or(width, |columns| columns as u32),
+        // /Rowsが0や省略されているときは画像の高さを使う
+        rows: match get_integer("Rows")? {
+            Some(rows) if rows > 0 => rows as u32,
+            _ => height,
+        },
+        encoded_byte_align: get_boolean("EncodedByteAlign")?,
+        black_is_1: get_boolean("BlackIs1")?,
+        inverted_decode,
+    })
+}
+
+fn get_jbig2_globals(
+    decode_parms: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Option<Vec<u8>>, Error> {
+    let globals_obj = match decode_parms.and_then(|dict| dict.get("JBIG2Globals")) {
+        Some(obj) => object::resolve(obj, file, xref)?,
+        None => return Ok(None),
+    };
+    let globals = object::PdfStreamObj::ensure_stream(&globals_obj)?;
+
+    Ok(Some(filter::decode_stream(globals, file, xref)?))
+}
@@ -0,0 +1,74 @@
+use super::*;
+
+fn ccitt_image(k: isize) -> EncodedImage {
+    EncodedImage {
+        width: 8,
+        height: 2,
+        format: EncodedFormat::CcittFax(CcittFaxParam {
+            k,
+            columns: 8,
+            rows: 2,
+            encoded_byte_align: false,
+            black_is_1: false,
+            inverted_decode: false,
+        }),
+        data: vec![0xaa, 0xbb],
+    }
+}
+
+// IFDからタグの値を読む
+fn tiff_tag(tiff: &[u8], tag: u16) -> Option<u32> {
+    let count = u16::from_le_bytes([tiff[8], tiff[9]]) as usize;
+
+    (0..count).find_map(|i| {
+        let entry = &tiff[10 + i * 12..10 + (i + 1) * 12];
+        if u16::from_le_bytes([entry[0], entry[1]]) != tag {
+            return None;
+        }
+
+        if u16::from_le_bytes([entry[2], entry[3]]) == TIFF_SHORT {
+            Some(u16::from_le_bytes([entry[8], entry[9]]) as u32)
+        } else {
+            Some(u32::from_le_bytes([
+                entry[8], entry[9], entry[10], entry[11],
+            ]))
+        }
+    })
+}
+
+#[test]
+fn to_tiff_g4() {
+    let tiff = ccitt_image(-1).to_tiff().unwrap();
+
+    assert_eq!(&tiff[0..8], b"II*\0\x08\0\0\0");
+    assert_eq!(tiff_tag(&tiff, 256), Some(8));
+    assert_eq!(tiff_tag(&tiff, 257), Some(2));
+    assert_eq!(tiff_tag(&tiff, 259), Some(4));
+    assert_eq!(tiff_tag(&tiff, 262), Some(0));
+    assert_eq!(tiff_tag(&tiff, 292), None);
+
+    let offset = tiff_tag(&tiff, 273).unwrap() as usize;
+    assert_eq!(&tiff[offset..], &[0xaa, 0xbb]);
+    assert_eq!(tiff_tag(&tiff, 279), Some(2));
+}
+
+#[test]
+fn to_tiff_g3_2d() {
+    let tiff = ccitt_image(4).to_tiff().unwrap();
+
+    assert_eq!(tiff_tag(&tiff, 259), Some(3));
+    assert_eq!(tiff_tag(&tiff, 292), Some(1));
+}
+
+#[test]
+fn to_tiff_not_ccitt() {
+    let image = EncodedImage {
+        width: 1,
+        height: 1,
+        format: EncodedFormat::Jpeg,
+        data: vec![],
+    };
+
+    assert!(image.to_tiff().is_none());
+    assert_eq!(image.extension(), Some("jpg"));
+}
@@ -0,0 +1,111 @@
+use std::fs::File;
+
+use super::colorspace;
+use super::Error;
+use crate::cross_reference;
+use crate::filter;
+use crate::object;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// 画像に付いているマスクの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskType {
+    None,
+    // /SMask
+    SoftMask,
+    // /Maskに画像XObjectが指定されている
+    StencilMask,
+    // /Maskに色の範囲の配列が指定されている
+    ColorKey,
+    // 画像自体がステンシルマスク（/ImageMask true）
+    ImageMask,
+}
+
+// ストリームの中身を読まずに辞書から分かる画像の情報
+#[derive(Debug, Clone)]
+pub struct ImageInfo {
+    // (オブジェクト番号, 世代番号)
+    pub object_ref: (usize, usize),
+    pub width: u32,
+    pub height: u32,
+    // JPXDecodeでは省略されることがある
+    pub bits_per_component: Option<usize>,
+    // ステンシルマスクや，JPXDecodeで省略されているときはNone
+    pub colorspace: Option<String>,
+    pub filters: Vec<String>,
+    pub mask: MaskType,
+    // 符号化されたストリームのバイト数（/Length）
+    pub encoded_length: u64,
+}
+
+impl ImageInfo {
+    pub fn new(
+        object_ref: (usize, usize),
+        image_dict: &object::PdfDict,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<ImageInfo, Error> {
+        image_dict.assert_with_key(vec!["Width", "Height", "Length"])?;
+
+        let width = object::PdfInteger::ensure(image_dict.get("Width").unwrap())?;
+        let height = object::PdfInteger::ensure(image_dict.get("Height").unwrap())?;
+
+        width.assert_natural()?;
+        height.assert_natural()?;
+
+        let mask = get_mask_type(image_dict)?;
+
+        let bits_per_component = if mask == MaskType::ImageMask {
+            Some(1)
+        } else {
+            match image_dict.get("BitsPerComponent") {
+                Some(obj) => Some(object::PdfInteger::ensure(obj)?.unpack() as usize),
+                None => None,
+            }
+        };
+
+        let colorspace = match image_dict.get("ColorSpace") {
+            Some(obj) if mask != MaskType::ImageMask => {
+                Some(colorspace::describe_colorspace(obj, file, xref)?)
+            }
+            _ => None,
+        };
+
+        let length = object::resolve(image_dict.get("Length").unwrap(), file, xref)?;
+        let length = object::PdfInteger::ensure(&length)?;
+        length.assert_not_negative()?;
+
+        Ok(ImageInfo {
+            object_ref,
+            width: width.unpack() as u32,
+            height: height.unpack() as u32,
+            bits_per_component,
+            colorspace,
+            filters: filter::get_filter_names(image_dict)?,
+            mask,
+            encoded_length: length.unpack() as u64,
+        })
+    }
+}
+
+fn get_mask_type(image_dict: &object::PdfDict) -> Result<MaskType, Error> {
+    if let Some(obj) = image_dict.get("ImageMask") {
+        if object::PdfBoolean::ensure(obj)?.unpack() {
+            return Ok(MaskType::ImageMask);
+        }
+    }
+
+    // /SMaskがあるときは/Maskは無視される
+    if image_dict.get("SMask").is_some() {
+        return Ok(MaskType::SoftMask);
+    }
+
+    Ok(match image_dict.get("Mask") {
+        Some(Object::IndirectRef(_)) | Some(Object::StreamObj(_)) => MaskType::StencilMask,
+        Some(Object::Array(_)) => MaskType::ColorKey,
+        _ => MaskType::None,
+    })
+}
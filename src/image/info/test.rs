@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use super::*;
+
+fn dict(entries: Vec<(&str, Object)>) -> object::PdfDict {
+    let hm: HashMap<String, Object> = entries
+        .into_iter()
+        .map(|(key, obj)| (String::from(key), obj))
+        .collect();
+
+    object::PdfDict::new(hm, 0)
+}
+
+#[test]
+fn mask_type_image_mask() {
+    let image_dict = dict(vec![(
+        "ImageMask",
+        Object::Boolean(object::PdfBoolean::new(true, 0)),
+    )]);
+
+    assert_eq!(get_mask_type(&image_dict).unwrap(), MaskType::ImageMask);
+}
+
+#[test]
+fn mask_type_soft_mask_takes_precedence() {
+    let image_dict = dict(vec![
+        (
+            "SMask",
+            Object::IndirectRef(object::PdfIndirectRef::new(10, 0, 0)),
+        ),
+        (
+            "Mask",
+            Object::IndirectRef(object::PdfIndirectRef::new(11, 0, 0)),
+        ),
+    ]);
+
+    assert_eq!(get_mask_type(&image_dict).unwrap(), MaskType::SoftMask);
+}
+
+#[test]
+fn mask_type_stencil_and_color_key() {
+    let stencil = dict(vec![(
+        "Mask",
+        Object::IndirectRef(object::PdfIndirectRef::new(11, 0, 0)),
+    )]);
+    let color_key = dict(vec![(
+        "Mask",
+        Object::Array(object::PdfArray::new(vec![], 0)),
+    )]);
+
+    assert_eq!(get_mask_type(&stencil).unwrap(), MaskType::StencilMask);
+    assert_eq!(get_mask_type(&color_key).unwrap(), MaskType::ColorKey);
+    assert_eq!(get_mask_type(&dict(vec![])).unwrap(), MaskType::None);
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::object;
+use crate::object::PdfObject;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+// インライン画像の辞書では省略形のキーと名前が使えるので，画像XObjectと同じ形に直す
+// 画像XObjectとして扱えるように/Subtype /Imageも補う
+// 色空間がリソースの/ColorSpaceにある名前で指定されていたら，その中身に置き換える
+pub fn expand_inline_image_dict(
+    image_dict: &object::PdfDict,
+    colorspaces: Option<&object::PdfDict>,
+) -> object::PdfDict {
+    let byte_offset = image_dict.byte_offset();
+
+    let mut expanded = HashMap::new();
+    expanded.insert(
+        String::from("Subtype"),
+        Object::Name(object::PdfName::new(String::from("Image"), byte_offset)),
+    );
+
+    for (key, value) in image_dict.iter() {
+        let key = expand_key(key);
+
+        let value = match key {
+            "ColorSpace" => match (value, colorspaces) {
+                (Object::Name(name), Some(colorspaces)) => match colorspaces.get(name.as_str()) {
+                    Some(colorspace) => colorspace.clone(),
+                    None => expand_names(value, expand_colorspace_name),
+                },
+                _ => expand_names(value, expand_colorspace_name),
+            },
+            "Filter" => expand_names(value, expand_filter_name),
+            _ => value.clone(),
+        };
+
+        expanded.insert(String::from(key), value);
+    }
+
+    object::PdfDict::new(expanded, byte_offset)
+}
+
+fn expand_key(key: &str) -> &str {
+    match key {
+        "BPC" => "BitsPerComponent",
+        "CS" => "ColorSpace",
+        "D" => "Decode",
+        "DP" => "DecodeParms",
+        "F" => "Filter",
+        "H" => "Height",
+        "IM" => "ImageMask",
+        "I" => "Interpolate",
+        "L" => "Length",
+        "W" => "Width",
+        key => key,
+    }
+}
+
+fn expand_colorspace_name(name: &str) -> &str {
+    match name {
+        "G" => "DeviceGray",
+        "RGB" => "DeviceRGB",
+        "CMYK" => "DeviceCMYK",
+        "I" => "Indexed",
+        name => name,
+    }
+}
+
+fn expand_filter_name(name: &str) -> &str {
+    match name {
+        "AHx" => "ASCIIHexDecode",
+        "A85" => "ASCII85Decode",
+        "LZW" => "LZWDecode",
+        "Fl" => "FlateDecode",
+        "RL" => "RunLengthDecode",
+        "CCF" => "CCITTFaxDecode",
+        "DCT" => "DCTDecode",
+        name => name,
+    }
+}
+
+// 名前単体か，名前を含む配列（[/I /RGB 255 <...>] など）の名前を展開する
+fn expand_names(obj: &Object, expand: fn(&str) -> &str) -> Object {
+    match obj {
+        Object::Name(name) => Object::Name(object::PdfName::new(
+            String::from(expand(name.as_str())),
+            name.byte_offset(),
+        )),
+        Object::Array(array) => Object::Array(object::PdfArray::new(
+            array
+                .into_iter()
+                .map(|obj| expand_names(obj, expand))
+                .collect(),
+            array.byte_offset(),
+        )),
+        obj => obj.clone(),
+    }
+}
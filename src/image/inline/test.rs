@@ -0,0 +1,81 @@
+use super::*;
+
+fn name(s: &str) -> Object {
+    Object::Name(object::PdfName::new(String::from(s), 0))
+}
+
+fn dict(entries: Vec<(&str, Object)>) -> object::PdfDict {
+    let hm: HashMap<String, Object> = entries
+        .into_iter()
+        .map(|(key, obj)| (String::from(key), obj))
+        .collect();
+
+    object::PdfDict::new(hm, 0)
+}
+
+#[test]
+fn expand_abbreviated_keys_and_names() {
+    let image_dict = dict(vec![
+        ("W", Object::Integer(object::PdfInteger::new(4, 0))),
+        ("CS", name("RGB")),
+        (
+            "F",
+            Object::Array(object::PdfArray::new(vec![name("A85"), name("Fl")], 0)),
+        ),
+    ]);
+
+    let expanded = expand_inline_image_dict(&image_dict, None);
+
+    assert_eq!(expanded.get("Subtype"), Some(&name("Image")));
+    assert_eq!(
+        expanded.get("Width"),
+        Some(&Object::Integer(object::PdfInteger::new(4, 0)))
+    );
+    assert_eq!(expanded.get("ColorSpace"), Some(&name("DeviceRGB")));
+    assert_eq!(
+        expanded.get("Filter"),
+        Some(&Object::Array(object::PdfArray::new(
+            vec![name("ASCII85Decode"), name("FlateDecode")],
+            0
+        )))
+    );
+}
+
+#[test]
+fn expand_indexed_colorspace_array() {
+    let image_dict = dict(vec![(
+        "CS",
+        Object::Array(object::PdfArray::new(
+            vec![
+                name("I"),
+                name("G"),
+                Object::Integer(object::PdfInteger::new(1, 0)),
+            ],
+            0,
+        )),
+    )]);
+
+    let expanded = expand_inline_image_dict(&image_dict, None);
+
+    assert_eq!(
+        expanded.get("ColorSpace"),
+        Some(&Object::Array(object::PdfArray::new(
+            vec![
+                name("Indexed"),
+                name("DeviceGray"),
+                Object::Integer(object::PdfInteger::new(1, 0)),
+            ],
+            0
+        )))
+    );
+}
+
+#[test]
+fn expand_colorspace_from_resources() {
+    let image_dict = dict(vec![("CS", name("CS0"))]);
+    let colorspaces = dict(vec![("CS0", name("DeviceCMYK"))]);
+
+    let expanded = expand_inline_image_dict(&image_dict, Some(&colorspaces));
+
+    assert_eq!(expanded.get("ColorSpace"), Some(&name("DeviceCMYK")));
+}
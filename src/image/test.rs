@@ -0,0 +1,234 @@
+use super::*;
+
+#[test]
+fn unpack_samples_1_bit() {
+    // 幅3の1ビット画像なので各行は1バイトにパディングされる
+    let data = [0b1010_0000, 0b0110_0000];
+
+    let samples = unpack_samples(&data, 3, 2, 1, 1).unwrap();
+
+    assert_eq!(samples, vec![1, 0, 1, 0, 1, 1]);
+}
+
+#[test]
+fn unpack_samples_4_bit_rgb() {
+    // 幅1のRGB画像なので1行は12ビットで2バイトにパディングされる
+    let data = [0x12, 0x30, 0xab, 0xc0];
+
+    let samples = unpack_samples(&data, 1, 2, 3, 4).unwrap();
+
+    assert_eq!(samples, vec![1, 2, 3, 10, 11, 12]);
+}
+
+#[test]
+fn unpack_samples_16_bit() {
+    let data = [0x01, 0x02, 0xff, 0xff];
+
+    let samples = unpack_samples(&data, 2, 1, 1, 16).unwrap();
+
+    assert_eq!(samples, vec![0x0102, 0xffff]);
+}
+
+#[test]
+fn unpack_samples_too_short() {
+    let data = [0x00];
+
+    assert!(matches!(
+        unpack_samples(&data, 9, 1, 1, 1),
+        Err(Error::InvalidImageData)
+    ));
+}
+
+fn stencil_mask_param(decode: Vec<f64>) -> ImageDecodeParam {
+    ImageDecodeParam {
+        width: 2,
+        height: 1,
+        colorspace: None,
+        bits_per_component: 1,
+        decode,
+        color_key: None,
+        filters: vec![],
+        decode_parms: vec![],
+    }
+}
+
+#[test]
+fn decode_stencil_mask_without_fill_color() {
+    let image = stencil_mask_param(vec![0.0, 1.0]);
+
+    let mask = decode_stencil_mask(&image, &[0b0100_0000], &ImageOption::default()).unwrap();
+
+    assert_eq!(mask.into_luma8().into_raw(), vec![0, 255]);
+}
+
+#[test]
+fn decode_stencil_mask_inverted_with_fill_color() {
+    let image = stencil_mask_param(vec![1.0, 0.0]);
+    let option = ImageOption {
+        stencil_fill_color: Some([255, 0, 0]),
+        ..Default::default()
+    };
+
+    let mask = decode_stencil_mask(&image, &[0b0100_0000], &option).unwrap();
+
+    assert_eq!(
+        mask.into_rgba8().into_raw(),
+        vec![0, 0, 0, 0, 255, 0, 0, 255]
+    );
+}
+
+fn image_param(width: u32, colorspace: ColorSpace, decode: Vec<f64>) -> ImageDecodeParam {
+    ImageDecodeParam {
+        width,
+        height: 1,
+        colorspace: Some(colorspace),
+        bits_per_component: 8,
+        decode,
+        color_key: None,
+        filters: vec![],
+        decode_parms: vec![],
+    }
+}
+
+#[test]
+fn decode_inverted_gray_image() {
+    let image = image_param(3, ColorSpace::DeviceGray, vec![1.0, 0.0]);
+
+    let decoded = decode_image(&image, &[0, 255, 64], &ImageOption::default()).unwrap();
+
+    assert_eq!(decoded.into_luma8().into_raw(), vec![255, 0, 191]);
+}
+
+#[test]
+fn decode_inverted_multi_component_image() {
+    // RGBは緑の成分だけを反転する
+    let image = image_param(2, ColorSpace::DeviceRGB, vec![0.0, 1.0, 1.0, 0.0, 0.0, 1.0]);
+
+    let decoded = decode_image(&image, &[255, 0, 0, 0, 255, 255], &ImageOption::default());
+
+    assert_eq!(
+        decoded.unwrap().into_rgb8().into_raw(),
+        vec![255, 255, 0, 0, 0, 255]
+    );
+
+    // CMYKはすべての成分を反転するので，255は白になる
+    let image = image_param(1, ColorSpace::DeviceCMYK, [1.0, 0.0].repeat(4));
+
+    let decoded = decode_image(&image, &[255, 255, 255, 255], &ImageOption::default());
+
+    assert_eq!(decoded.unwrap().into_rgb8().into_raw(), vec![255, 255, 255]);
+}
+
+#[test]
+fn decode_corrupt_dct_image() {
+    let image = ImageDecodeParam {
+        filters: vec![String::from("DCTDecode")],
+        decode_parms: vec![],
+        ..image_param(2, ColorSpace::DeviceGray, vec![0.0, 1.0])
+    };
+
+    assert!(matches!(
+        decode_image(&image, b"not a jpeg", &ImageOption::default()),
+        Err(Error::Dct(_))
+    ));
+}
+
+#[test]
+fn apply_soft_mask_same_size() {
+    let image =
+        DynamicImage::ImageRgb8(ImageBuffer::from_raw(2, 1, vec![255, 0, 0, 0, 0, 255]).unwrap());
+    let mask = DynamicImage::ImageLuma8(ImageBuffer::from_raw(2, 1, vec![255, 0]).unwrap());
+
+    let rgba = apply_soft_mask(image, &mask, None);
+
+    assert_eq!(
+        rgba.into_rgba8().into_raw(),
+        vec![255, 0, 0, 255, 0, 0, 255, 0]
+    );
+}
+
+#[test]
+fn apply_soft_mask_resized() {
+    let image = DynamicImage::ImageRgb8(ImageBuffer::from_raw(2, 2, vec![0; 12]).unwrap());
+    let mask = DynamicImage::ImageLuma8(ImageBuffer::from_raw(1, 1, vec![128]).unwrap());
+
+    let rgba = apply_soft_mask(image, &mask, None).into_rgba8();
+
+    assert_eq!(rgba.dimensions(), (2, 2));
+    assert!(rgba.pixels().all(|p| p[3] == 128));
+}
+
+#[test]
+fn apply_soft_mask_with_matte() {
+    // 白の背景と50%で事前に乗算された黒
+    let image = DynamicImage::ImageRgb8(ImageBuffer::from_raw(1, 1, vec![128, 128, 128]).unwrap());
+    let mask = DynamicImage::ImageLuma8(ImageBuffer::from_raw(1, 1, vec![128]).unwrap());
+
+    let rgba = apply_soft_mask(image, &mask, Some([1.0, 1.0, 1.0])).into_rgba8();
+
+    let pixel = rgba.get_pixel(0, 0);
+    assert!(pixel[0] < 3 && pixel[1] < 3 && pixel[2] < 3);
+    assert_eq!(pixel[3], 128);
+}
+
+#[test]
+fn build_color_key_mask_rgb() {
+    // 2画素目のみが全成分で色キーの範囲に入る
+    let samples = [10, 200, 10, 250, 250, 250];
+    let color_key = [240, 255, 240, 255, 240, 255];
+
+    let mask = build_color_key_mask(&samples, &color_key, 3, 2, 1);
+
+    assert_eq!(mask.into_luma8().into_raw(), vec![255, 0]);
+}
+
+#[test]
+fn stencil_mask_to_alpha_inverts() {
+    let stencil_mask = DynamicImage::ImageLuma8(ImageBuffer::from_raw(2, 1, vec![0, 255]).unwrap());
+
+    let alpha = stencil_mask_to_alpha(&stencil_mask);
+
+    assert_eq!(alpha.into_luma8().into_raw(), vec![255, 0]);
+}
+
+#[test]
+fn to_native_image_cmyk_keeps_components() {
+    let image = ImageDecodeParam {
+        width: 1,
+        height: 1,
+        colorspace: Some(ColorSpace::DeviceCMYK),
+        bits_per_component: 8,
+        decode: vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0],
+        color_key: None,
+        filters: vec![],
+        decode_parms: vec![],
+    };
+
+    let native = to_native_image(&image, &[0, 128, 255, 0], 8).unwrap();
+
+    match native {
+        NativeImage::Cmyk8(raw) => assert_eq!(raw.data, vec![0, 128, 255, 255]),
+        _ => panic!("expected cmyk image"),
+    }
+}
+
+#[test]
+fn to_native_image_gray_16_bit() {
+    let image = ImageDecodeParam {
+        width: 2,
+        height: 1,
+        colorspace: Some(ColorSpace::DeviceGray),
+        bits_per_component: 16,
+        decode: vec![0.0, 1.0],
+        color_key: None,
+        filters: vec![],
+        decode_parms: vec![],
+    };
+
+    let native = to_native_image(&image, &[0x01, 0x02, 0xff, 0xff], 16).unwrap();
+
+    match native {
+        NativeImage::Luma16(luma) => assert_eq!(luma.into_raw(), vec![0x0102, 0xffff]),
+        _ => panic!("expected 16-bit gray image"),
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::text::TextLine;
+
+#[cfg(test)]
+mod test;
+
+// 行の間がフォントの大きさのこの割合以内なら同じ段落とみなす
+const BLOCK_GAP_THRESHOLD: f64 = 1.0;
+// ページの上下の端からこの行数までをヘッダーとフッターの候補とする
+const MARGIN_LINES: usize = 2;
+
+// 段落などのひとまとまりの行
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    // 行を改行でつないだもの
+    pub text: String,
+    pub bbox: [f64; 4],
+    pub lines: Vec<TextLine>,
+}
+
+// レイアウト解析の挙動を指定する
+#[derive(Debug, Clone, Default)]
+pub struct LayoutOption {
+    // 複数のページで同じ位置に繰り返されるヘッダーとフッターを取り除くかどうか
+    pub remove_headers_footers: bool,
+}
+
+impl TextBlock {
+    fn new(lines: Vec<TextLine>) -> TextBlock {
+        let mut bbox = lines[0].bbox;
+        for line in &lines[1..] {
+            bbox = union(&bbox, &line.bbox);
+        }
+
+        TextBlock {
+            text: lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            bbox,
+            lines,
+        }
+    }
+
+    fn is_vertical(&self) -> bool {
+        is_vertical(&self.lines[0])
+    }
+}
+
+fn union(a: &[f64; 4], b: &[f64; 4]) -> [f64; 4] {
+    [
+        a[0].min(b[0]),
+        a[1].min(b[1]),
+        a[2].max(b[2]),
+        a[3].max(b[3]),
+    ]
+}
+
+fn is_vertical(line: &TextLine) -> bool {
+    line.words
+        .first()
+        .and_then(|word| word.chars.first())
+        .is_some_and(|c| c.is_vertical)
+}
+
+fn font_size(line: &TextLine) -> f64 {
+    line.words
+        .iter()
+        .map(|word| word.font_size)
+        .fold(0.0, f64::max)
+}
+
+// 1ページの行を段落にまとめ，読む順に並べる
+pub fn analyze_page(lines: Vec<TextLine>) -> Vec<TextBlock> {
+    order_blocks(group_blocks(lines))
+}
+
+// 前の行のすぐ下（縦書きではすぐ左）にあって，範囲が重なっている行を同じ段落にする
+fn group_blocks(lines: Vec<TextLine>) -> Vec<TextBlock> {
+    let mut groups: Vec<Vec<TextLine>> = vec![];
+
+    for line in lines {
+        let size = font_size(&line);
+        let position = groups.iter().rposition(|group| {
+            let last = group.last().unwrap();
+            if is_vertical(last) != is_vertical(&line) {
+                return false;
+            }
+
+            let (a, b) = (&last.bbox, &line.bbox);
+            let threshold = size.max(font_size(last)) * BLOCK_GAP_THRESHOLD;
+            if is_vertical(&line) {
+                let gap = a[0] - b[2];
+                (-threshold..=threshold).contains(&gap) && a[1] < b[3] && b[1] < a[3]
+            } else {
+                let gap = a[1] - b[3];
+                (-threshold..=threshold).contains(&gap) && a[0] < b[2] && b[0] < a[2]
+            }
+        });
+
+        match position {
+            Some(i) => groups[i].push(line),
+            None => groups.push(vec![line]),
+        }
+    }
+
+    groups.into_iter().map(TextBlock::new).collect()
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+}
+
+// 射影が重ならないところで段落を分ける（XY-cut）
+// 段組みを優先するため，先に縦の隙間で列に分け，分けられなければ横の隙間で分ける
+fn order_blocks(blocks: Vec<TextBlock>) -> Vec<TextBlock> {
+    if blocks.len() <= 1 {
+        return blocks;
+    }
+
+    let mut groups = split(blocks, Axis::X);
+    if groups.len() > 1 {
+        // 縦書きが多ければ右の列から読む
+        let vertical_count = groups
+            .iter()
+            .flatten()
+            .filter(|block| block.is_vertical())
+            .count();
+        let block_count: usize = groups.iter().map(|group| group.len()).sum();
+        if vertical_count * 2 > block_count {
+            groups.reverse();
+        }
+
+        return groups.into_iter().flat_map(order_blocks).collect();
+    }
+
+    let mut groups = split(groups.remove(0), Axis::Y);
+    if groups.len() > 1 {
+        // 段落の切れ目が左右の列でそろっていると段組みの途中で切れてしまうので，
+        // 列の切れ目が共通する隣り合った部分はまとめてから列に分ける
+        let mut merged: Vec<Vec<TextBlock>> = vec![];
+        for group in groups {
+            match merged.last_mut() {
+                Some(last)
+                    if column_count(last.iter()) > 1
+                        && column_count(group.iter()) > 1
+                        && column_count(last.iter().chain(&group)) > 1 =>
+                {
+                    last.extend(group)
+                }
+                _ => merged.push(group),
+            }
+        }
+
+        return merged.into_iter().flat_map(order_blocks).collect();
+    }
+
+    // どちらにも分けられなければ上から，左から読む
+    let mut blocks = groups.remove(0);
+    blocks.sort_by(|a, b| {
+        b.bbox[3]
+            .total_cmp(&a.bbox[3])
+            .then(a.bbox[0].total_cmp(&b.bbox[0]))
+    });
+    blocks
+}
+
+// X軸なら左から，Y軸なら上から，射影の重なる段落ごとに分ける
+fn split(mut blocks: Vec<TextBlock>, axis: Axis) -> Vec<Vec<TextBlock>> {
+    let range = |block: &TextBlock| match axis {
+        Axis::X => (block.bbox[0], block.bbox[2]),
+        Axis::Y => (-block.bbox[3], -block.bbox[1]),
+    };
+    blocks.sort_by(|a, b| range(a).0.total_cmp(&range(b).0));
+
+    let mut groups: Vec<Vec<TextBlock>> = vec![];
+    let mut end = f64::MIN;
+    for block in blocks {
+        let (start, block_end) = range(&block);
+        match groups.last_mut() {
+            Some(group) if start < end => group.push(block),
+            _ => groups.push(vec![block]),
+        }
+        end = end.max(block_end);
+    }
+
+    groups
+}
+
+// 横に並ぶ，射影の重ならない列の数
+fn column_count<'a>(blocks: impl Iterator<Item = &'a TextBlock>) -> usize {
+    let mut ranges: Vec<(f64, f64)> = blocks.map(|block| (block.bbox[0], block.bbox[2])).collect();
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut count = 0;
+    let mut end = f64::MIN;
+    for (start, range_end) in ranges {
+        if start >= end {
+            count += 1;
+        }
+        end = end.max(range_end);
+    }
+
+    count
+}
+
+// 比べるときにはページ番号などの数字を区別しない
+fn normalize(text: &str) -> String {
+    let mut normalized = String::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if !c.is_ascii_digit() {
+            normalized.push(c);
+        } else if !normalized.ends_with('#') {
+            normalized.push('#');
+        }
+    }
+
+    normalized
+}
+
+// ページの上端と下端から数行ずつの位置
+// 行が少ないページでは全ての行が候補になって本文まで消えてしまうので，候補を作らない
+fn margin_lines(lines: &[TextLine]) -> Vec<usize> {
+    if lines.len() <= MARGIN_LINES * 2 {
+        return vec![];
+    }
+
+    let mut indexes: Vec<usize> = (0..lines.len()).collect();
+    indexes.sort_by(|a, b| lines[*b].bbox[3].total_cmp(&lines[*a].bbox[3]));
+
+    let mut margins = indexes[..MARGIN_LINES].to_vec();
+    margins.extend(&indexes[indexes.len() - MARGIN_LINES..]);
+    margins
+}
+
+// ページの上下の端にあって，半分以上のページに同じ文字列で現れる行を取り除く
+pub fn remove_repeated_lines(pages: &mut [Vec<TextLine>]) {
+    if pages.len() < 2 {
+        return;
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for lines in pages.iter() {
+        let texts: HashSet<String> = margin_lines(lines)
+            .into_iter()
+            .map(|i| normalize(&lines[i].text))
+            .collect();
+        for text in texts {
+            *counts.entry(text).or_insert(0) += 1;
+        }
+    }
+
+    let min_count = pages.len().div_ceil(2).max(2);
+    for lines in pages.iter_mut() {
+        let removed: HashSet<usize> = margin_lines(lines)
+            .into_iter()
+            .filter(|i| {
+                let text = normalize(&lines[*i].text);
+                !text.is_empty() && counts.get(&text).is_some_and(|count| *count >= min_count)
+            })
+            .collect();
+
+        let mut i = 0;
+        lines.retain(|_| {
+            i += 1;
+            !removed.contains(&(i - 1))
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use crate::graphics::Color;
+use crate::text::{TextChar, TextWord};
+
+// 大きさ10の横書きの行．yはベースライン
+fn line(text: &str, x: f64, y: f64, width: f64) -> TextLine {
+    let bbox = [x, y - 2.0, x + width, y + 8.0];
+    let c = TextChar {
+        text: String::from(text),
+        x,
+        y,
+        end_x: x + width,
+        end_y: y,
+        font_size: 10.0,
+        is_vertical: false,
+        font_name: String::new(),
+        fill_color: Color::default(),
+        render_mode: 0,
+        bbox,
+    };
+
+    TextLine {
+        text: String::from(text),
+        bbox,
+        words: vec![TextWord {
+            text: String::from(text),
+            bbox,
+            font_name: String::new(),
+            font_size: 10.0,
+            fill_color: Color::default(),
+            render_mode: 0,
+            chars: vec![c],
+        }],
+    }
+}
+
+fn texts(blocks: &[TextBlock]) -> Vec<&str> {
+    blocks.iter().map(|block| block.text.as_str()).collect()
+}
+
+#[test]
+fn two_columns_with_title() {
+    // 左右の列を交互に描いても，左の列を読んでから右の列を読む
+    let lines = vec![
+        line("Title", 100.0, 750.0, 300.0),
+        line("left 1", 100.0, 700.0, 140.0),
+        line("right 1", 260.0, 700.0, 140.0),
+        line("left 2", 100.0, 688.0, 140.0),
+        line("right 2", 260.0, 688.0, 140.0),
+        line("left 3", 100.0, 640.0, 140.0),
+        line("right 3", 260.0, 640.0, 140.0),
+    ];
+
+    let blocks = analyze_page(lines);
+
+    assert_eq!(
+        texts(&blocks),
+        [
+            "Title",
+            "left 1\nleft 2",
+            "left 3",
+            "right 1\nright 2",
+            "right 3"
+        ]
+    );
+    assert_eq!(blocks[1].bbox, [100.0, 686.0, 240.0, 708.0]);
+}
+
+#[test]
+fn remove_headers_and_page_numbers() {
+    let page = |number: usize, body: [&str; 3]| {
+        vec![
+            line("Annual Report", 100.0, 780.0, 100.0),
+            line(body[0], 100.0, 700.0, 200.0),
+            line(body[1], 100.0, 400.0, 200.0),
+            line(body[2], 100.0, 300.0, 200.0),
+            line(&format!("Page {}", number), 300.0, 30.0, 40.0),
+        ]
+    };
+    let mut pages = vec![
+        page(1, ["a", "b", "c"]),
+        page(2, ["d", "e", "f"]),
+        page(10, ["g", "h", "i"]),
+    ];
+
+    remove_repeated_lines(&mut pages);
+
+    let texts: Vec<Vec<&str>> = pages
+        .iter()
+        .map(|lines| lines.iter().map(|line| line.text.as_str()).collect())
+        .collect();
+    assert_eq!(texts, [["a", "b", "c"], ["d", "e", "f"], ["g", "h", "i"]]);
+}
+
+#[test]
+fn keep_repeated_lines_on_short_pages() {
+    // 行の少ないページでは，繰り返される本文をヘッダーとみなさない
+    let page = |number: usize| {
+        vec![
+            line("Signature", 100.0, 400.0, 100.0),
+            line(&format!("Page {}", number), 300.0, 30.0, 40.0),
+        ]
+    };
+    let mut pages = vec![page(1), page(2), page(3)];
+
+    remove_repeated_lines(&mut pages);
+
+    assert!(pages.iter().all(|lines| lines.len() == 2));
+}
@@ -39,7 +39,6 @@ impl std::fmt::Display for Token {
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenContent {
-    Eol,
     Boolean(bool),
     Integer(isize),
     Real(f64),
@@ -57,12 +56,15 @@ pub enum TokenContent {
     // streamキーワードが表れたらそこで字句解析を終了するのでEndはない
     // Streamバイト列が始まるバイトオフセット
     StreamObjStart(u64),
+    // コンテンツストリームの演算子
+    Operator(String),
+    // インライン画像のIDとEIの間のバイト列
+    InlineImageData(Vec<u8>),
 }
 
 impl std::fmt::Display for TokenContent {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match self {
-            TokenContent::Eol => write!(f, "(EOL)"),
             TokenContent::Boolean(boolean) => write!(f, "(boolean `{}`)", boolean),
             TokenContent::Integer(int) => write!(f, "(integer `{}`)", int),
             TokenContent::Real(real) => write!(f, "(real `{}`)", real),
@@ -82,6 +84,8 @@ impl std::fmt::Display for TokenContent {
             }
             TokenContent::IndirectObjEnd => write!(f, "(indirect object end)"),
             TokenContent::StreamObjStart(_) => write!(f, "(stream object start)"),
+            TokenContent::Operator(operator) => write!(f, "(operator `{}`)", operator),
+            TokenContent::InlineImageData(_) => write!(f, "(inline image data)"),
         }
     }
 }
@@ -211,6 +215,27 @@ fn parse_hex_string(buffer: &[u8]) -> Result<Vec<u8>, ()> {
     Ok(vec)
 }
 
+fn is_regular_byte(byte: u8) -> bool {
+    !matches!(
+        char::from(byte),
+        '\0' | '\t'
+            | '\n'
+            | '\x0c'
+            | '\r'
+            | ' '
+            | '('
+            | ')'
+            | '<'
+            | '>'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '/'
+            | '%'
+    )
+}
+
 // PDFオブジェクトの字句解析を行う
 pub struct Lexer<'a> {
     buffer: &'a [u8],
@@ -221,11 +246,13 @@ pub struct Lexer<'a> {
     char: char,
     has_indirect_obj_start: bool,
     has_indirect_obj_end: bool,
+    // コンテンツストリームでは間接参照や間接オブジェクトは現れず，代わりに演算子が現れる
+    is_content_stream: bool,
     pub token_vec: Vec<Token>,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(buffer: &[u8], buffer_start_offset: u64) -> Lexer {
+    pub fn new(buffer: &[u8], buffer_start_offset: u64) -> Lexer<'_> {
         if buffer.is_empty() {
             panic!("buffer is empty");
         }
@@ -239,10 +266,18 @@ impl<'a> Lexer<'a> {
             char: char::from(buffer[0]),
             has_indirect_obj_start: false,
             has_indirect_obj_end: false,
+            is_content_stream: false,
             token_vec: vec![],
         }
     }
 
+    pub fn new_content_stream(buffer: &[u8], buffer_start_offset: u64) -> Lexer<'_> {
+        let mut lexer = Lexer::new(buffer, buffer_start_offset);
+        lexer.is_content_stream = true;
+
+        lexer
+    }
+
     fn move_next_byte(&mut self) -> bool {
         self.i += 1;
 
@@ -290,24 +325,7 @@ impl<'a> Lexer<'a> {
     }
 
     fn is_regular_char(&self) -> bool {
-        !matches!(
-            self.char,
-            '\0' | '\t'
-                | '\n'
-                | '\x12'
-                | '\r'
-                | ' '
-                | '('
-                | ')'
-                | '<'
-                | '>'
-                | '['
-                | ']'
-                | '{'
-                | '}'
-                | '/'
-                | '%'
-        )
+        is_regular_byte(self.byte)
     }
 
     // カーソル下のバイト列がtargetバイト列と一致するならカーソルをtargetの最後まで移動させる
@@ -344,10 +362,47 @@ impl<'a> Lexer<'a> {
         Error::new(kind, self.buffer_start_offset + self.token_head_i as u64)
     }
 
+    pub fn buffer_start_offset(&self) -> u64 {
+        self.buffer_start_offset
+    }
+
     pub fn has_unbalanced_indirectobj(&self) -> bool {
         self.has_indirect_obj_start ^ self.has_indirect_obj_end
     }
 
+    // IDの直後の空白1文字から，空白に続くEIの直前までをインライン画像のデータとする
+    // データ中に偶然同じ並びが現れると誤るが，長さが分からないのでこうするしかない
+    fn tokenize_inline_image_data(&mut self) -> Result<(), Error> {
+        let start = self.i + 1;
+
+        let mut end = start;
+        loop {
+            if self.buffer.len() < end + 3 {
+                return Err(self.construct_error(ErrorKind::FinishInObject));
+            }
+
+            let is_ei = matches!(self.buffer[end], 0 | 9 | 10 | 12 | 13 | 32)
+                && &self.buffer[(end + 1)..(end + 3)] == "EI".as_bytes()
+                && (self.buffer.len() == end + 3 || !is_regular_byte(self.buffer[end + 3]));
+            if is_ei {
+                break;
+            }
+
+            end += 1;
+        }
+
+        self.token_head_i = start;
+        self.i = end;
+        self.byte = self.buffer[end];
+        self.char = char::from(self.byte);
+
+        self.confirm_token(TokenContent::InlineImageData(
+            self.buffer[start..end].to_vec(),
+        ));
+
+        Ok(())
+    }
+
     // オブジェクト境界で区切られたbufferが入力されることを想定してbufferを字句解析する
     // 基本的に1つのオブジェクトの字句解析を目的としているのでstreamキーワードやendobjキーワードが来たらその時点で強制的に終了する
     pub fn tokenize(&mut self) -> Result<(), Error> {
@@ -525,11 +580,7 @@ impl<'a> Lexer<'a> {
 
                     // バックスラッシュを呼んだときには次の文字をエスケープする必要がある
                     // ただしバックスラッシュの連続はバックスラッシュそのものを表すため無視する
-                    if !prev_backslash && self.char == '\\' {
-                        prev_backslash = true;
-                    } else {
-                        prev_backslash = false;
-                    }
+                    prev_backslash = !prev_backslash && self.char == '\\';
 
                     if !self.move_next_byte() {
                         return Err(self.construct_error(ErrorKind::FinishInObject));
@@ -561,7 +612,8 @@ impl<'a> Lexer<'a> {
             }
 
             // 間接参照
-            if self.char == 'R' {
+            // コンテンツストリームではRGなどの演算子と衝突するので解釈しない
+            if !self.is_content_stream && self.char == 'R' {
                 let may_gen_num = self.cancel_token();
                 let may_obj_num = self.cancel_token();
 
@@ -591,7 +643,7 @@ impl<'a> Lexer<'a> {
             }
 
             // 間接オブジェクト
-            if self.assume_and_move("obj".as_bytes()) {
+            if !self.is_content_stream && self.assume_and_move("obj".as_bytes()) {
                 let may_gen_num = self.cancel_token();
                 let may_obj_num = self.cancel_token();
 
@@ -619,7 +671,7 @@ impl<'a> Lexer<'a> {
             }
 
             // 間接参照オブジェクトを読み終えたら強制的に字句解析を終了
-            if self.assume_and_move("endobj".as_bytes()) {
+            if !self.is_content_stream && self.assume_and_move("endobj".as_bytes()) {
                 self.expect_keyword_end()?;
 
                 self.confirm_token(TokenContent::IndirectObjEnd);
@@ -629,7 +681,7 @@ impl<'a> Lexer<'a> {
 
             // ストリームオブジェクト
             // ストリームバイト列が始まるファイル内オフセットを計算して字句解析を終了する
-            if self.assume_and_move("stream".as_bytes()) {
+            if !self.is_content_stream && self.assume_and_move("stream".as_bytes()) {
                 // 現在mというバイトを指している
                 // バイトmの1バイトとその後のEOLが最大2バイトあるのでStream先頭を把握するためには残り3バイト以上あることが必須
                 if (self.buffer.len() - self.i) < 3 {
@@ -681,6 +733,28 @@ impl<'a> Lexer<'a> {
                 continue;
             }
 
+            // コンテンツストリームではそれ以外のキーワードを演算子とみなす
+            if self.is_content_stream && self.is_regular_char() {
+                while self.is_regular_char() {
+                    if !self.move_next_byte() {
+                        break;
+                    }
+                }
+
+                let operator = match parse_name(&self.buffer[self.token_head_i..self.i]) {
+                    Ok(operator) => operator,
+                    Err(_) => return Err(self.construct_error(ErrorKind::UndefinedKeyword)),
+                };
+
+                let is_inline_image_data_start = operator == "ID";
+                self.confirm_token(TokenContent::Operator(operator));
+
+                if is_inline_image_data_start {
+                    self.tokenize_inline_image_data()?;
+                }
+                continue;
+            }
+
             return Err(self.construct_error(ErrorKind::UndefinedKeyword));
         }
 
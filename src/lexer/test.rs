@@ -1,6 +1,6 @@
 use super::*;
 
-fn eq_token_vec(v1: &Vec<Token>, v2: &Vec<Token>) -> bool {
+fn eq_token_vec(v1: &[Token], v2: &[Token]) -> bool {
     if v1.len() != v2.len() {
         false
     } else {
@@ -8,7 +8,7 @@ fn eq_token_vec(v1: &Vec<Token>, v2: &Vec<Token>) -> bool {
     }
 }
 
-fn assert_eq_token_vec(v1: &Vec<Token>, v2: &Vec<Token>) {
+fn assert_eq_token_vec(v1: &[Token], v2: &[Token]) {
     if !eq_token_vec(v1, v2) {
         panic!("left: {:?} right: {:?}", v1, v2);
     }
@@ -21,7 +21,7 @@ fn tokenize_space_eol() {
 
     lexer.tokenize().unwrap();
 
-    assert_eq_token_vec(&lexer.token_vec, &vec![])
+    assert_eq_token_vec(&lexer.token_vec, &[])
 }
 
 #[test]
@@ -33,7 +33,7 @@ fn tokenize_integer() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::Integer(123), 1),
             Token::new(TokenContent::Integer(-123), 6),
         ],
@@ -49,7 +49,7 @@ fn tokenize_float() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::Real(1.5), 0),
             Token::new(TokenContent::Real(-23.4), 4),
             Token::new(TokenContent::Real(110.0), 10),
@@ -70,7 +70,7 @@ fn tokenize_hex_str() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![Token::new(TokenContent::HexStr(vec![160, 224, 240]), 0)],
+        &[Token::new(TokenContent::HexStr(vec![160, 224, 240]), 0)],
     )
 }
 
@@ -83,7 +83,7 @@ fn tokenize_string() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![Token::new(
+        &[Token::new(
             TokenContent::String(vec![
                 104, 111, 103, 101, 32, 9, 32, 92, 32, 43, 32, 40, 5, 51, 41,
             ]),
@@ -101,7 +101,7 @@ fn tokenize_array() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::ArrayStart, 0),
             Token::new(TokenContent::Integer(123), 1),
             Token::new(TokenContent::String(vec![97, 97, 40]), 5),
@@ -120,7 +120,7 @@ fn tokenize_indirect_ref_1() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![Token::new(TokenContent::IndirectRef(1, 0), 2)],
+        &[Token::new(TokenContent::IndirectRef(1, 0), 2)],
     )
 }
 
@@ -133,7 +133,7 @@ fn tokenize_indirect_ref_2() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::Integer(123), 0),
             Token::new(TokenContent::IndirectRef(1, 0), 4),
         ],
@@ -149,13 +149,30 @@ fn tokenize_name() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![Token::new(
+        &[Token::new(
             TokenContent::Name(String::from("Name..;$@?!")),
             0,
         )],
     )
 }
 
+#[test]
+fn tokenize_name_followed_by_form_feed() {
+    // 改ページ(0x0C)も空白文字なので名前はそこで終わる
+    let buffer = "/A\x0c/B ".as_bytes();
+    let mut lexer = Lexer::new(buffer, 0);
+
+    lexer.tokenize().unwrap();
+
+    assert_eq_token_vec(
+        &lexer.token_vec,
+        &[
+            Token::new(TokenContent::Name(String::from("A")), 0),
+            Token::new(TokenContent::Name(String::from("B")), 3),
+        ],
+    )
+}
+
 #[test]
 fn tokenize_comment() {
     let buffer = "/Name%hogehoge /..<>(){}[]\r\n123".as_bytes();
@@ -165,7 +182,7 @@ fn tokenize_comment() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::Name(String::from("Name")), 0),
             Token::new(TokenContent::Integer(123), 28),
         ],
@@ -181,7 +198,7 @@ fn tokenize_boolean_null() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::Null, 0),
             Token::new(TokenContent::Boolean(true), 5),
             Token::new(TokenContent::Boolean(false), 10),
@@ -199,10 +216,31 @@ fn tokenize_indirect_obj() {
 
     assert_eq_token_vec(
         &lexer.token_vec,
-        &vec![
+        &[
             Token::new(TokenContent::IndirectObjStart(1, 0), 0),
             Token::new(TokenContent::Integer(123), 8),
             Token::new(TokenContent::IndirectObjEnd, 12),
         ],
     )
 }
+
+#[test]
+fn tokenize_content_stream_operators() {
+    let buffer = "1 0 0 RG q T* ET".as_bytes();
+    let mut lexer = Lexer::new_content_stream(buffer, 0);
+
+    lexer.tokenize().unwrap();
+
+    assert_eq_token_vec(
+        &lexer.token_vec,
+        &[
+            Token::new(TokenContent::Integer(1), 0),
+            Token::new(TokenContent::Integer(0), 2),
+            Token::new(TokenContent::Integer(0), 4),
+            Token::new(TokenContent::Operator(String::from("RG")), 6),
+            Token::new(TokenContent::Operator(String::from("q")), 9),
+            Token::new(TokenContent::Operator(String::from("T*")), 11),
+            Token::new(TokenContent::Operator(String::from("ET")), 14),
+        ],
+    )
+}
@@ -1,23 +1,47 @@
 use ::image as image_lib;
 use std::fs::File;
 
+mod cie;
+mod content;
 mod cross_reference;
 mod error;
+mod filter;
+mod font;
+mod function;
+mod graphics;
 mod header;
+mod icc;
 mod image;
+mod layout;
 mod lexer;
 mod object;
 mod page;
 mod page_tree;
 mod parser;
 mod raw_byte;
+#[cfg(test)]
+mod test_util;
+mod text;
 mod trailer;
 mod util;
 
+pub use crate::content::Operation;
+pub use crate::font::{EmbeddedFont, FontFileType, FontInfo};
+pub use crate::graphics::{Color, ImagePlacement};
+pub use crate::image::{
+    CcittFaxParam, EncodedFormat, EncodedImage, ImageInfo, ImageOption, MaskType, NativeImage,
+    RawImage,
+};
+pub use crate::layout::{LayoutOption, TextBlock};
+pub use crate::object::{
+    PdfArray, PdfBoolean, PdfDict, PdfIndirectObj, PdfIndirectRef, PdfInteger, PdfName, PdfNull,
+    PdfReal, PdfStreamObj, PdfString,
+};
+pub use crate::parser::Object;
+pub use crate::text::{TextChar, TextLine, TextWord};
+
 pub struct PDF<'a> {
     file: &'a mut File,
-    size: u64,
-    trailer: trailer::Trailer,
     xref: cross_reference::XRef,
     pages: page_tree::Pages,
 }
@@ -44,30 +68,218 @@ impl<'a> PDF<'a> {
 
         let pages = page_tree::Pages::new(file, &xref, pages_ref)?;
 
-        Ok(PDF {
-            file,
-            size,
-            trailer,
-            xref,
-            pages,
-        })
+        Ok(PDF { file, xref, pages })
     }
 
     fn get_file_size(file: &File) -> Result<u64, std::io::Error> {
         Ok(file.metadata()?.len())
     }
 
+    pub fn page_count(&self) -> usize {
+        self.pages.get_page_number()
+    }
+
+    // ページのコンテンツストリームを演算子とオペランドの列として読む
+    pub fn content_operations(
+        &mut self,
+        page_number: usize,
+    ) -> Result<Vec<Operation>, error::Error> {
+        let page = self.pages.get_page(page_number)?;
+
+        Ok(page.content_operations(self.file, &self.xref)?)
+    }
+
     pub fn extract_image(
         &mut self,
         request_pages: &Vec<usize>,
     ) -> Result<Vec<Vec<image_lib::RgbImage>>, error::Error> {
-        let mut images_of_pages: Vec<Vec<image_lib::RgbImage>> = vec![];
+        let images_of_pages =
+            self.extract_image_with_option(request_pages, &ImageOption::default())?;
+
+        Ok(images_of_pages
+            .into_iter()
+            .map(|images| images.into_iter().map(|image| image.into_rgb8()).collect())
+            .collect())
+    }
+
+    pub fn extract_image_with_option(
+        &mut self,
+        request_pages: &Vec<usize>,
+        option: &ImageOption,
+    ) -> Result<Vec<Vec<image_lib::DynamicImage>>, error::Error> {
+        let mut images_of_pages: Vec<Vec<image_lib::DynamicImage>> = vec![];
         for page_number in request_pages {
             let page = self.pages.get_page(*page_number)?;
 
-            images_of_pages.push(page.extract_images(self.file, &self.xref).unwrap());
+            images_of_pages.push(page.extract_images(self.file, &self.xref, option)?);
         }
 
         Ok(images_of_pages)
     }
+
+    // 色空間を変換せずに，画像の成分をそのまま取り出す
+    pub fn extract_native_image(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<Vec<NativeImage>>, error::Error> {
+        let mut images_of_pages: Vec<Vec<NativeImage>> = vec![];
+        for page_number in request_pages {
+            let page = self.pages.get_page(*page_number)?;
+
+            images_of_pages.push(page.extract_native_images(self.file, &self.xref)?);
+        }
+
+        Ok(images_of_pages)
+    }
+
+    // 画像XObjectに埋め込まれているJPEGなどのデータを再符号化せずに取り出す
+    pub fn extract_encoded_image(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<Vec<EncodedImage>>, error::Error> {
+        let mut images_of_pages: Vec<Vec<EncodedImage>> = vec![];
+        for page_number in request_pages {
+            let page = self.pages.get_page(*page_number)?;
+
+            images_of_pages.push(page.extract_encoded_images(self.file, &self.xref)?);
+        }
+
+        Ok(images_of_pages)
+    }
+
+    // 画像をデコードせずに，解像度や色空間などの情報だけを集める
+    pub fn image_infos(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<Vec<ImageInfo>>, error::Error> {
+        let mut infos_of_pages: Vec<Vec<ImageInfo>> = vec![];
+        for page_number in request_pages {
+            let page = self.pages.get_page(*page_number)?;
+
+            infos_of_pages.push(page.image_infos(self.file, &self.xref)?);
+        }
+
+        Ok(infos_of_pages)
+    }
+
+    // ページ上で画像が描かれる位置と大きさ，実効解像度を集める
+    pub fn image_placements(
+        &mut self,
+        page_number: usize,
+    ) -> Result<Vec<ImagePlacement>, error::Error> {
+        let page = self.pages.get_page(page_number)?;
+
+        Ok(page.image_placements(self.file, &self.xref)?)
+    }
+
+    // ページごとに，使われるフォントの名前，種類，符号化，埋め込みの有無などを集める
+    pub fn font_infos(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<Vec<FontInfo>>, error::Error> {
+        let mut infos_of_pages: Vec<Vec<FontInfo>> = vec![];
+        for page_number in request_pages {
+            let page = self.pages.get_page(*page_number)?;
+
+            infos_of_pages.push(page.font_infos(self.file, &self.xref)?);
+        }
+
+        Ok(infos_of_pages)
+    }
+
+    // 文書全体で使われるフォントの情報．複数のページで使われるフォントは一度だけ返す
+    pub fn document_font_infos(&mut self) -> Result<Vec<FontInfo>, error::Error> {
+        let request_pages: Vec<usize> = (1..=self.page_count()).collect();
+
+        let mut infos: Vec<FontInfo> = vec![];
+        for info in self.font_infos(&request_pages)?.into_iter().flatten() {
+            let is_new = match info.object_ref {
+                Some(_) => infos.iter().all(|i| i.object_ref != info.object_ref),
+                // 直接書かれたフォント辞書は参照で区別できないので，同じ内容なら一度だけ返す
+                None => !infos.contains(&info),
+            };
+            if is_new {
+                infos.push(info);
+            }
+        }
+
+        Ok(infos)
+    }
+
+    // ページで使われるフォントの埋め込まれたフォントプログラム
+    // 複数のページで同じものが使われていても一度だけ返す
+    pub fn extract_fonts(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<EmbeddedFont>, error::Error> {
+        let mut fonts: Vec<EmbeddedFont> = vec![];
+        for page_number in request_pages {
+            let page = self.pages.get_page(*page_number)?;
+
+            for font in page.embedded_fonts(self.file, &self.xref)? {
+                if fonts.iter().all(|f| f.object_ref != font.object_ref) {
+                    fonts.push(font);
+                }
+            }
+        }
+
+        Ok(fonts)
+    }
+
+    // ページに描かれた文字を，位置に応じて空白や改行を補ってつなげる
+    pub fn extract_text(&mut self, page_number: usize) -> Result<String, error::Error> {
+        let page = self.pages.get_page(page_number)?;
+
+        Ok(page.extract_text(self.file, &self.xref)?)
+    }
+
+    // ページに描かれた1文字ずつの文字列，フォント，色と位置
+    pub fn text_chars(&mut self, page_number: usize) -> Result<Vec<TextChar>, error::Error> {
+        let page = self.pages.get_page(page_number)?;
+
+        Ok(page.text_chars(self.file, &self.xref)?)
+    }
+
+    // ページの文字を語と行にまとめる
+    pub fn text_lines(&mut self, page_number: usize) -> Result<Vec<TextLine>, error::Error> {
+        Ok(text::group_lines(&self.text_chars(page_number)?))
+    }
+
+    // ページの文字を段落にまとめ，段組みを考えて読む順に並べる
+    pub fn text_blocks(
+        &mut self,
+        request_pages: &Vec<usize>,
+        option: &LayoutOption,
+    ) -> Result<Vec<Vec<TextBlock>>, error::Error> {
+        let mut lines_of_pages: Vec<Vec<TextLine>> = vec![];
+        for page_number in request_pages {
+            lines_of_pages.push(self.text_lines(*page_number)?);
+        }
+
+        if option.remove_headers_footers {
+            layout::remove_repeated_lines(&mut lines_of_pages);
+        }
+
+        Ok(lines_of_pages
+            .into_iter()
+            .map(layout::analyze_page)
+            .collect())
+    }
+
+    // /SMaskや/Maskを透明度として合成したRGBA画像を取り出す
+    pub fn extract_image_with_alpha(
+        &mut self,
+        request_pages: &Vec<usize>,
+    ) -> Result<Vec<Vec<image_lib::RgbaImage>>, error::Error> {
+        let option = ImageOption {
+            apply_mask: true,
+            ..Default::default()
+        };
+        let images_of_pages = self.extract_image_with_option(request_pages, &option)?;
+
+        Ok(images_of_pages
+            .into_iter()
+            .map(|images| images.into_iter().map(|image| image.into_rgba8()).collect())
+            .collect())
+    }
 }
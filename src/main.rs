@@ -1,8 +1,7 @@
 extern crate pdf_parser;
 
-use image::codecs::jpeg::JpegEncoder;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::process;
 
 use pdf_parser::PDF;
@@ -27,22 +26,106 @@ fn main() {
         process::exit(1)
     });
 
+    // --list-fontsを付けるとpdffontsのように文書で使われるフォントを一覧にする
+    if args[2..].iter().any(|arg| arg == "--list-fonts") {
+        println!(
+            "{:<40} {:<24} {:<20} emb sub uni object ID",
+            "name", "type", "encoding"
+        );
+        println!(
+            "{} {} {} --- --- --- ---------",
+            "-".repeat(40),
+            "-".repeat(24),
+            "-".repeat(20)
+        );
+
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        for info in pdf.document_font_infos().unwrap() {
+            let object_id = match info.object_ref {
+                Some((object_number, generation_number)) => {
+                    format!("{:>6} {:>2}", object_number, generation_number)
+                }
+                None => String::from("[none]"),
+            };
+            let font_type = match &info.descendant_subtype {
+                Some(descendant_subtype) => format!("{} ({})", info.subtype, descendant_subtype),
+                None => info.subtype.clone(),
+            };
+            println!(
+                "{:<40} {:<24} {:<20} {:<3} {:<3} {:<3} {}",
+                info.name.as_deref().unwrap_or("[none]"),
+                font_type,
+                info.encoding,
+                yes_no(info.is_embedded),
+                yes_no(info.is_subset),
+                yes_no(info.has_to_unicode),
+                object_id
+            );
+        }
+
+        return;
+    }
+
+    // --fontsを付けると画像の代わりに埋め込まれたフォントを保存する
+    if args[2..].iter().any(|arg| arg == "--fonts") {
+        let request_pages: Vec<usize> = (1..=pdf.page_count()).collect();
+
+        for font in pdf.extract_fonts(&request_pages).unwrap() {
+            let name: String = font
+                .base_font
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let filename = format!(
+                "{}-{}.{}",
+                font.object_ref.0,
+                name,
+                font.font_type.extension()
+            );
+
+            fs::write(filename, &font.data).unwrap();
+        }
+
+        return;
+    }
+
+    let request_pages: Vec<usize> = (1..=5).collect();
+
     for (page_number, images) in pdf
-        .extract_image(&(1..=5).collect())
+        .extract_encoded_image(&request_pages)
         .unwrap()
         .iter()
         .enumerate()
     {
-        for (image_number, image) in images.iter().enumerate() {
-            let mut file = File::create(format!("{}-{}.jpg", page_number, image_number)).unwrap();
+        // そのまま保存できない画像があるページだけデコードする
+        let mut decoded_images = None;
 
-            let mut encoder = JpegEncoder::new(file);
+        for (image_number, image) in images.iter().enumerate() {
+            let filename = format!("{}-{}", page_number, image_number);
 
-            encoder.encode_image(image).unwrap();
+            match image.extension() {
+                Some(extension) => {
+                    let data = image.to_tiff().unwrap_or_else(|| image.data.clone());
+                    fs::write(format!("{}.{}", filename, extension), data).unwrap();
+                }
+                None => {
+                    let decoded_images = decoded_images.get_or_insert_with(|| {
+                        pdf.extract_image(&vec![request_pages[page_number]])
+                            .unwrap()
+                            .remove(0)
+                    });
 
-            //image
-            //    .save(format!("{}-{}.png", page_number, image_number))
-            //    .unwrap();
+                    decoded_images[image_number]
+                        .save(format!("{}.png", filename))
+                        .unwrap();
+                }
+            }
         }
     }
 }
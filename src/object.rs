@@ -74,6 +74,13 @@ impl PdfBoolean {
         }
     }
 
+    pub fn ensure(obj: &Object) -> Result<&Self, Error> {
+        match obj {
+            Object::Boolean(boolean) => Ok(boolean),
+            _ => Err(PdfBoolean::type_missmatch_error(obj.byte_offset())),
+        }
+    }
+
     pub fn unpack(&self) -> bool {
         self.payload
     }
@@ -158,7 +165,7 @@ impl std::convert::TryFrom<PdfInteger> for u64 {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfReal {
     payload: f64,
     byte_offset: u64,
@@ -174,6 +181,18 @@ impl PdfReal {
     pub fn unpack(&self) -> f64 {
         self.payload
     }
+
+    // 整数と実数を区別せずに数値として取り出す
+    pub fn ensure_number(obj: &Object) -> Result<f64, Error> {
+        match obj {
+            Object::Integer(int) => Ok(int.unpack() as f64),
+            Object::Real(real) => Ok(real.unpack()),
+            _ => Err(Error::new(
+                ErrorKind::ObjectTypeMissMatch("number"),
+                obj.byte_offset(),
+            )),
+        }
+    }
 }
 impl PdfObject for PdfReal {
     fn byte_offset(&self) -> u64 {
@@ -222,7 +241,7 @@ impl PartialEq<str> for PdfName {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfString {
     payload: Vec<u8>,
     byte_offset: u64,
@@ -234,6 +253,17 @@ impl PdfString {
             byte_offset,
         }
     }
+
+    pub fn ensure(obj: &Object) -> Result<&Self, Error> {
+        match obj {
+            Object::String(string) => Ok(string),
+            _ => Err(PdfString::type_missmatch_error(obj.byte_offset())),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.payload.as_slice()
+    }
 }
 impl PdfObject for PdfString {
     fn byte_offset(&self) -> u64 {
@@ -244,7 +274,7 @@ impl PdfObject for PdfString {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfArray {
     payload: Vec<Object>,
     byte_offset: u64,
@@ -267,6 +297,14 @@ impl PdfArray {
     pub fn get(&self, i: usize) -> Option<&Object> {
         self.payload.get(i)
     }
+
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
 }
 impl PdfObject for PdfArray {
     fn byte_offset(&self) -> u64 {
@@ -286,7 +324,7 @@ impl<'a> std::iter::IntoIterator for &'a PdfArray {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfNull {
     byte_offset: u64,
 }
@@ -328,7 +366,7 @@ impl PdfIndirectRef {
         }
     }
 
-    pub fn get_indirect_obj(
+    pub(crate) fn get_indirect_obj(
         &self,
         file: &mut File,
         xref: &cross_reference::XRef,
@@ -391,7 +429,7 @@ impl PdfObject for PdfIndirectRef {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfDict {
     payload: HashMap<String, Object>,
     byte_offset: u64,
@@ -437,7 +475,7 @@ impl PdfDict {
     pub fn assert_with_key(&self, keys: Vec<&'static str>) -> Result<(), Error> {
         for key in keys {
             let key_str = String::from(key);
-            if self.payload.get(&key_str).is_none() {
+            if !self.payload.contains_key(&key_str) {
                 return Err(Error::new(
                     ErrorKind::DictKeyNotFound(key),
                     self.byte_offset,
@@ -448,11 +486,11 @@ impl PdfDict {
         Ok(())
     }
 
-    pub fn get(&self, key: &'static str) -> Option<&Object> {
+    pub fn get(&self, key: &str) -> Option<&Object> {
         self.payload.get(key)
     }
 
-    pub fn iter(&self) -> std::collections::hash_map::Iter<String, Object> {
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, String, Object> {
         self.payload.iter()
     }
 }
@@ -465,7 +503,7 @@ impl PdfObject for PdfDict {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfIndirectObj {
     payload: Box<Object>,
     byte_offset: u64,
@@ -486,7 +524,11 @@ impl PdfIndirectObj {
     }
 
     pub fn get_object(&self) -> &Object {
-        &*self.payload
+        &self.payload
+    }
+
+    pub fn into_object(self) -> Object {
+        *self.payload
     }
 }
 impl PdfObject for PdfIndirectObj {
@@ -501,7 +543,7 @@ impl PdfObject for PdfIndirectObj {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PdfStreamObj {
     pub dict: PdfDict,
     byte_offset: u64,
@@ -524,7 +566,7 @@ impl PdfStreamObj {
         }
     }
 
-    pub fn get_stream(
+    pub(crate) fn get_stream(
         &self,
         file: &mut File,
         xref: &cross_reference::XRef,
@@ -573,3 +615,19 @@ impl PdfObject for PdfStreamObj {
         Error::new(ErrorKind::ObjectTypeMissMatch("stream object"), byte_offset)
     }
 }
+
+// 間接参照なら参照先のオブジェクトを取り出し，そうでなければそのまま複製して返す
+// 参照先がストリームオブジェクトの場合はストリームオブジェクトを返す
+pub fn resolve(
+    obj: &Object,
+    file: &mut File,
+    xref: &cross_reference::XRef,
+) -> Result<Object, Error> {
+    match obj {
+        Object::IndirectRef(indirect_ref) => match indirect_ref.get_indirect_obj(file, xref)? {
+            Object::IndirectObj(indirect_obj) => Ok(indirect_obj.into_object()),
+            obj => Ok(obj),
+        },
+        obj => Ok(obj.clone()),
+    }
+}
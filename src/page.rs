@@ -1,19 +1,41 @@
 use image as image_lib;
 use std::fs::File;
 
+use crate::content;
 use crate::cross_reference::XRef;
+use crate::font;
+use crate::graphics;
 use crate::image as image_localmod;
 use crate::object;
+use crate::parser::Object;
+use crate::text;
+
+#[cfg(test)]
+mod test;
 
 #[derive(Debug)]
 pub enum Error {
     Object(object::Error),
+    Image(image_localmod::Error),
+    NotImageXObject(String),
+    Content(content::Error),
+    Graphics(graphics::Error),
+    Text(text::Error),
+    Font(font::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match self {
             Self::Object(e) => write!(f, "object: {}", e),
+            Self::Image(e) => write!(f, "image: {}", e),
+            Self::NotImageXObject(subtype) => {
+                write!(f, "XObject of subtype `{}` is not image", subtype)
+            }
+            Self::Content(e) => write!(f, "content: {}", e),
+            Self::Graphics(e) => write!(f, "graphics: {}", e),
+            Self::Text(e) => write!(f, "text: {}", e),
+            Self::Font(e) => write!(f, "font: {}", e),
         }
     }
 }
@@ -24,54 +46,287 @@ impl From<object::Error> for Error {
     }
 }
 
+impl From<image_localmod::Error> for Error {
+    fn from(e: image_localmod::Error) -> Error {
+        Self::Image(e)
+    }
+}
+
+impl From<content::Error> for Error {
+    fn from(e: content::Error) -> Error {
+        Self::Content(e)
+    }
+}
+
+impl From<graphics::Error> for Error {
+    fn from(e: graphics::Error) -> Error {
+        Self::Graphics(e)
+    }
+}
+
+impl From<text::Error> for Error {
+    fn from(e: text::Error) -> Error {
+        Self::Text(e)
+    }
+}
+
+impl From<font::Error> for Error {
+    fn from(e: font::Error) -> Error {
+        Self::Font(e)
+    }
+}
+
 #[derive(Debug)]
 pub struct Page {
     page_number: usize,
-    thumbnail: Option<object::PdfIndirectRef>,
     external_objects: Vec<object::PdfIndirectRef>,
+    // ページとForm XObjectの/Resourcesにあるフォント辞書への参照か，直接書かれたフォント辞書
+    fonts: Vec<Object>,
+    // /Contentsが省略されたページは何も描かない
+    contents: Option<Object>,
+    resources: Option<Object>,
 }
 
 impl Page {
     pub fn new(
         page_number: usize,
-        thumbnail_ref: Option<object::PdfIndirectRef>,
         external_objects: Vec<object::PdfIndirectRef>,
+        fonts: Vec<Object>,
+        contents: Option<Object>,
+        resources: Option<Object>,
     ) -> Self {
         Self {
             page_number,
-            thumbnail: thumbnail_ref,
             external_objects,
+            fonts,
+            contents,
+            resources,
         }
     }
 
+    fn get_resources(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Option<object::PdfDict>, Error> {
+        let resources_obj = match &self.resources {
+            Some(resources_obj) => object::resolve(resources_obj, file, xref)?,
+            None => return Ok(None),
+        };
+
+        Ok(Some(
+            object::PdfDict::ensure_with_key(&resources_obj, vec![])?.clone(),
+        ))
+    }
+
     pub fn get_page_number(&self) -> usize {
         self.page_number
     }
 
+    // /Contentsのストリームを連結して操作の列にする
+    pub fn content_operations(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<content::Operation>, Error> {
+        let contents_obj = match &self.contents {
+            Some(contents_obj) => contents_obj,
+            None => return Ok(vec![]),
+        };
+
+        let content = content::decode_contents(contents_obj, file, xref)?;
+
+        Ok(content::parse_operations(&content)?)
+    }
+
+    // 画像XObjectのDoとインライン画像が，ページのどこにどの大きさで描かれるかを集める
+    pub fn image_placements(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<graphics::ImagePlacement>, Error> {
+        let operations = self.content_operations(file, xref)?;
+        let resources = self.get_resources(file, xref)?;
+
+        Ok(graphics::find_image_placements(
+            &operations,
+            resources.as_ref(),
+            file,
+            xref,
+        )?)
+    }
+
+    // 文字を描く操作を解釈して，ページ上の1文字ずつの位置を集める
+    pub fn text_chars(&self, file: &mut File, xref: &XRef) -> Result<Vec<text::TextChar>, Error> {
+        let operations = self.content_operations(file, xref)?;
+        let resources = self.get_resources(file, xref)?;
+
+        let mut extractor = text::TextExtractor::new();
+        graphics::walk_operations(&operations, resources.as_ref(), &mut extractor, file, xref)?;
+
+        Ok(extractor.into_chars())
+    }
+
+    // ページのテキストを取り出す
+    pub fn extract_text(&self, file: &mut File, xref: &XRef) -> Result<String, Error> {
+        Ok(text::chars_to_text(&self.text_chars(file, xref)?))
+    }
+
+    // ページ，Form XObjectと注釈の外観で使われるフォントの情報
+    pub fn font_infos(&self, file: &mut File, xref: &XRef) -> Result<Vec<font::FontInfo>, Error> {
+        let mut infos = vec![];
+        for font_obj in &self.fonts {
+            let object_ref = match font_obj {
+                Object::IndirectRef(font_ref) => Some(font_ref.unpack()),
+                _ => None,
+            };
+            let font_obj = object::resolve(font_obj, file, xref)?;
+            let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![])?;
+
+            infos.push(font::FontInfo::new(object_ref, font_dict, file, xref)?);
+        }
+
+        Ok(infos)
+    }
+
+    // ページで使われるフォントに埋め込まれたフォントプログラムを取り出す
+    pub fn embedded_fonts(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<font::EmbeddedFont>, Error> {
+        let mut fonts = vec![];
+        for font_obj in &self.fonts {
+            let font_obj = object::resolve(font_obj, file, xref)?;
+            let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![])?;
+
+            fonts.append(&mut font::extract_embedded_fonts(font_dict, file, xref)?);
+        }
+
+        Ok(fonts)
+    }
+
     pub fn extract_images(
         &self,
         file: &mut File,
         xref: &XRef,
-    ) -> Result<Vec<image_lib::RgbImage>, Error> {
-        let mut images: Vec<image_lib::RgbImage> = vec![];
+        option: &image_localmod::ImageOption,
+    ) -> Result<Vec<image_lib::DynamicImage>, Error> {
+        let mut images: Vec<image_lib::DynamicImage> = vec![];
 
-        let mut smasks: Vec<object::PdfIndirectRef> = vec![];
-        for xobj_ref in &(self.external_objects) {
-            let may_smask_ref = contained_smask_in_xobj(xobj_ref, file, xref)?;
-            if let Some(smask_ref) = may_smask_ref {
-                smasks.push(smask_ref);
-            }
+        for xobj_ref in self.image_xobjects(file, xref)? {
+            let image = construct_image_from_xobj(xobj_ref, file, xref, option)?;
+            images.push(image);
         }
 
-        for xobj_ref in &(self.external_objects) {
-            if !smasks.contains(xobj_ref) {
-                let image = construct_image_from_xobj(xobj_ref, file, xref)?;
-                images.push(image);
-            }
+        // インライン画像はリソースには現れずコンテンツストリームの中にだけある
+        // 画像XObjectと同じく，読めない画像があれば失敗する
+        if option.extract_inline_images {
+            let mut collector = InlineImageCollector {
+                option,
+                images: vec![],
+            };
+            let operations = self.content_operations(file, xref)?;
+            let resources = self.get_resources(file, xref)?;
+            graphics::walk_operations(&operations, resources.as_ref(), &mut collector, file, xref)?;
+            images.append(&mut collector.images);
+        }
+
+        Ok(images)
+    }
+
+    pub fn extract_native_images(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<image_localmod::NativeImage>, Error> {
+        let mut images: Vec<image_localmod::NativeImage> = vec![];
+
+        for xobj_ref in self.image_xobjects(file, xref)? {
+            let xobj = xobj_ref.get_indirect_obj(file, xref)?;
+            let xobj = object::PdfStreamObj::ensure_stream(&xobj)?;
+
+            assert_xobj_is_image(&xobj.dict)?;
+
+            let stream_content = xobj.get_stream(file, xref)?;
+            let image_param = image_localmod::ImageDecodeParam::new(&xobj.dict, file, xref)?;
+            images.push(image_localmod::decode_image_native(
+                &image_param,
+                &stream_content,
+            )?);
+        }
+
+        Ok(images)
+    }
+
+    // 画像XObjectのストリームをデコードせずに取り出す
+    pub fn extract_encoded_images(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<image_localmod::EncodedImage>, Error> {
+        let mut images: Vec<image_localmod::EncodedImage> = vec![];
+
+        for xobj_ref in self.image_xobjects(file, xref)? {
+            let xobj = xobj_ref.get_indirect_obj(file, xref)?;
+            let xobj = object::PdfStreamObj::ensure_stream(&xobj)?;
+
+            assert_xobj_is_image(&xobj.dict)?;
+
+            let stream_content = xobj.get_stream(file, xref)?;
+            images.push(image_localmod::EncodedImage::new(
+                &xobj.dict,
+                stream_content,
+                file,
+                xref,
+            )?);
         }
 
         Ok(images)
     }
+
+    // 画像XObjectのストリームを読まずに，辞書から分かる情報を集める
+    pub fn image_infos(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<image_localmod::ImageInfo>, Error> {
+        let mut infos: Vec<image_localmod::ImageInfo> = vec![];
+
+        for xobj_ref in self.image_xobjects(file, xref)? {
+            let xobj = xobj_ref.get_indirect_obj(file, xref)?;
+            let xobj = object::PdfStreamObj::ensure_stream(&xobj)?;
+
+            assert_xobj_is_image(&xobj.dict)?;
+
+            infos.push(image_localmod::ImageInfo::new(
+                xobj_ref.unpack(),
+                &xobj.dict,
+                file,
+                xref,
+            )?);
+        }
+
+        Ok(infos)
+    }
+
+    // 他の画像のマスクとして使われているものを除いた画像XObjectを返す
+    fn image_xobjects(
+        &self,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<Vec<&object::PdfIndirectRef>, Error> {
+        let mut masks: Vec<object::PdfIndirectRef> = vec![];
+        for xobj_ref in &(self.external_objects) {
+            masks.append(&mut contained_masks_in_xobj(xobj_ref, file, xref)?);
+        }
+
+        Ok(self
+            .external_objects
+            .iter()
+            .filter(|xobj_ref| !masks.contains(xobj_ref))
+            .collect())
+    }
 }
 
 fn assert_xobj_is_image(xobj_dict: &object::PdfDict) -> Result<(), Error> {
@@ -79,7 +334,7 @@ fn assert_xobj_is_image(xobj_dict: &object::PdfDict) -> Result<(), Error> {
 
     let subtype = object::PdfName::ensure(xobj_dict.get("Subtype").unwrap())?;
     if subtype != "Image" {
-        panic!("subtype is not image");
+        return Err(Error::NotImageXObject(String::from(subtype.as_str())));
     };
 
     Ok(())
@@ -89,7 +344,8 @@ fn construct_image_from_xobj(
     xobj_ref: &object::PdfIndirectRef,
     file: &mut File,
     xref: &XRef,
-) -> Result<image_lib::RgbImage, Error> {
+    option: &image_localmod::ImageOption,
+) -> Result<image_lib::DynamicImage, Error> {
     let xobj = xobj_ref.get_indirect_obj(file, xref)?;
     let xobj = object::PdfStreamObj::ensure_stream(&xobj)?;
 
@@ -97,24 +353,162 @@ fn construct_image_from_xobj(
 
     let stream_content = xobj.get_stream(file, xref)?;
 
-    let image_param = image_localmod::ImageDecodeParam::new(&xobj.dict, file, xref).unwrap();
-    let image = image_localmod::decode_image(&image_param, &stream_content).unwrap();
+    let image_param = image_localmod::ImageDecodeParam::new(&xobj.dict, file, xref)?;
+    let image = image_localmod::decode_image(&image_param, &stream_content, option)?;
+
+    if option.apply_mask {
+        if let Some(smask_obj) = xobj.dict.get("SMask") {
+            return composite_soft_mask(image, &image_param, smask_obj, file, xref, option);
+        }
+
+        // 色キーマスクはデコード時に適用済みなのでここではステンシルマスクのみを扱う
+        if let Some(Object::IndirectRef(mask_ref)) = xobj.dict.get("Mask") {
+            return composite_stencil_mask(image, mask_ref, file, xref);
+        }
+    }
+
+    Ok(image)
+}
+
+// ページとForm XObjectのコンテンツストリームにあるインライン画像を集める
+// デコードできないインライン画像は飛ばす
+struct InlineImageCollector<'a> {
+    option: &'a image_localmod::ImageOption,
+    images: Vec<image_lib::DynamicImage>,
+}
+
+impl graphics::OperationHandler for InlineImageCollector<'_> {
+    type Error = Error;
+
+    fn handle(
+        &mut self,
+        operation: &content::Operation,
+        _state: &mut graphics::GraphicsState,
+        resources: Option<&object::PdfDict>,
+        file: &mut File,
+        xref: &XRef,
+    ) -> Result<(), Error> {
+        if operation.operator != "BI" {
+            return Ok(());
+        }
+
+        // 略記された色空間の名前は，そのコンテンツストリームのリソースから引く
+        let colorspaces = match resources {
+            Some(resources) => graphics::get_sub_dict(resources, "ColorSpace", file, xref)?,
+            None => None,
+        };
+        self.images.push(construct_image_from_inline(
+            operation,
+            colorspaces.as_ref(),
+            file,
+            xref,
+            self.option,
+        )?);
+
+        Ok(())
+    }
+}
+
+// インライン画像の操作はBIを演算子とし，画像の辞書とデータをオペランドに持つ
+fn construct_image_from_inline(
+    operation: &content::Operation,
+    colorspaces: Option<&object::PdfDict>,
+    file: &mut File,
+    xref: &XRef,
+    option: &image_localmod::ImageOption,
+) -> Result<image_lib::DynamicImage, Error> {
+    let (image_dict, data) = match operation.operands.as_slice() {
+        [image_dict, data] => (
+            object::PdfDict::ensure_with_key(image_dict, vec![])?,
+            object::PdfString::ensure(data)?,
+        ),
+        _ => return Err(Error::Content(content::Error::InvalidInlineImage)),
+    };
+
+    let image_dict = image_localmod::expand_inline_image_dict(image_dict, colorspaces);
+
+    let image_param = image_localmod::ImageDecodeParam::new(&image_dict, file, xref)?;
+    let image = image_localmod::decode_image(&image_param, data.as_bytes(), option)?;
 
     Ok(image)
 }
 
-fn contained_smask_in_xobj(
+// /Maskのステンシルマスクで塗る部分のみを不透明にする
+fn composite_stencil_mask(
+    image: image_lib::DynamicImage,
+    mask_ref: &object::PdfIndirectRef,
+    file: &mut File,
+    xref: &XRef,
+) -> Result<image_lib::DynamicImage, Error> {
+    let mask = mask_ref.get_indirect_obj(file, xref)?;
+    let mask = object::PdfStreamObj::ensure_stream(&mask)?;
+
+    let mask_content = mask.get_stream(file, xref)?;
+    let mask_param = image_localmod::ImageDecodeParam::new(&mask.dict, file, xref)?;
+    let stencil_mask = image_localmod::decode_image(
+        &mask_param,
+        &mask_content,
+        &image_localmod::ImageOption::default(),
+    )?;
+
+    let alpha = image_localmod::stencil_mask_to_alpha(&stencil_mask);
+
+    Ok(image_localmod::apply_soft_mask(image, &alpha, None))
+}
+
+// /SMaskはDeviceGrayの画像XObjectで，その輝度を透明度として扱う
+fn composite_soft_mask(
+    image: image_lib::DynamicImage,
+    image_param: &image_localmod::ImageDecodeParam,
+    smask_obj: &Object,
+    file: &mut File,
+    xref: &XRef,
+    option: &image_localmod::ImageOption,
+) -> Result<image_lib::DynamicImage, Error> {
+    let smask = object::resolve(smask_obj, file, xref)?;
+    let smask = object::PdfStreamObj::ensure_stream(&smask)?;
+
+    let smask_content = smask.get_stream(file, xref)?;
+    let smask_param = image_localmod::ImageDecodeParam::new(&smask.dict, file, xref)?;
+    let mask_option = image_localmod::ImageOption {
+        keep_16bit_precision: true,
+        ..Default::default()
+    };
+    let mask = image_localmod::decode_image(&smask_param, &smask_content, &mask_option)?;
+
+    // /Matteは元画像の色空間での色で，元画像がこの色と事前に乗算されていることを表す
+    let matte = match smask.dict.get("Matte") {
+        Some(matte_obj) => {
+            let mut components = vec![];
+            for obj in object::PdfArray::ensure(matte_obj)? {
+                components.push(object::PdfReal::ensure_number(obj)?);
+            }
+            image_param.color_to_rgb(&components, option)
+        }
+        None => None,
+    };
+
+    Ok(image_localmod::apply_soft_mask(image, &mask, matte))
+}
+
+// /SMaskと，ステンシルマスクとして指定された/Maskの参照を返す
+fn contained_masks_in_xobj(
     xobj_ref: &object::PdfIndirectRef,
     file: &mut File,
     xref: &XRef,
-) -> Result<Option<object::PdfIndirectRef>, Error> {
+) -> Result<Vec<object::PdfIndirectRef>, Error> {
     let xobj = xobj_ref.get_indirect_obj(file, xref)?;
     let xobj = object::PdfStreamObj::ensure_stream(&xobj)?;
 
     assert_xobj_is_image(&xobj.dict)?;
 
-    match &xobj.dict.get("SMask") {
-        Some(obj) => Ok(Some(object::PdfIndirectRef::ensure(obj)?.clone())),
-        None => Ok(None),
+    let mut masks = vec![];
+    if let Some(obj) = xobj.dict.get("SMask") {
+        masks.push(object::PdfIndirectRef::ensure(obj)?.clone());
     }
+    if let Some(Object::IndirectRef(mask_ref)) = xobj.dict.get("Mask") {
+        masks.push(mask_ref.clone());
+    }
+
+    Ok(masks)
 }
@@ -0,0 +1,118 @@
+use super::*;
+use crate::test_util;
+
+fn indirect_ref(object_number: usize) -> object::PdfIndirectRef {
+    let obj = test_util::parse(&format!("{} 0 R", object_number));
+    object::PdfIndirectRef::ensure(&obj).unwrap().clone()
+}
+
+fn gray_image() -> Vec<u8> {
+    test_util::stream(
+        "/Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray /BitsPerComponent 8",
+        &[0x40],
+    )
+}
+
+#[test]
+fn extract_images_with_broken_content_stream() {
+    // コンテンツストリームが壊れていても画像XObjectは取り出せる
+    let (mut file, xref) = test_util::write_pdf(&[
+        gray_image(),
+        test_util::stream("/Filter /FlateDecode", b"broken"),
+    ]);
+    let page = Page::new(
+        1,
+        vec![indirect_ref(1)],
+        vec![],
+        Some(test_util::parse("2 0 R")),
+        Some(test_util::parse("<< /XObject << /Im1 1 0 R >> >>")),
+    );
+
+    let images = page
+        .extract_images(&mut file, &xref, &image_localmod::ImageOption::default())
+        .unwrap();
+
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].to_luma8().into_raw(), vec![0x40]);
+}
+
+#[test]
+fn extract_inline_images_in_form() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        test_util::stream(
+            "/Type /XObject /Subtype /Form /BBox [0 0 1 1]",
+            b"q BI /W 1 /H 1 /CS /G /BPC 8 ID \x80 EI Q",
+        ),
+        test_util::stream("", b"/Fm1 Do"),
+    ]);
+    let page = Page::new(
+        1,
+        vec![],
+        vec![],
+        Some(test_util::parse("2 0 R")),
+        Some(test_util::parse("<< /XObject << /Fm1 1 0 R >> >>")),
+    );
+    let option = image_localmod::ImageOption {
+        extract_inline_images: true,
+        ..Default::default()
+    };
+
+    let images = page.extract_images(&mut file, &xref, &option).unwrap();
+
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].to_luma8().into_raw(), vec![0x80]);
+
+    // 指定しなければインライン画像は取り出さない
+    let images = page
+        .extract_images(&mut file, &xref, &image_localmod::ImageOption::default())
+        .unwrap();
+    assert!(images.is_empty());
+}
+
+#[test]
+fn extract_inline_images_with_broken_content_stream() {
+    // インライン画像を取り出すときはコンテンツストリームが読めなければ失敗する
+    let (mut file, xref) = test_util::write_pdf(&[
+        gray_image(),
+        test_util::stream("/Filter /FlateDecode", b"broken"),
+    ]);
+    let page = Page::new(
+        1,
+        vec![indirect_ref(1)],
+        vec![],
+        Some(test_util::parse("2 0 R")),
+        Some(test_util::parse("<< /XObject << /Im1 1 0 R >> >>")),
+    );
+    let option = image_localmod::ImageOption {
+        extract_inline_images: true,
+        ..Default::default()
+    };
+
+    assert!(page.extract_images(&mut file, &xref, &option).is_err());
+}
+
+#[test]
+fn font_infos_with_inline_font_dict() {
+    let (mut file, xref) =
+        test_util::write_pdf(&[b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec()]);
+    let page = Page::new(
+        1,
+        vec![],
+        vec![
+            test_util::parse("1 0 R"),
+            test_util::parse("<< /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+Arial >>"),
+        ],
+        None,
+        None,
+    );
+
+    let infos = page.font_infos(&mut file, &xref).unwrap();
+
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].object_ref, Some((1, 0)));
+    assert_eq!(infos[0].name.as_deref(), Some("Helvetica"));
+    // 直接書かれたフォント辞書には参照が無い
+    assert_eq!(infos[1].object_ref, None);
+    assert_eq!(infos[1].name.as_deref(), Some("ABCDEF+Arial"));
+    assert!(infos[1].is_subset);
+}
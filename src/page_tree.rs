@@ -3,6 +3,10 @@ use std::fs::File;
 use crate::cross_reference::XRef;
 use crate::object;
 use crate::page::Page;
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
 
 #[derive(Debug)]
 pub enum Error {
@@ -138,61 +142,227 @@ impl Pages {
         node_dict: &object::PdfDict,
         page_number: usize,
     ) -> Result<Page, Error> {
-        let external_objects = Self::extract_external_objects(file, xref, node_dict)?;
-        let may_thumbnail_ref = Self::extract_thumbnail_ref(node_dict)?;
-
-        Ok(Page::new(page_number, may_thumbnail_ref, external_objects))
+        let (external_objects, fonts) = Self::extract_external_objects(file, xref, node_dict)?;
+
+        let contents = node_dict.get("Contents").cloned();
+        let resources = node_dict.get("Resources").cloned();
+
+        Ok(Page::new(
+            page_number,
+            external_objects,
+            fonts,
+            contents,
+            resources,
+        ))
     }
 
     fn extract_external_objects(
         file: &mut File,
         xref: &XRef,
         node_dict: &object::PdfDict,
-    ) -> Result<Vec<object::PdfIndirectRef>, Error> {
-        let mut external_objects = Vec::<object::PdfIndirectRef>::new();
+    ) -> Result<(Vec<object::PdfIndirectRef>, Vec<Object>), Error> {
+        let mut images = Vec::<object::PdfIndirectRef>::new();
+        let mut fonts = Vec::<Object>::new();
+        let mut visited_forms = Vec::<object::PdfIndirectRef>::new();
 
-        // XObjectはResource Dictionaryの更に配下にある
         if let Some(resource_obj) = node_dict.get("Resources") {
-            let resource_dict = object::PdfDict::ensure_with_key(resource_obj, vec![])?;
-
-            if let Some(xobj_obj) = resource_dict.get("XObject") {
-                if let Ok(indirect_ref) = object::PdfIndirectRef::ensure(xobj_obj) {
-                    let xobj_obj = indirect_ref.get_indirect_obj(file, xref)?;
-                    let xobj_obj = object::PdfIndirectObj::ensure(&xobj_obj)?;
-                    let xobj_obj = xobj_obj.get_object();
-
-                    let xobj_dict = object::PdfDict::ensure_with_key(&xobj_obj, vec![])?;
-
-                    xobj_dict
-                        .iter()
-                        .filter_map(|kv| object::PdfIndirectRef::ensure(kv.1).ok())
-                        .for_each(|indirect_ref| external_objects.push(indirect_ref.clone()));
-                } else {
-                    let xobj_dict = object::PdfDict::ensure_with_key(xobj_obj, vec![])?;
-
-                    xobj_dict
-                        .iter()
-                        .filter_map(|kv| object::PdfIndirectRef::ensure(kv.1).ok())
-                        .for_each(|indirect_ref| external_objects.push(indirect_ref.clone()));
+            Self::collect_in_resources(
+                file,
+                xref,
+                resource_obj,
+                &mut images,
+                &mut fonts,
+                &mut visited_forms,
+            )?;
+        }
+
+        // 注釈の外観ストリームもFormと同じく/Resourcesを持つ
+        // 外観の中の画像はページに描かれる画像としては数えない
+        // 注釈はページの本文ではないので，壊れた/Annotsや/APは読み飛ばす
+        let annots_obj = match node_dict.get("Annots") {
+            Some(annots_obj) => object::resolve(annots_obj, file, xref).ok(),
+            None => None,
+        };
+        if let Some(annots) = annots_obj
+            .as_ref()
+            .and_then(|annots_obj| object::PdfArray::ensure(annots_obj).ok())
+        {
+            let mut appearance_images = Vec::<object::PdfIndirectRef>::new();
+
+            for annot_obj in annots {
+                let annot_obj = match object::resolve(annot_obj, file, xref) {
+                    Ok(annot_obj) => annot_obj,
+                    Err(_) => continue,
+                };
+                let appearance_obj = match object::PdfDict::ensure_with_key(&annot_obj, vec![])
+                    .ok()
+                    .and_then(|annot_dict| annot_dict.get("AP"))
+                {
+                    Some(appearance_obj) => match object::resolve(appearance_obj, file, xref) {
+                        Ok(appearance_obj) => appearance_obj,
+                        Err(_) => continue,
+                    },
+                    None => continue,
                 };
+                let appearance_dict =
+                    match object::PdfDict::ensure_with_key(&appearance_obj, vec![]) {
+                        Ok(appearance_dict) => appearance_dict,
+                        Err(_) => continue,
+                    };
+
+                for key in ["N", "R", "D"] {
+                    if let Some(obj) = appearance_dict.get(key) {
+                        // 壊れた外観でも，それまでに見つけたフォントは残す
+                        let _ = Self::collect_in_appearance(
+                            file,
+                            xref,
+                            obj,
+                            &mut appearance_images,
+                            &mut fonts,
+                            &mut visited_forms,
+                        );
+                    }
+                }
             }
         }
 
-        Ok(external_objects)
+        Ok((images, fonts))
     }
 
-    fn extract_thumbnail_ref(
-        node_dict: &object::PdfDict,
-    ) -> Result<Option<object::PdfIndirectRef>, Error> {
-        let may_thumbnail_ref = match node_dict.get("Thumb") {
-            Some(thumbnail_ref) => Some(object::PdfIndirectRef::ensure(thumbnail_ref)?.clone()),
-            _ => None,
+    // 外観は1つのストリームか，状態の名前からストリームへの辞書
+    fn collect_in_appearance(
+        file: &mut File,
+        xref: &XRef,
+        appearance_obj: &Object,
+        images: &mut Vec<object::PdfIndirectRef>,
+        fonts: &mut Vec<Object>,
+        visited_forms: &mut Vec<object::PdfIndirectRef>,
+    ) -> Result<(), Error> {
+        if let Ok(appearance_ref) = object::PdfIndirectRef::ensure(appearance_obj) {
+            if visited_forms.contains(appearance_ref) {
+                return Ok(());
+            }
+            visited_forms.push(appearance_ref.clone());
+        }
+
+        match object::resolve(appearance_obj, file, xref)? {
+            Object::StreamObj(stream) => {
+                if let Some(resource_obj) = stream.dict.get("Resources") {
+                    Self::collect_in_resources(
+                        file,
+                        xref,
+                        resource_obj,
+                        images,
+                        fonts,
+                        visited_forms,
+                    )?;
+                }
+            }
+            Object::Dict(states) => {
+                let mut entries: Vec<(&String, &Object)> = states.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+
+                for (_, obj) in entries {
+                    if let Object::IndirectRef(_) = obj {
+                        Self::collect_in_appearance(file, xref, obj, images, fonts, visited_forms)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    // XObjectとフォントはResource Dictionaryの更に配下にある
+    // Form XObjectは自身の/Resourcesを持つので，その配下の画像とフォントも再帰的に集める
+    // 複数の場所から参照される画像，フォントやFormは一度だけ数える
+    fn collect_in_resources(
+        file: &mut File,
+        xref: &XRef,
+        resource_obj: &Object,
+        images: &mut Vec<object::PdfIndirectRef>,
+        fonts: &mut Vec<Object>,
+        visited_forms: &mut Vec<object::PdfIndirectRef>,
+    ) -> Result<(), Error> {
+        let resource_obj = object::resolve(resource_obj, file, xref)?;
+        let resource_dict = object::PdfDict::ensure_with_key(&resource_obj, vec![])?;
+
+        // フォントは参照か，直接書かれたフォント辞書
+        // /Fontが壊れていてもページは読めるように，フォントを数えずに進む
+        let font_obj = match resource_dict.get("Font") {
+            Some(font_obj) => object::resolve(font_obj, file, xref).ok(),
+            None => None,
+        };
+        if let Some(font_dict) = font_obj
+            .as_ref()
+            .and_then(|font_obj| object::PdfDict::ensure_with_key(font_obj, vec![]).ok())
+        {
+            let mut entries: Vec<(&String, &Object)> = font_dict.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (_, obj) in entries {
+                if matches!(obj, Object::IndirectRef(_) | Object::Dict(_)) && !fonts.contains(obj) {
+                    fonts.push(obj.clone());
+                }
+            }
+        }
+
+        let xobj_obj = match resource_dict.get("XObject") {
+            Some(xobj_obj) => object::resolve(xobj_obj, file, xref)?,
+            None => return Ok(()),
         };
+        let xobj_dict = object::PdfDict::ensure_with_key(&xobj_obj, vec![])?;
+
+        // 辞書の順序は不定なので名前順に並べる
+        let mut entries: Vec<(&String, &Object)> = xobj_dict.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (_, obj) in entries {
+            let xobj_ref = match object::PdfIndirectRef::ensure(obj) {
+                Ok(xobj_ref) => xobj_ref,
+                Err(_) => continue,
+            };
+            if images.contains(xobj_ref) || visited_forms.contains(xobj_ref) {
+                continue;
+            }
+
+            let xobj = xobj_ref.get_indirect_obj(file, xref)?;
+            let xobj = match object::PdfStreamObj::ensure_stream(&xobj) {
+                Ok(xobj) => xobj,
+                Err(_) => continue,
+            };
+
+            let subtype = match xobj.dict.get("Subtype") {
+                Some(subtype) => object::PdfName::ensure(subtype)?,
+                None => continue,
+            };
+
+            match subtype.as_str() {
+                "Image" => images.push(xobj_ref.clone()),
+                "Form" => {
+                    visited_forms.push(xobj_ref.clone());
+
+                    if let Some(form_resource_obj) = xobj.dict.get("Resources") {
+                        Self::collect_in_resources(
+                            file,
+                            xref,
+                            form_resource_obj,
+                            images,
+                            fonts,
+                            visited_forms,
+                        )?;
+                    }
+                }
+                // PostScript XObjectなどは無視する
+                _ => {}
+            }
+        }
 
-        Ok(may_thumbnail_ref)
+        Ok(())
     }
 
-    fn get_page_number(&self) -> usize {
+    pub fn get_page_number(&self) -> usize {
         self.pages.len()
     }
 }
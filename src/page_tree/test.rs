@@ -0,0 +1,158 @@
+use super::*;
+use crate::test_util;
+
+fn unpack(refs: &[object::PdfIndirectRef]) -> Vec<(usize, usize)> {
+    refs.iter().map(|r| r.unpack()).collect()
+}
+
+// 直接書かれたフォント辞書はNone
+fn unpack_fonts(fonts: &[Object]) -> Vec<Option<(usize, usize)>> {
+    fonts
+        .iter()
+        .map(|font| {
+            object::PdfIndirectRef::ensure(font)
+                .ok()
+                .map(|r| r.unpack())
+        })
+        .collect()
+}
+
+fn image() -> Vec<u8> {
+    test_util::stream(
+        "/Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray /BitsPerComponent 8",
+        &[0],
+    )
+}
+
+fn form(resources: &str) -> Vec<u8> {
+    test_util::stream(
+        &format!(
+            "/Type /XObject /Subtype /Form /BBox [0 0 1 1] /Resources {}",
+            resources
+        ),
+        b"",
+    )
+}
+
+#[test]
+fn collect_images_in_nested_forms() {
+    // ページ -> Fm1 -> Fm2 -> Im2 と，Fm1から直接参照されるIm1
+    let (mut file, xref) = test_util::write_pdf(&[
+        form("<< /XObject << /Fm2 2 0 R /Im1 3 0 R >> >>"),
+        form("<< /XObject << /Im2 4 0 R >> >>"),
+        image(),
+        image(),
+    ]);
+    let page = test_util::parse("<< /Type /Page /Resources << /XObject << /Fm1 1 0 R >> >> >>");
+    let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+    let (images, _) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+
+    assert_eq!(unpack(&images), [(4, 0), (3, 0)]);
+}
+
+#[test]
+fn collect_images_in_cyclic_forms() {
+    // Fm1とFm2が互いに参照し合っていても止まり，同じ画像は一度だけ数える
+    let (mut file, xref) = test_util::write_pdf(&[
+        form("<< /XObject << /Fm2 2 0 R /Im1 3 0 R >> >>"),
+        form("<< /XObject << /Fm1 1 0 R /Im1 3 0 R >> >>"),
+        image(),
+    ]);
+    let page = test_util::parse(
+        "<< /Type /Page /Resources << /XObject << /Fm1 1 0 R /Fm2 2 0 R /Im1 3 0 R >> >> >>",
+    );
+    let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+    let (images, _) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+
+    assert_eq!(unpack(&images), [(3, 0)]);
+}
+
+#[test]
+fn collect_fonts_with_broken_font_resources() {
+    // /Fontが辞書でなかったり，存在しないオブジェクトを指していてもページは読める
+    let (mut file, xref) = test_util::write_pdf(&[
+        form("<< /Font << /F2 4 0 R /F1 3 0 R >> /XObject << /Im1 2 0 R >> >>"),
+        image(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_vec(),
+    ]);
+
+    for font in ["/Font 3", "/Font 9 0 R"] {
+        let page = test_util::parse(&format!(
+            "<< /Type /Page /Resources << {} /XObject << /Fm1 1 0 R >> >> >>",
+            font
+        ));
+        let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+        let (images, fonts) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+
+        assert_eq!(unpack(&images), [(2, 0)]);
+        // Formの中のフォントは名前順に数える
+        assert_eq!(unpack_fonts(&fonts), [Some((3, 0)), Some((4, 0))]);
+    }
+}
+
+#[test]
+fn collect_fonts_in_annotation_appearances() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Times-Roman >>".to_vec(),
+        image(),
+        b"<< /Type /Annot /Subtype /Widget /AP << /N 6 0 R >> >>".to_vec(),
+        form("<< /Font << /F1 1 0 R >> /XObject << /Im1 4 0 R >> >>"),
+        form("<< /Font << /F2 2 0 R >> /XObject 42 >>"),
+        form("<< /Font << /F3 3 0 R >> >>"),
+    ]);
+
+    // 存在しない注釈，壊れた/AP，状態ごとの外観，/XObjectが壊れた外観が混ざっている
+    let page = test_util::parse(
+        "<< /Type /Page /Annots [5 0 R 99 0 R << /AP 99 0 R >> << /AP 42 >> \
+         << /AP << /D << /On 7 0 R /Off 8 0 R >> >> >>] >>",
+    );
+    let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+    let (images, fonts) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+
+    // 外観の中の画像は数えない
+    assert!(images.is_empty());
+    assert_eq!(
+        unpack_fonts(&fonts),
+        [Some((1, 0)), Some((3, 0)), Some((2, 0))]
+    );
+
+    // /Annotsが配列でなければ注釈を無視する
+    let page = test_util::parse("<< /Type /Page /Annots 42 >>");
+    let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+    let (images, fonts) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+    assert!(images.is_empty() && fonts.is_empty());
+}
+
+#[test]
+fn collect_inline_font_dicts() {
+    // /Fontに直接書かれたフォント辞書も数える
+    // Fm1とFm2は同じ/Resourcesを持つので，その中の辞書は一度だけ数える
+    let (mut file, xref) = test_util::write_pdf(&[
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+        b"<< /Font << /F1 1 0 R /F2 << /Type /Font /Subtype /Type1 /BaseFont /Courier >> \
+          /F3 42 >> >>"
+            .to_vec(),
+        form("2 0 R"),
+        form("2 0 R"),
+    ]);
+    let page = test_util::parse(
+        "<< /Type /Page /Resources << /Font << /F0 << /Type /Font /Subtype /Type1 \
+         /BaseFont /Symbol >> >> /XObject << /Fm1 3 0 R /Fm2 4 0 R >> >> >>",
+    );
+    let page = object::PdfDict::ensure_with_key(&page, vec![]).unwrap();
+
+    let (_, fonts) = Pages::extract_external_objects(&mut file, &xref, page).unwrap();
+
+    assert_eq!(unpack_fonts(&fonts), [None, Some((1, 0)), None]);
+    let font_dict = object::PdfDict::ensure_with_key(&fonts[2], vec![]).unwrap();
+    let base_font = object::PdfName::ensure(font_dict.get("BaseFont").unwrap()).unwrap();
+    assert_eq!(base_font.as_str(), "Courier");
+}
@@ -13,7 +13,7 @@ use error::{Error, ErrorKind};
 #[cfg(test)]
 pub mod test;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Object {
     Boolean(PdfBoolean),
     Integer(PdfInteger),
@@ -58,7 +58,23 @@ impl Parser {
             panic!("buffer is empty");
         };
 
-        let mut lexer = lexer::Lexer::new(buffer, buffer_start_offset);
+        Parser::from_lexer(lexer::Lexer::new(buffer, buffer_start_offset))
+    }
+
+    // コンテンツストリームをオペランドと演算子の列として読む
+    pub fn new_content_stream(buffer: &[u8], buffer_start_offset: u64) -> Result<Parser, Error> {
+        if buffer.is_empty() {
+            panic!("buffer is empty");
+        };
+
+        Parser::from_lexer(lexer::Lexer::new_content_stream(
+            buffer,
+            buffer_start_offset,
+        ))
+    }
+
+    fn from_lexer(mut lexer: lexer::Lexer) -> Result<Parser, Error> {
+        let buffer_start_offset = lexer.buffer_start_offset();
 
         if let Err(e) = lexer.tokenize() {
             return Err(Error::new(ErrorKind::Lexer(e), buffer_start_offset));
@@ -84,6 +100,38 @@ impl Parser {
         self.parse_object()
     }
 
+    pub fn has_next(&self) -> bool {
+        self.current_token().is_some()
+    }
+
+    // 次のトークンが演算子なら読み進めてその名前を返す
+    pub fn next_operator(&mut self) -> Option<String> {
+        let operator = match self.current_token() {
+            Some(Token {
+                token_content: TokenContent::Operator(operator),
+                byte_offset: _,
+            }) => operator.clone(),
+            _ => return None,
+        };
+
+        self.next();
+        Some(operator)
+    }
+
+    // 次のトークンがインライン画像のデータなら読み進めてそのバイト列を返す
+    pub fn next_inline_image_data(&mut self) -> Option<Vec<u8>> {
+        let data = match self.current_token() {
+            Some(Token {
+                token_content: TokenContent::InlineImageData(data),
+                byte_offset: _,
+            }) => data.clone(),
+            _ => return None,
+        };
+
+        self.next();
+        Some(data)
+    }
+
     fn next(&mut self) -> Option<&Token> {
         let i = self.token_i;
 
@@ -178,11 +226,9 @@ impl Parser {
                 byte_offset: _,
             }) = self.next()
             {
-                let stream = match PdfStreamObj::new(obj, *offset) {
-                    Ok(obj) => return Ok(Object::StreamObj(obj)),
-                    Err(_) => {
-                        return Err(Error::new(ErrorKind::InvalidStreamObj, self.byte_offset))
-                    }
+                return match PdfStreamObj::new(obj, *offset) {
+                    Ok(obj) => Ok(Object::StreamObj(obj)),
+                    Err(_) => Err(Error::new(ErrorKind::InvalidStreamObj, self.byte_offset)),
                 };
             } else {
                 return Ok(Object::IndirectObj(PdfIndirectObj::new(
@@ -208,11 +254,6 @@ impl Parser {
 
             let token = may_token.unwrap();
 
-            if let TokenContent::Eol = token.content() {
-                self.next();
-                continue;
-            }
-
             if let TokenContent::ArrayEnd = token.content() {
                 self.next();
                 return Ok(content);
@@ -238,11 +279,6 @@ impl Parser {
 
             let token = may_token.unwrap();
 
-            if let TokenContent::Eol = token.content() {
-                self.next();
-                continue;
-            }
-
             if is_prev_name {
                 content.insert(key.clone(), self.parse_object()?);
                 is_prev_name = false;
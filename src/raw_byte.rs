@@ -95,22 +95,6 @@ pub fn cut_after_eol(buffer: &[u8]) -> Option<&[u8]> {
     }
 }
 
-pub fn extract_from_eol(buffer: &[u8]) -> Option<&[u8]> {
-    if let Some((eol_i, _)) = first_match_eol(buffer) {
-        Some(&buffer[eol_i..])
-    } else {
-        None
-    }
-}
-
-pub fn extract_from<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
-    if let Some(match_i) = first_match_index(buffer, target) {
-        Some(&buffer[match_i..])
-    } else {
-        None
-    }
-}
-
 pub fn cut_from<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
     if let Some(match_i) = first_match_index(buffer, target) {
         Some(&buffer[..match_i])
@@ -127,22 +111,6 @@ pub fn extract_after<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
     }
 }
 
-pub fn cut_after<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
-    if let Some(match_i) = first_match_index(buffer, target) {
-        Some(&buffer[..(match_i + target.len())])
-    } else {
-        None
-    }
-}
-
-pub fn extract_tail_from<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
-    if let Some(match_i) = last_match_index(buffer, target) {
-        Some(&buffer[match_i..])
-    } else {
-        None
-    }
-}
-
 pub fn cut_tail_from<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
     if let Some(match_i) = last_match_index(buffer, target) {
         Some(&buffer[..match_i])
@@ -158,11 +126,3 @@ pub fn extract_tail_after<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8
         None
     }
 }
-
-pub fn cut_tail_after<'a>(buffer: &'a [u8], target: &[u8]) -> Option<&'a [u8]> {
-    if let Some(match_i) = last_match_index(buffer, target) {
-        Some(&buffer[..(match_i + target.len())])
-    } else {
-        None
-    }
-}
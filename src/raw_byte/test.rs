@@ -47,7 +47,7 @@ fn first_match_index_3() {
     let buffer = "hogehoge".as_bytes();
     let target = "too long target ".as_bytes();
 
-    if let Some(_) = first_match_index(buffer, target) {
+    if first_match_index(buffer, target).is_some() {
         panic!();
     }
 }
@@ -75,29 +75,11 @@ fn last_match_index_3() {
     let buffer = "hogehoge".as_bytes();
     let target = "too long target ".as_bytes();
 
-    if let Some(_) = last_match_index(buffer, target) {
+    if last_match_index(buffer, target).is_some() {
         panic!();
     }
 }
 
-#[test]
-fn extract_from_1() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "target".as_bytes();
-
-    let i = extract_from(buffer, target).unwrap();
-    assert_eq!(i, "target jjjj\n\rhoge".as_bytes());
-}
-
-#[test]
-fn extract_from_2() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "".as_bytes();
-
-    let i = extract_from(buffer, target).unwrap();
-    assert_eq!(i, "hogehoge target jjjj\n\rhoge".as_bytes());
-}
-
 #[test]
 fn cut_from_1() {
     let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
@@ -134,42 +116,6 @@ fn extract_after_2() {
     assert_eq!(i, "hogehoge target jjjj\n\rhoge".as_bytes());
 }
 
-#[test]
-fn cut_after_1() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "target".as_bytes();
-
-    let i = cut_after(buffer, target).unwrap();
-    assert_eq!(i, "hogehoge target".as_bytes());
-}
-
-#[test]
-fn cut_after_2() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "".as_bytes();
-
-    let i = cut_after(buffer, target).unwrap();
-    assert_eq!(i, "".as_bytes());
-}
-
-#[test]
-fn extract_tail_from_1() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "target".as_bytes();
-
-    let i = extract_tail_from(buffer, target).unwrap();
-    assert_eq!(i, "target jjjj\n\rhoge".as_bytes());
-}
-
-#[test]
-fn extract_tail_from_2() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "".as_bytes();
-
-    let i = extract_tail_from(buffer, target).unwrap();
-    assert_eq!(i, "".as_bytes());
-}
-
 #[test]
 fn cut_tail_from_1() {
     let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
@@ -205,21 +151,3 @@ fn extract_tail_after_2() {
     let i = extract_tail_after(buffer, target).unwrap();
     assert_eq!(i, "".as_bytes());
 }
-
-#[test]
-fn cut_tail_after_1() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "target".as_bytes();
-
-    let i = cut_tail_after(buffer, target).unwrap();
-    assert_eq!(i, "hogehoge target".as_bytes());
-}
-
-#[test]
-fn cut_tail_after_2() {
-    let buffer = "hogehoge target jjjj\n\rhoge".as_bytes();
-    let target = "".as_bytes();
-
-    let i = cut_tail_after(buffer, target).unwrap();
-    assert_eq!(i, "hogehoge target jjjj\n\rhoge".as_bytes());
-}
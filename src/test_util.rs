@@ -0,0 +1,96 @@
+// テストで使うPDFファイルを組み立てる
+use std::fs::File;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cross_reference::XRef;
+use crate::parser::{Object, Parser};
+
+// 一時ディレクトリに書き出したPDFファイル
+// 読み込み用に開いたFileとして使え，破棄するときにファイルを削除する
+pub struct TempPdf {
+    file: Option<File>,
+    path: PathBuf,
+}
+
+impl Deref for TempPdf {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        self.file.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for TempPdf {
+    fn deref_mut(&mut self) -> &mut File {
+        self.file.as_mut().unwrap()
+    }
+}
+
+impl Drop for TempPdf {
+    fn drop(&mut self) {
+        // 開いたままでは削除できない環境もあるので先に閉じる
+        self.file.take();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// 与えた中身を1番から順に間接オブジェクトとして並べたPDFファイルを一時ディレクトリに書き出し，
+// 読み込み用に開いたファイルと相互参照表を返す
+pub fn write_pdf(objects: &[Vec<u8>]) -> (TempPdf, XRef) {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    let mut offsets = vec![];
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend(body);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref_start_offset = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+    pdf.extend(b"0000000000 65535 f \n");
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_start_offset
+        )
+        .as_bytes(),
+    );
+
+    let path = std::env::temp_dir().join(format!(
+        "pdf_parser-test-{}-{}.pdf",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    File::create(&path).unwrap().write_all(&pdf).unwrap();
+
+    let mut pdf = TempPdf {
+        file: Some(File::open(&path).unwrap()),
+        path,
+    };
+    let xref = XRef::new(&mut pdf, xref_start_offset as u64).unwrap();
+    (pdf, xref)
+}
+
+// 辞書と中身からストリームオブジェクトを作る．/Lengthは自動で付ける
+pub fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+    body.extend(data);
+    body.extend(b"\nendstream");
+    body
+}
+
+// 直接オブジェクトを解析する
+pub fn parse(buffer: &str) -> Object {
+    let buffer = format!("{} ", buffer);
+    Parser::new(buffer.as_bytes(), 0).unwrap().parse().unwrap()
+}
@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::rc::Rc;
+
+use crate::content;
+use crate::cross_reference;
+use crate::font;
+use crate::graphics::{self, Color, GraphicsState, Matrix};
+use crate::object::{self, PdfObject};
+use crate::parser::Object;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug)]
+pub enum Error {
+    Object(object::Error),
+    Graphics(graphics::Error),
+    Font(font::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::Object(e) => write!(f, "object: {}", e),
+            Error::Graphics(e) => write!(f, "graphics: {}", e),
+            Error::Font(e) => write!(f, "font: {}", e),
+        }
+    }
+}
+impl From<object::Error> for Error {
+    fn from(e: object::Error) -> Self {
+        Self::Object(e)
+    }
+}
+impl From<graphics::Error> for Error {
+    fn from(e: graphics::Error) -> Self {
+        Self::Graphics(e)
+    }
+}
+impl From<font::Error> for Error {
+    fn from(e: font::Error) -> Self {
+        Self::Font(e)
+    }
+}
+
+// 文字の間隔がフォントの大きさのこの割合より開いていたら空白を入れる
+const SPACE_THRESHOLD: f64 = 0.15;
+// ベースラインがフォントの大きさのこの割合よりずれていたら改行を入れる
+const NEWLINE_THRESHOLD: f64 = 0.5;
+
+// ページ上に描かれた1文字
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChar {
+    pub text: String,
+    // ベースライン上の始点と終点（ページの座標）
+    pub x: f64,
+    pub y: f64,
+    pub end_x: f64,
+    pub end_y: f64,
+    // ページの座標での文字の大きさ
+    pub font_size: f64,
+    // 縦書きなら始点から終点へ下に進む
+    pub is_vertical: bool,
+    // フォントの/BaseFont
+    pub font_name: String,
+    pub fill_color: Color,
+    // Tr
+    pub render_mode: isize,
+    // 字幅とフォントの上端，下端から求めたページの座標での [x_min y_min x_max y_max]
+    pub bbox: [f64; 4],
+}
+
+// 空白や字間で区切られた語
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextWord {
+    pub text: String,
+    pub bbox: [f64; 4],
+    // 語の最初の文字のもの
+    pub font_name: String,
+    pub font_size: f64,
+    pub fill_color: Color,
+    pub render_mode: isize,
+    pub chars: Vec<TextChar>,
+}
+
+// ベースラインを同じくする語の並び
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    // 語を空白でつないだもの
+    pub text: String,
+    pub bbox: [f64; 4],
+    pub words: Vec<TextWord>,
+}
+
+// 文字を描く操作を解釈し，ページ上の文字を集める
+pub struct TextExtractor {
+    // テキスト行列 Tm とテキスト行列の行頭 Tlm
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    // リソースの/Fontの名前から引いたフォント
+    // Formごとにリソースが異なるので，リソース辞書の位置と名前の組で区別する
+    fonts_by_name: HashMap<(u64, String), Rc<font::Font>>,
+    // 間接参照されたフォントは一度だけ読む
+    fonts_by_ref: HashMap<(usize, usize), Rc<font::Font>>,
+    // フォントが無いときや読めないときに使う
+    default_font: Rc<font::Font>,
+    chars: Vec<TextChar>,
+}
+
+impl Default for TextExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextExtractor {
+    pub fn new() -> TextExtractor {
+        TextExtractor {
+            text_matrix: Matrix::identity(),
+            line_matrix: Matrix::identity(),
+            fonts_by_name: HashMap::new(),
+            fonts_by_ref: HashMap::new(),
+            default_font: Rc::new(font::Font::default()),
+            chars: vec![],
+        }
+    }
+
+    pub fn into_chars(self) -> Vec<TextChar> {
+        self.chars
+    }
+
+    // Tfで選ばれているフォントをリソースの/Fontから引く
+    fn get_font(
+        &mut self,
+        state: &GraphicsState,
+        resources: Option<&object::PdfDict>,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<Rc<font::Font>, Error> {
+        let (name, resources) = match (&state.text.font_name, resources) {
+            (Some(name), Some(resources)) => (name, resources),
+            _ => return Ok(Rc::clone(&self.default_font)),
+        };
+
+        let key = (resources.byte_offset(), name.clone());
+        if let Some(font) = self.fonts_by_name.get(&key) {
+            return Ok(Rc::clone(font));
+        }
+
+        let fonts = graphics::get_sub_dict(resources, "Font", file, xref)?;
+        let font = match fonts.as_ref().and_then(|fonts| fonts.get(name.as_str())) {
+            Some(Object::IndirectRef(font_ref)) => {
+                match self.fonts_by_ref.get(&font_ref.unpack()) {
+                    Some(font) => Rc::clone(font),
+                    None => {
+                        let font =
+                            self.load_font(&Object::IndirectRef(font_ref.clone()), file, xref);
+                        self.fonts_by_ref
+                            .insert(font_ref.unpack(), Rc::clone(&font));
+                        font
+                    }
+                }
+            }
+            Some(font_obj) => self.load_font(font_obj, file, xref),
+            None => Rc::clone(&self.default_font),
+        };
+        self.fonts_by_name.insert(key, Rc::clone(&font));
+
+        Ok(font)
+    }
+
+    // 読めないフォントで描かれた文字も位置は分かるので，既定のフォントで代用する
+    fn load_font(
+        &self,
+        font_obj: &Object,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Rc<font::Font> {
+        let font = object::resolve(font_obj, file, xref)
+            .map_err(font::Error::from)
+            .and_then(|font_obj| {
+                let font_dict = object::PdfDict::ensure_with_key(&font_obj, vec![])?;
+                font::Font::new(font_dict, file, xref)
+            });
+
+        match font {
+            Ok(font) => Rc::new(font),
+            Err(_) => Rc::clone(&self.default_font),
+        }
+    }
+
+    // 文字の位置を動かす操作と文字を描く操作を処理する
+    pub fn process(
+        &mut self,
+        operation: &content::Operation,
+        state: &mut GraphicsState,
+        font: &font::Font,
+    ) {
+        let operands = &operation.operands;
+        let number = |i: usize| {
+            operands
+                .get(i)
+                .and_then(|obj| object::PdfReal::ensure_number(obj).ok())
+        };
+
+        match operation.operator.as_str() {
+            "BT" => {
+                self.text_matrix = Matrix::identity();
+                self.line_matrix = Matrix::identity();
+            }
+            "Td" => {
+                if let (Some(tx), Some(ty)) = (number(0), number(1)) {
+                    self.move_line(tx, ty);
+                }
+            }
+            "TD" => {
+                if let (Some(tx), Some(ty)) = (number(0), number(1)) {
+                    state.text.leading = -ty;
+                    self.move_line(tx, ty);
+                }
+            }
+            "Tm" => {
+                if let Some(matrix) = Matrix::from_operands(operands) {
+                    self.text_matrix = matrix;
+                    self.line_matrix = matrix;
+                }
+            }
+            "T*" => self.move_line(0.0, -state.text.leading),
+            "Tj" => {
+                if let Some(Object::String(string)) = operands.first() {
+                    self.show_string(string.as_bytes(), state, font);
+                }
+            }
+            "'" => {
+                self.move_line(0.0, -state.text.leading);
+                if let Some(Object::String(string)) = operands.first() {
+                    self.show_string(string.as_bytes(), state, font);
+                }
+            }
+            "\"" => {
+                if let (Some(word_spacing), Some(char_spacing)) = (number(0), number(1)) {
+                    state.text.word_spacing = word_spacing;
+                    state.text.char_spacing = char_spacing;
+                }
+                self.move_line(0.0, -state.text.leading);
+                if let Some(Object::String(string)) = operands.get(2) {
+                    self.show_string(string.as_bytes(), state, font);
+                }
+            }
+            "TJ" => {
+                if let Some(Object::Array(array)) = operands.first() {
+                    for obj in array {
+                        match obj {
+                            Object::String(string) => {
+                                self.show_string(string.as_bytes(), state, font)
+                            }
+                            // 数値は1000分の1単位で左（縦書きでは下）へずらす量
+                            obj => {
+                                if let Ok(adjustment) = object::PdfReal::ensure_number(obj) {
+                                    let text = &state.text;
+                                    if font.is_vertical() {
+                                        self.advance_vertically(
+                                            -adjustment / 1000.0 * text.font_size,
+                                        );
+                                    } else {
+                                        self.advance(
+                                            -adjustment / 1000.0
+                                                * text.font_size
+                                                * text.horizontal_scaling,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn move_line(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix([1.0, 0.0, 0.0, 1.0, tx, ty]).multiply(&self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn advance(&mut self, tx: f64) {
+        self.text_matrix = Matrix([1.0, 0.0, 0.0, 1.0, tx, 0.0]).multiply(&self.text_matrix);
+    }
+
+    fn advance_vertically(&mut self, ty: f64) {
+        self.text_matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, ty]).multiply(&self.text_matrix);
+    }
+
+    fn show_string(&mut self, bytes: &[u8], state: &GraphicsState, font: &font::Font) {
+        let text = &state.text;
+
+        for glyph in font.decode(bytes) {
+            // テキスト空間からページの座標への変換
+            let render_matrix = Matrix([
+                text.font_size * text.horizontal_scaling,
+                0.0,
+                0.0,
+                text.font_size,
+                0.0,
+                text.rise,
+            ])
+            .multiply(&self.text_matrix)
+            .multiply(&state.ctm);
+
+            // 縦書きでは現在の位置が文字の上端の中央で，そこから下へ進む
+            let (x, y) = render_matrix.transform_point(0.0, 0.0);
+            let (end_x, end_y) = match &glyph.vertical {
+                Some(vertical) => render_matrix.transform_point(0.0, vertical.advance),
+                None => render_matrix.transform_point(glyph.width, 0.0),
+            };
+            let (top_x, top_y) = render_matrix.transform_point(0.0, 1.0);
+
+            // 字の箱（テキスト空間）．縦書きでは現在の位置から原点までずらす
+            let (ascent, descent) = font.extent();
+            let (left, bottom, right, top) = match &glyph.vertical {
+                Some(vertical) => {
+                    let origin_x = vertical.origin_x.unwrap_or(glyph.width / 2.0);
+                    (
+                        -origin_x,
+                        descent - vertical.origin_y,
+                        glyph.width - origin_x,
+                        ascent - vertical.origin_y,
+                    )
+                }
+                None => (0.0, descent, glyph.width, ascent),
+            };
+            let bbox = graphics::bounding_box(
+                [(left, bottom), (right, bottom), (left, top), (right, top)]
+                    .map(|(x, y)| render_matrix.transform_point(x, y)),
+            );
+
+            self.chars.push(TextChar {
+                text: glyph.text,
+                x,
+                y,
+                end_x,
+                end_y,
+                font_size: (top_x - x).hypot(top_y - y),
+                is_vertical: glyph.vertical.is_some(),
+                font_name: String::from(font.base_font()),
+                fill_color: state.fill_color.clone(),
+                render_mode: text.render_mode,
+                bbox,
+            });
+
+            let mut spacing = text.char_spacing;
+            if glyph.is_space {
+                spacing += text.word_spacing;
+            }
+            match &glyph.vertical {
+                Some(vertical) => {
+                    self.advance_vertically(vertical.advance * text.font_size + spacing)
+                }
+                None => {
+                    self.advance((glyph.width * text.font_size + spacing) * text.horizontal_scaling)
+                }
+            }
+        }
+    }
+}
+
+impl graphics::OperationHandler for TextExtractor {
+    type Error = Error;
+
+    fn handle(
+        &mut self,
+        operation: &content::Operation,
+        state: &mut GraphicsState,
+        resources: Option<&object::PdfDict>,
+        file: &mut File,
+        xref: &cross_reference::XRef,
+    ) -> Result<(), Error> {
+        let font = match operation.operator.as_str() {
+            "Tj" | "TJ" | "'" | "\"" => self.get_font(state, resources, file, xref)?,
+            _ => Rc::clone(&self.default_font),
+        };
+
+        self.process(operation, state, &font);
+
+        Ok(())
+    }
+}
+
+// 縦書きの文字が続くところは，右の列から順に，列の中は上から順に並べ直す
+fn order_vertical_columns(chars: &[TextChar]) -> Vec<&TextChar> {
+    let mut ordered = vec![];
+
+    for run in chars.chunk_by(|a, b| a.is_vertical == b.is_vertical) {
+        if !run[0].is_vertical {
+            ordered.extend(run);
+            continue;
+        }
+
+        let mut sorted: Vec<&TextChar> = run.iter().collect();
+        sorted.sort_by(|a, b| b.x.total_cmp(&a.x));
+
+        // x座標が文字の大きさの半分以内の文字を同じ列とする
+        let mut columns: Vec<Vec<&TextChar>> = vec![];
+        for c in sorted {
+            match columns.last_mut() {
+                Some(column) if (column[0].x - c.x).abs() <= c.font_size * 0.5 => column.push(c),
+                _ => columns.push(vec![c]),
+            }
+        }
+
+        for mut column in columns {
+            column.sort_by(|a, b| b.y.total_cmp(&a.y));
+            ordered.extend(column);
+        }
+    }
+
+    ordered
+}
+
+// 隣り合う文字の間に補う区切り
+#[derive(Debug, PartialEq)]
+enum Separator {
+    None,
+    Space,
+    Newline,
+}
+
+// 前の文字の終点から見た次の文字の始点の位置で区切りを決める
+fn separator(prev: &TextChar, c: &TextChar) -> Separator {
+    // 前の文字のベースラインの向き
+    let (dx, dy) = (prev.end_x - prev.x, prev.end_y - prev.y);
+    let length = dx.hypot(dy);
+    let (ux, uy) = if length > 0.0 {
+        (dx / length, dy / length)
+    } else {
+        (1.0, 0.0)
+    };
+
+    let size = prev.font_size.max(c.font_size);
+    // ベースライン方向と垂直方向の距離
+    let (gx, gy) = (c.x - prev.end_x, c.y - prev.end_y);
+    let gap = gx * ux + gy * uy;
+    let offset = -gx * uy + gy * ux;
+
+    if offset.abs() > size * NEWLINE_THRESHOLD {
+        Separator::Newline
+    } else if gap > size * SPACE_THRESHOLD {
+        Separator::Space
+    } else {
+        Separator::None
+    }
+}
+
+// 描かれた順の文字を，位置を見て空白や改行を補いながらつなげる
+pub fn chars_to_text(chars: &[TextChar]) -> String {
+    let mut text = String::new();
+    let mut prev: Option<&TextChar> = None;
+
+    for c in order_vertical_columns(chars) {
+        if let Some(p) = prev {
+            match separator(p, c) {
+                Separator::Newline => text.push('\n'),
+                Separator::Space if !text.ends_with(' ') && !c.text.starts_with(' ') => {
+                    text.push(' ')
+                }
+                _ => {}
+            }
+        }
+
+        text.push_str(&c.text);
+        prev = Some(c);
+    }
+
+    text
+}
+
+impl TextWord {
+    fn new(chars: Vec<TextChar>) -> TextWord {
+        let first = &chars[0];
+
+        TextWord {
+            text: chars.iter().map(|c| c.text.as_str()).collect(),
+            bbox: union_bbox(chars.iter().map(|c| &c.bbox)),
+            font_name: first.font_name.clone(),
+            font_size: first.font_size,
+            fill_color: first.fill_color.clone(),
+            render_mode: first.render_mode,
+            chars,
+        }
+    }
+}
+
+impl TextLine {
+    fn new(words: Vec<TextWord>) -> TextLine {
+        TextLine {
+            text: words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<&str>>()
+                .join(" "),
+            bbox: union_bbox(words.iter().map(|word| &word.bbox)),
+            words,
+        }
+    }
+}
+
+fn union_bbox<'a>(bboxes: impl Iterator<Item = &'a [f64; 4]>) -> [f64; 4] {
+    graphics::bounding_box(bboxes.flat_map(|bbox| [(bbox[0], bbox[1]), (bbox[2], bbox[3])]))
+}
+
+// 文字を語と行にまとめる．空白の文字はどの語にも含めない
+pub fn group_lines(chars: &[TextChar]) -> Vec<TextLine> {
+    let mut lines = vec![];
+    let mut words = vec![];
+    let mut word: Vec<TextChar> = vec![];
+    let mut prev: Option<&TextChar> = None;
+
+    for c in order_vertical_columns(chars) {
+        let separator = match prev {
+            Some(p) => separator(p, c),
+            None => Separator::None,
+        };
+        prev = Some(c);
+
+        if separator != Separator::None && !word.is_empty() {
+            words.push(TextWord::new(std::mem::take(&mut word)));
+        }
+        if separator == Separator::Newline && !words.is_empty() {
+            lines.push(TextLine::new(std::mem::take(&mut words)));
+        }
+
+        if !c.text.is_empty() && c.text.trim().is_empty() {
+            if !word.is_empty() {
+                words.push(TextWord::new(std::mem::take(&mut word)));
+            }
+            continue;
+        }
+
+        word.push(c.clone());
+    }
+
+    if !word.is_empty() {
+        words.push(TextWord::new(word));
+    }
+    if !words.is_empty() {
+        lines.push(TextLine::new(words));
+    }
+
+    lines
+}
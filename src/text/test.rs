@@ -0,0 +1,225 @@
+use super::*;
+use crate::graphics::OperationHandler;
+use crate::test_util;
+
+fn operation(operator: &str, operands: Vec<Object>) -> content::Operation {
+    content::Operation {
+        operator: String::from(operator),
+        operands,
+    }
+}
+
+fn number(n: f64) -> Object {
+    Object::Real(object::PdfReal::new(n, 0))
+}
+
+fn string(s: &str) -> Object {
+    Object::String(object::PdfString::new(s.as_bytes().to_vec(), 0))
+}
+
+fn state(font_size: f64) -> GraphicsState {
+    let mut state = GraphicsState {
+        ctm: Matrix::identity(),
+        text: graphics::TextState::default(),
+        fill_color: graphics::Color::default(),
+    };
+    state.text.font_name = Some(String::from("F1"));
+    state.text.font_size = font_size;
+
+    state
+}
+
+fn extract(operations: Vec<content::Operation>, state: &mut GraphicsState) -> String {
+    let font = font::Font::default();
+    let mut extractor = TextExtractor::new();
+    for operation in &operations {
+        extractor.process(operation, state, &font);
+    }
+
+    chars_to_text(&extractor.into_chars())
+}
+
+#[test]
+fn show_string_positions() {
+    let font = font::Font::default();
+    let mut state = state(10.0);
+    let mut extractor = TextExtractor::new();
+
+    extractor.process(&operation("BT", vec![]), &mut state, &font);
+    extractor.process(
+        &operation("Td", vec![number(100.0), number(200.0)]),
+        &mut state,
+        &font,
+    );
+    extractor.process(&operation("Tj", vec![string("ab")]), &mut state, &font);
+
+    let chars = extractor.into_chars();
+    assert_eq!(chars.len(), 2);
+    // 既定の字幅は500なので，大きさ10なら5ずつ進む
+    assert_eq!((chars[0].x, chars[0].y), (100.0, 200.0));
+    assert_eq!((chars[1].x, chars[1].end_x), (105.0, 110.0));
+    assert_eq!(chars[1].font_size, 10.0);
+    // 既定のフォントの上端と下端は0.8と-0.2
+    assert_eq!(chars[0].bbox, [100.0, 198.0, 105.0, 208.0]);
+}
+
+#[test]
+fn kerning_inserts_space() {
+    let mut state = state(10.0);
+    let text = extract(
+        vec![
+            operation("BT", vec![]),
+            operation(
+                "TJ",
+                vec![Object::Array(object::PdfArray::new(
+                    vec![
+                        string("Hello"),
+                        number(-300.0),
+                        string("World"),
+                        number(50.0),
+                        string("!"),
+                    ],
+                    0,
+                ))],
+            ),
+        ],
+        &mut state,
+    );
+
+    assert_eq!(text, "Hello World!");
+}
+
+#[test]
+fn line_moves_insert_newline() {
+    let mut state = state(10.0);
+    let text = extract(
+        vec![
+            operation("BT", vec![]),
+            operation("TD", vec![number(72.0), number(-12.0)]),
+            operation("Tj", vec![string("first")]),
+            operation("T*", vec![]),
+            operation("Tj", vec![string("second")]),
+            operation("'", vec![string("third")]),
+        ],
+        &mut state,
+    );
+
+    assert_eq!(text, "first\nsecond\nthird");
+    assert_eq!(state.text.leading, 12.0);
+}
+
+fn vertical_char(text: &str, x: f64, y: f64) -> TextChar {
+    TextChar {
+        text: String::from(text),
+        x,
+        y,
+        end_x: x,
+        end_y: y - 10.0,
+        font_size: 10.0,
+        is_vertical: true,
+        font_name: String::new(),
+        fill_color: graphics::Color::default(),
+        render_mode: 0,
+        bbox: [x - 5.0, y - 10.0, x + 5.0, y],
+    }
+}
+
+#[test]
+fn vertical_columns_right_to_left() {
+    // 左の列を先に描いていても，右の列から上から下へ読む
+    let chars = vec![
+        vertical_char("ふ", 100.0, 700.0),
+        vertical_char("た", 100.0, 690.0),
+        vertical_char("い", 120.0, 690.0),
+        vertical_char("ち", 120.0, 700.0),
+    ];
+
+    assert_eq!(chars_to_text(&chars), "ちい\nふた");
+}
+
+#[test]
+fn group_words_and_lines() {
+    let font = font::Font::default();
+    let mut state = state(10.0);
+    let mut extractor = TextExtractor::new();
+
+    extractor.process(&operation("BT", vec![]), &mut state, &font);
+    extractor.process(
+        &operation("Td", vec![number(100.0), number(700.0)]),
+        &mut state,
+        &font,
+    );
+    extractor.process(&operation("Tj", vec![string("ab cd")]), &mut state, &font);
+    state.fill_color = graphics::Color {
+        color_space: String::from("DeviceRGB"),
+        components: vec![1.0, 0.0, 0.0],
+    };
+    extractor.process(
+        &operation("Td", vec![number(0.0), number(-12.0)]),
+        &mut state,
+        &font,
+    );
+    extractor.process(&operation("Tj", vec![string("ef")]), &mut state, &font);
+
+    let lines = group_lines(&extractor.into_chars());
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "ab cd");
+    let words: Vec<&str> = lines[0].words.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(words, ["ab", "cd"]);
+    // 空白の文字の分だけ離れて次の語が始まる
+    assert_eq!(lines[0].words[0].bbox, [100.0, 698.0, 110.0, 708.0]);
+    assert_eq!(lines[0].words[1].bbox, [115.0, 698.0, 125.0, 708.0]);
+    assert_eq!(lines[0].bbox, [100.0, 698.0, 125.0, 708.0]);
+
+    assert_eq!(lines[1].text, "ef");
+    assert_eq!(lines[1].words[0].fill_color.components, [1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn reuse_fonts_and_fall_back_on_broken_font() {
+    let (mut file, xref) = test_util::write_pdf(&[
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_vec(),
+        b"<< /Type /Font /Subtype /TrueType /BaseFont /Broken /Widths /Bad >>".to_vec(),
+    ]);
+    let resources = test_util::parse("<< /Font << /F1 1 0 R /F2 2 0 R /F3 1 0 R >> >>");
+    let resources = object::PdfDict::ensure_with_key(&resources, vec![]).unwrap();
+    let mut extractor = TextExtractor::new();
+    let mut state = state(10.0);
+
+    // 同じフォントは一度だけ読み，別の名前で参照されていても共有する
+    let font = extractor
+        .get_font(&state, Some(resources), &mut file, &xref)
+        .unwrap();
+    assert_eq!(font.base_font(), "Helvetica");
+    assert!(Rc::ptr_eq(
+        &font,
+        &extractor
+            .get_font(&state, Some(resources), &mut file, &xref)
+            .unwrap()
+    ));
+    state.text.font_name = Some(String::from("F3"));
+    assert!(Rc::ptr_eq(
+        &font,
+        &extractor
+            .get_font(&state, Some(resources), &mut file, &xref)
+            .unwrap()
+    ));
+
+    // 読めないフォントの文字も既定のフォントで取り出す
+    state.text.font_name = Some(String::from("F2"));
+    extractor
+        .handle(
+            &operation("Tj", vec![string("ab")]),
+            &mut state,
+            Some(resources),
+            &mut file,
+            &xref,
+        )
+        .unwrap();
+
+    let chars = extractor.into_chars();
+    let texts: Vec<&str> = chars.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(texts, ["a", "b"]);
+    assert_eq!(chars[1].x, 5.0);
+}
@@ -10,7 +10,6 @@ pub mod error;
 
 pub struct Trailer {
     pub xref_start_offset: u64,
-    xref_entry_num: usize,
     root_catalog_ref: object::PdfIndirectRef,
 }
 
@@ -34,9 +33,7 @@ pub fn parse_trailer(file: &mut File, filesize: u64) -> Result<Trailer, error::E
 
     let may_trailer_dict = parse_trailer_dict(buffer, byte_offset)?;
     let trailer_dict = object::PdfDict::ensure_with_key(&may_trailer_dict, vec!["Size", "Root"])?;
-    let xref_entry_num = object::PdfInteger::ensure(trailer_dict.get("Size").unwrap())?;
-    xref_entry_num.assert_natural()?;
-    let xref_entry_num = xref_entry_num.unpack() as usize;
+    object::PdfInteger::ensure(trailer_dict.get("Size").unwrap())?.assert_natural()?;
 
     let root_catalog_ref =
         object::PdfIndirectRef::ensure(trailer_dict.get("Root").unwrap())?.clone();
@@ -45,7 +42,6 @@ pub fn parse_trailer(file: &mut File, filesize: u64) -> Result<Trailer, error::E
 
     Ok(Trailer {
         xref_start_offset,
-        xref_entry_num,
         root_catalog_ref,
     })
 }